@@ -0,0 +1,380 @@
+//! Move sequences as group elements: inverses, mirrors, rotations, orders,
+//! effect comparison, and commutator and conjugate notation; and the metrics
+//! move counts are given in.
+
+use std::fmt;
+use std::str::FromStr;
+
+use crate::rubik::{CubeState, Move};
+
+/// A way of counting moves.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum Metric {
+    /// Half-turn metric (also called outer block turn metric): any turn of
+    /// an outer face or block counts 1, a slice turn 2 (one for each outer
+    /// layer it stands for), and rotations 0.
+    Htm,
+    /// Quarter-turn metric: like HTM, but half turns count twice.
+    Qtm,
+    /// Slice-turn metric: any turn of a face, block or slice counts 1, and
+    /// rotations 0.
+    Stm,
+    /// Execution-turn metric: every move counts 1, rotations included.
+    Etm,
+}
+
+impl Metric {
+    pub const ALL: [Metric; 4] = [Metric::Htm, Metric::Qtm, Metric::Stm, Metric::Etm];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Metric::Htm => "HTM",
+            Metric::Qtm => "QTM",
+            Metric::Stm => "STM",
+            Metric::Etm => "ETM",
+        }
+    }
+
+    /// What a single move counts.
+    pub fn cost(&self, mv: Move) -> usize {
+        let slice = mv.is_slice();
+        match self {
+            Metric::Etm => 1,
+            _ if mv.is_rotation() => 0,
+            Metric::Stm => 1,
+            Metric::Htm => if slice { 2 } else { 1 },
+            Metric::Qtm => {
+                let quarters = if mv.amount() == 2 { 2 } else { 1 };
+                if slice { 2 * quarters } else { quarters }
+            }
+        }
+    }
+
+    /// The length of a sequence.
+    pub fn count(&self, moves: &[Move]) -> usize {
+        moves.iter().map(|&mv| self.cost(mv)).sum()
+    }
+}
+
+impl FromStr for Metric {
+    type Err = String;
+
+    /// Parse a metric name such as `HTM` or `qtm`. `OBTM` is taken as HTM.
+    fn from_str(input: &str) -> Result<Metric, String> {
+        match input.to_ascii_uppercase().as_str() {
+            "OBTM" => Ok(Metric::Htm),
+            name => Metric::ALL.into_iter().find(|m| m.name() == name).ok_or(format!("Unknown metric {}", input)),
+        }
+    }
+}
+
+impl fmt::Display for Metric {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// A sequence of moves.
+///
+/// Parsing accepts commutators `[A, B]` (meaning `A B A' B'`), conjugates
+/// `[A: B]` (meaning `A B A'`) and groups `(A)`, optionally repeated as in
+/// `(R U R' U')3`, nested freely. They are expanded into plain moves.
+#[derive(Clone, PartialEq, Eq, Hash, Default, Debug)]
+pub struct Algorithm {
+    moves: Vec<Move>,
+}
+
+impl Algorithm {
+    pub fn new(moves: Vec<Move>) -> Algorithm {
+        Algorithm { moves }
+    }
+
+    pub fn moves(&self) -> &[Move] {
+        &self.moves
+    }
+
+    pub fn len(&self) -> usize {
+        self.moves.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.moves.is_empty()
+    }
+
+    /// The length of the algorithm in a metric.
+    pub fn count(&self, metric: Metric) -> usize {
+        metric.count(&self.moves)
+    }
+
+    /// `A B A' B'`.
+    pub fn commutator(a: &Algorithm, b: &Algorithm) -> Algorithm {
+        Algorithm::new([a.moves(), b.moves(), a.inverse().moves(), b.inverse().moves()].concat())
+    }
+
+    /// `A B A'`.
+    pub fn conjugate(a: &Algorithm, b: &Algorithm) -> Algorithm {
+        Algorithm::new([a.moves(), b.moves(), a.inverse().moves()].concat())
+    }
+
+    /// This algorithm followed by another.
+    pub fn then(&self, other: &Algorithm) -> Algorithm {
+        Algorithm::new([self.moves(), other.moves()].concat())
+    }
+
+    /// This algorithm done `times` times in a row.
+    pub fn repeat(&self, times: usize) -> Algorithm {
+        Algorithm::new(self.moves.repeat(times))
+    }
+
+    /// The algorithm undoing this one.
+    pub fn inverse(&self) -> Algorithm {
+        Algorithm::new(Move::invert_sequence(&self.moves))
+    }
+
+    /// The algorithm seen in a mirror perpendicular to an axis; see
+    /// [`Move::mirror`]. Mirroring across axis 0 turns right-handed
+    /// algorithms into left-handed ones.
+    pub fn mirror(&self, axis: usize) -> Algorithm {
+        Algorithm::new(self.moves.iter().map(|mv| mv.mirror(axis)).collect())
+    }
+
+    /// The algorithm with the same effect as `[rotation: self]`, without the
+    /// rotation; see [`Move::rotate`].
+    ///
+    /// Panics if `rotation` isn't a rotation.
+    pub fn rotate(&self, rotation: Move) -> Algorithm {
+        Algorithm::new(self.moves.iter().map(|mv| mv.rotate(rotation)).collect())
+    }
+
+    /// Merge and cancel moves; see [`Move::simplify`].
+    pub fn simplify(&self) -> Algorithm {
+        Algorithm::new(Move::simplify(&self.moves))
+    }
+
+    /// The facelet each facelet ends up at.
+    pub fn permutation(&self) -> [usize; 54] {
+        CubeState::facelet_permutation(self.moves.iter().copied())
+    }
+
+    /// Whether two algorithms do the same to every sticker, including the
+    /// centers.
+    pub fn same_effect(&self, other: &Algorithm) -> bool {
+        self.permutation() == other.permutation()
+    }
+
+    /// How many times the algorithm has to be repeated to get back to where
+    /// it started: the least common multiple of its sticker cycle lengths.
+    pub fn order(&self) -> usize {
+        let perm = self.permutation();
+        let mut seen = [false; 54];
+        let mut order = 1;
+        for start in 0..54 {
+            let mut length = 0;
+            let mut f = start;
+            while !seen[f] {
+                seen[f] = true;
+                f = perm[f];
+                length += 1;
+            }
+            if length > 0 {
+                order = lcm(order, length);
+            }
+        }
+        order
+    }
+}
+
+fn lcm(a: usize, b: usize) -> usize {
+    let (mut x, mut y) = (a, b);
+    while y != 0 {
+        (x, y) = (y, x % y);
+    }
+    a / x * b
+}
+
+impl From<Vec<Move>> for Algorithm {
+    fn from(moves: Vec<Move>) -> Self {
+        Algorithm::new(moves)
+    }
+}
+
+impl fmt::Display for Algorithm {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&Move::to_sequence(&self.moves))
+    }
+}
+
+/// Split notation into moves, repetition counts and the characters
+/// `[`, `]`, `,`, `:`, `(` and `)`.
+fn tokens(input: &str) -> Vec<&str> {
+    let mut out = vec![];
+    let mut start = None;
+    for (i, c) in input.char_indices() {
+        if c.is_whitespace() || "[],:()".contains(c) {
+            if let Some(s) = start.take() {
+                out.push(&input[s..i]);
+            }
+            if !c.is_whitespace() {
+                out.push(&input[i..i + 1]);
+            }
+        } else if start.is_none() {
+            start = Some(i);
+        }
+    }
+    if let Some(s) = start {
+        out.push(&input[s..]);
+    }
+    out
+}
+
+/// Parse moves up to a token that doesn't belong to a sequence, which is
+/// left in place.
+fn parse_sequence(tokens: &[&str], pos: &mut usize) -> Result<Vec<Move>, String> {
+    let mut moves = vec![];
+    while let Some(&token) = tokens.get(*pos) {
+        match token {
+            "]" | "," | ":" | ")" => break,
+            "[" => {
+                *pos += 1;
+                let a = Algorithm::new(parse_sequence(tokens, pos)?);
+                let separator = tokens.get(*pos).copied();
+                if separator != Some(",") && separator != Some(":") {
+                    return Err("Expected , or : inside brackets".to_string());
+                }
+                *pos += 1;
+                let b = Algorithm::new(parse_sequence(tokens, pos)?);
+                if tokens.get(*pos) != Some(&"]") {
+                    return Err("Missing ]".to_string());
+                }
+                *pos += 1;
+                let expanded = if separator == Some(",") {
+                    Algorithm::commutator(&a, &b)
+                } else {
+                    Algorithm::conjugate(&a, &b)
+                };
+                moves.extend(expanded.moves);
+            }
+            "(" => {
+                *pos += 1;
+                let group = parse_sequence(tokens, pos)?;
+                if tokens.get(*pos) != Some(&")") {
+                    return Err("Missing )".to_string());
+                }
+                *pos += 1;
+                let times = match tokens.get(*pos).and_then(|t| t.parse::<usize>().ok()) {
+                    Some(times) => {
+                        *pos += 1;
+                        times
+                    }
+                    None => 1,
+                };
+                moves.extend(group.repeat(times));
+            }
+            _ => {
+                moves.push(token.parse()?);
+                *pos += 1;
+            }
+        }
+    }
+    Ok(moves)
+}
+
+impl FromStr for Algorithm {
+    type Err = String;
+
+    fn from_str(input: &str) -> Result<Algorithm, String> {
+        let tokens = tokens(input);
+        let mut pos = 0;
+        let moves = parse_sequence(&tokens, &mut pos)?;
+        match tokens.get(pos) {
+            Some(token) => Err(format!("Unexpected {}", token)),
+            None => Ok(Algorithm::new(moves)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn alg(notation: &str) -> Algorithm {
+        notation.parse().unwrap()
+    }
+
+    #[test]
+    fn metrics() {
+        let counts = |notation: &str| Metric::ALL.map(|metric| alg(notation).count(metric));
+        assert_eq!(counts("M2 U M2"), [5, 9, 3, 3]);
+        assert_eq!(counts("x R2 y"), [1, 2, 1, 3]);
+        assert_eq!(counts("Rw' E2 S"), [5, 7, 3, 3]);
+        assert_eq!(Metric::ALL.map(|metric| metric.to_string().parse()), Metric::ALL.map(Ok));
+        assert_eq!("obtm".parse(), Ok(Metric::Htm));
+        assert!("HTMX".parse::<Metric>().is_err());
+    }
+
+    #[test]
+    fn notation() {
+        assert_eq!(alg("[R, U]").to_string(), "R U R' U'");
+        assert_eq!(alg("[R: U]").to_string(), "R U R'");
+        assert_eq!(alg("(R U)3 F").to_string(), "R U R U R U F");
+        assert_eq!(alg("(R U) F").to_string(), "R U F");
+        assert_eq!(alg("[F: [R, U]]").to_string(), "F R U R' U' F'");
+        assert_eq!(alg("[R U R', D]"), alg("R U R' D R U' R' D'"));
+        assert_eq!(alg("F ( [R,U] )2"), alg("F R U R' U' R U R' U'"));
+        for (invalid, error) in [
+            ("[R U]", "Expected , or : inside brackets"),
+            ("[R, U", "Missing ]"),
+            ("(R U", "Missing )"),
+            ("R U]", "Unexpected ]"),
+            ("R, U", "Unexpected ,"),
+        ] {
+            assert_eq!(invalid.parse::<Algorithm>(), Err(error.to_string()), "{}", invalid);
+        }
+        assert!("R Q".parse::<Algorithm>().is_err());
+    }
+
+    #[test]
+    fn inverse() {
+        assert_eq!(alg("R U2 F'").inverse(), alg("F U2 R'"));
+        assert!(alg("R U R' F").then(&alg("R U R' F").inverse()).simplify().is_empty());
+    }
+
+    #[test]
+    fn mirror() {
+        assert_eq!(alg("R U R' F").mirror(0), alg("L' U' L F'"));
+        let sune = alg("R U R' U R U2 R'");
+        for axis in 0..3 {
+            assert_eq!(sune.mirror(axis).mirror(axis), sune);
+            assert_eq!(sune.mirror(axis).order(), sune.order());
+        }
+    }
+
+    #[test]
+    fn rotate() {
+        assert_eq!(alg("R").rotate(Move::Y), alg("B"));
+        let sune = alg("R U R' U R U2 R'");
+        for rotation in [Move::X, Move::YP, Move::Z2] {
+            let conjugated = Algorithm::conjugate(&Algorithm::new(vec![rotation]), &sune);
+            assert!(sune.rotate(rotation).same_effect(&conjugated));
+        }
+    }
+
+    #[test]
+    fn effects() {
+        assert!(alg("R U R' U'").repeat(6).same_effect(&Algorithm::default()));
+        assert!(alg("M2 U M2 U2 M2 U M2").same_effect(&alg("M2 U' M2 U2 M2 U' M2")));
+        assert!(!alg("R").same_effect(&alg("R'")));
+        assert!(alg("x").same_effect(&alg("R M' L'")));
+        assert!(!alg("x").same_effect(&alg("R L'")));
+    }
+
+    #[test]
+    fn order() {
+        assert_eq!(alg("[R, U]").order(), 6);
+        assert_eq!(alg("R U").order(), 105);
+        assert_eq!(alg("R").order(), 4);
+        assert_eq!(alg("R2").order(), 2);
+        assert_eq!(Algorithm::default().order(), 1);
+        assert_eq!(alg("R U R' U R U2 R'").order(), 6);
+    }
+}
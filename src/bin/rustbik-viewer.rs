@@ -0,0 +1,84 @@
+use rustbik::bld::{EdgeMethod, Execution, LetterScheme};
+use rustbik::clock::FixedStep;
+use rustbik::library::CaseSet;
+use rustbik::native::{run_viewer, ViewerOptions};
+use rustbik::rubik::{CubeState, Move};
+use rustbik::scramble::Rng;
+use rustbik::reconstruction::{Reconstruction, Site};
+use rustbik::share::Shared;
+use rustbik::trainer::Trainer;
+
+const USAGE: &str = "Usage: rustbik-viewer [--state <facelets>] [--moves <sequence>] [--move-time <ms>] [--still]
+                      [--fixed-step <ms>]
+                      [--link <url or query>] [--blind op|m2]
+                      [--train f2l|oll|pll [--cases <name>,...]]";
+
+fn parse_args() -> Result<ViewerOptions, String> {
+    let mut opts = ViewerOptions::default();
+    let mut train = None;
+    let mut cases = String::new();
+    let mut blind: Option<EdgeMethod> = None;
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().ok_or(format!("Missing value for {}", arg));
+        match arg.as_str() {
+            "--state" => opts.state = CubeState::from_facelet_str(&value()?)?,
+            "--moves" => opts.moves = Move::from_sequence(&value()?)?.collect(),
+            "--move-time" => {
+                opts.animation.move_time = value()?.parse().map_err(|e| format!("Invalid move time: {}", e))?
+            }
+            "--still" => opts.auto_rotate = false,
+            "--fixed-step" => {
+                let step: f64 = value()?.parse().map_err(|e| format!("Invalid fixed step: {}", e))?;
+                if !(step.is_finite() && step > 0.0) {
+                    return Err(format!("Invalid fixed step: {}", step));
+                }
+                opts.clock = Box::new(FixedStep::new(step));
+            }
+            "--link" => {
+                let link = value()?;
+                if Site::of_url(&link).is_some() {
+                    let reconstruction = Reconstruction::from_url(&link)?;
+                    opts.state = CubeState::solved();
+                    opts.state.apply_all(reconstruction.setup.iter().copied());
+                    opts.moves = reconstruction.moves();
+                } else {
+                    let shared = Shared::from_query(&link)?;
+                    opts.state = shared.position();
+                    opts.moves = shared.alg;
+                }
+            }
+            "--train" => {
+                let set = value()?;
+                train = Some(CaseSet::ALL.into_iter()
+                    .find(|s| s.name().eq_ignore_ascii_case(&set))
+                    .ok_or(format!("Unknown case set {}", set))?);
+            }
+            "--cases" => cases = value()?,
+            "--blind" => blind = Some(value()?.parse()?),
+            "-h" | "--help" => return Err(USAGE.to_string()),
+            _ => return Err(format!("Unknown argument {}\n{}", arg, USAGE)),
+        }
+    }
+    if let Some(method) = blind {
+        // Play the blindfolded solution of the state the other options lead to.
+        let mut state = opts.state.clone();
+        state.apply_all(opts.moves.iter().copied());
+        opts.state = state.clone();
+        opts.moves = Execution::new(&state, &LetterScheme::speffz(), method)?.moves();
+    }
+    if let Some(set) = train {
+        let names: Vec<&str> = cases.split(',').map(str::trim).filter(|name| !name.is_empty()).collect();
+        opts.trainer = Some(Trainer::new(set, &names, Rng::from_time())?);
+    } else if !cases.is_empty() {
+        return Err("--cases needs --train".to_string());
+    }
+    Ok(opts)
+}
+
+fn main() {
+    if let Err(e) = parse_args().and_then(run_viewer) {
+        eprintln!("{}", e);
+        std::process::exit(1);
+    }
+}
@@ -0,0 +1,444 @@
+use std::io::BufRead;
+
+use serde_json::{json, Value};
+
+use rustbik::rubik::{CubeState, CubieCube, Move};
+use rustbik::scramble::{random_state_scramble, Rng};
+use rustbik::bld::{EdgeMethod, Execution, LetterScheme};
+use rustbik::cfop::{Cfop, TimedMove};
+use rustbik::fmc::{find_insertions, Leftover, Niss};
+use rustbik::library::find_case;
+use rustbik::reconstruction::Reconstruction;
+use rustbik::solver::generate::{ergonomics, generate, GenerateOptions, Generators, Goal};
+use rustbik::solver::method::{solve_step, Step};
+use rustbik::solver::reduction::{self, Analysis, AXIS_NAMES, STEP_LENGTHS};
+use rustbik::solver::{self, optimal::{self, Progress}};
+
+const USAGE: &str = "Usage: rustbik <command> [options] [input]
+
+Commands:
+  scramble [--count <n>] [--seed <n>]     Random-state scrambles
+  solve [--optimal] <facelets>             Solve a state, optimally in face turns
+                                           with --optimal (slow for long solutions)
+  apply [--state <facelets>] <moves>       Apply moves to a state (solved by default)
+  convert --from <format> --to <format> <facelets>
+                                           Convert a state between formats
+  generate [--gen <moves>] [--max-length <n>] [--count <n>] <case or facelets>
+                                           Algorithms for a library case (such as
+                                           `OLL 27`, `T` or `PLL H`), or solving a
+                                           state, using only some moves (<R,U> by
+                                           default)
+  blind [--edges op|m2] <facelets>         Blindfolded memo in Speffz letters and
+                                           its execution, with Old Pochmann corners
+                                           and Old Pochmann (default) or M2 edges
+  fmc --scramble <moves> [--count <n>] <moves>
+                                           What a partial solution, with inverse
+                                           moves in parentheses, leaves unsolved
+                                           and commutators to insert to finish it
+  reduce [--count <n>] <facelets>          Bad edges and corners on each axis, and
+                                           the next of the EO, DR and HTR steps
+  step --step <step> [--count <n>] <moves> Shortest ways to do a Roux (fb, sb, cmll,
+                                           lse), ZZ (eoline, eocross) or Petrus
+                                           (2x2x2, 2x2x3) step after some moves,
+                                           holding the cube any way for a first step
+  splits --scramble <moves> <timed moves>  Split a solve, with moves timed in ms as
+                                           in `R@0 U'@180`, into CFOP steps
+  invert <moves>                           Undo a sequence
+  simplify <moves>                         Merge and cancel moves
+
+Formats are `colors` (54 color letters, B Y R W G O, in L U F D R B face order)
+and `kociemba` (54 face letters in U R F D L B face order).
+Without an input argument, each line of stdin is an input.
+
+Options:
+  --json    Print a JSON object per result instead of text";
+
+/// Most commutators `fmc` inserts into a skeleton.
+const MAX_INSERTIONS: usize = 3;
+
+/// Every position can be solved in 20 face turns.
+const MAX_OPTIMAL_LENGTH: usize = 20;
+
+const COMMANDS: [&str; 12] = [
+    "scramble", "solve", "apply", "convert", "generate", "blind", "fmc", "reduce", "step", "splits", "invert",
+    "simplify",
+];
+
+#[derive(Clone, Copy, PartialEq)]
+enum Format {
+    Colors,
+    Kociemba,
+}
+
+impl Format {
+    fn parse(name: &str) -> Result<Format, String> {
+        match name {
+            "colors" => Ok(Format::Colors),
+            "kociemba" => Ok(Format::Kociemba),
+            _ => Err(format!("Unknown format {}", name)),
+        }
+    }
+
+    fn read(&self, input: &str) -> Result<CubeState, String> {
+        match self {
+            Format::Colors => CubeState::from_facelet_str(input),
+            Format::Kociemba => CubeState::from_kociemba_str(input),
+        }
+    }
+
+    fn write(&self, state: &CubeState) -> Result<String, String> {
+        match self {
+            Format::Colors => Ok(state.to_facelet_string()),
+            Format::Kociemba => state.to_kociemba_string(),
+        }
+    }
+}
+
+struct Args {
+    command: String,
+    json: bool,
+    count: Option<usize>,
+    generators: Generators,
+    max_length: usize,
+    seed: Option<u64>,
+    optimal: bool,
+    edges: EdgeMethod,
+    state: Option<String>,
+    scramble: Option<String>,
+    step: Option<Step>,
+    from: Format,
+    to: Format,
+    input: Vec<String>,
+}
+
+fn parse_args() -> Result<Args, String> {
+    let mut args = std::env::args().skip(1);
+    let command = args.next().ok_or(USAGE.to_string())?;
+    if command == "-h" || command == "--help" {
+        return Err(USAGE.to_string());
+    }
+    if !COMMANDS.contains(&command.as_str()) {
+        return Err(format!("Unknown command {}\n{}", command, USAGE));
+    }
+    let mut out = Args {
+        command,
+        json: false,
+        count: None,
+        generators: GenerateOptions::default().generators,
+        max_length: 10,
+        seed: None,
+        optimal: false,
+        edges: EdgeMethod::OldPochmann,
+        state: None,
+        scramble: None,
+        step: None,
+        from: Format::Colors,
+        to: Format::Colors,
+        input: vec![],
+    };
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().ok_or(format!("Missing value for {}", arg));
+        match arg.as_str() {
+            "--json" => out.json = true,
+            "--count" => out.count = Some(value()?.parse().map_err(|e| format!("Invalid count: {}", e))?),
+            "--gen" => out.generators = value()?.parse()?,
+            "--max-length" => {
+                out.max_length = value()?.parse().map_err(|e| format!("Invalid length: {}", e))?
+            }
+            "--seed" => out.seed = Some(value()?.parse().map_err(|e| format!("Invalid seed: {}", e))?),
+            "--optimal" => out.optimal = true,
+            "--edges" => out.edges = value()?.parse()?,
+            "--state" => out.state = Some(value()?),
+            "--scramble" => out.scramble = Some(value()?),
+            "--step" => out.step = Some(value()?.parse()?),
+            "--from" => out.from = Format::parse(&value()?)?,
+            "--to" => out.to = Format::parse(&value()?)?,
+            "-h" | "--help" => return Err(USAGE.to_string()),
+            _ if arg.starts_with("--") => return Err(format!("Unknown argument {}\n{}", arg, USAGE)),
+            _ => out.input.push(arg),
+        }
+    }
+    Ok(out)
+}
+
+/// The inputs to work on: the positional arguments joined into one, or
+/// every non-empty line of stdin.
+fn inputs(args: &Args) -> Result<Vec<String>, String> {
+    if !args.input.is_empty() {
+        return Ok(vec![args.input.join(" ")]);
+    }
+    let mut lines = vec![];
+    for line in std::io::stdin().lock().lines() {
+        let line = line.map_err(|e| e.to_string())?;
+        if !line.trim().is_empty() {
+            lines.push(line.trim().to_string());
+        }
+    }
+    Ok(lines)
+}
+
+fn parse_moves(input: &str) -> Result<Vec<Move>, String> {
+    Ok(Move::from_sequence(input)?.collect())
+}
+
+/// Solve optimally, reporting progress on stderr.
+fn solve_optimal(state: &CubeState) -> Result<Vec<Move>, String> {
+    let cube = CubieCube::from_state(state)?;
+    let mut searching = None;
+    let solution = optimal::solve(&cube, MAX_OPTIMAL_LENGTH, |progress| {
+        match progress {
+            Progress::Building { table, depth, filled, total } => {
+                eprintln!("Building {} table: depth {}, {}/{}", table, depth, filled, total)
+            }
+            Progress::Searching { depth, .. } if searching != Some(depth) => {
+                searching = Some(depth);
+                eprintln!("Searching depth {}", depth)
+            }
+            Progress::Searching { .. } => {}
+        }
+        true
+    });
+    let solution = solution.map_err(|_| "The search was cancelled".to_string())?;
+    solution.ok_or(format!("No solution within {} moves", MAX_OPTIMAL_LENGTH))
+}
+
+/// Run one input through a command, returning the text and JSON results.
+fn run_one(args: &Args, input: &str) -> Result<(String, Value), String> {
+    match args.command.as_str() {
+        "solve" => {
+            let state = args.from.read(input)?;
+            let solution = if args.optimal {
+                solve_optimal(&state)?
+            } else {
+                solver::solve(&state)?
+            };
+            let text = Move::to_sequence(&solution);
+            Ok((text.clone(), json!({ "state": input, "solution": text, "length": solution.len() })))
+        }
+        "apply" => {
+            let mut state = match &args.state {
+                Some(s) => args.from.read(s)?,
+                None => CubeState::solved(),
+            };
+            state.apply_all(parse_moves(input)?);
+            let text = args.to.write(&state)?;
+            Ok((text.clone(), json!({ "moves": input, "state": text, "solved": state.is_solved() })))
+        }
+        "convert" => {
+            let text = args.to.write(&args.from.read(input)?)?;
+            Ok((text.clone(), json!({ "input": input, "output": text })))
+        }
+        "generate" => {
+            let case = find_case(input)?;
+            let (start, goal) = match case {
+                Some(case) => (case.setup_state(), Goal::Step(case.set())),
+                None => (args.from.read(input)?, Goal::State(CubeState::solved())),
+            };
+            let options = GenerateOptions {
+                generators: args.generators.clone(),
+                max_length: args.max_length,
+                pre_auf: case.is_some(),
+                max_results: args.count.unwrap_or(20),
+            };
+            let found = generate(&start, &goal, &options, ergonomics);
+            // Written with the AUFs, so every line can be done as it is.
+            let algorithms: Vec<String> = found.iter().map(|alg| {
+                let moves: Vec<Move> = alg.pre_auf.into_iter().chain(alg.moves.iter().copied()).chain(alg.post_auf).collect();
+                Move::to_sequence(&moves)
+            }).collect();
+            let values: Vec<Value> = found.iter().zip(&algorithms).map(|(alg, text)| json!({
+                "algorithm": text,
+                "length": alg.moves.len(),
+                "score": alg.score,
+            })).collect();
+            Ok((algorithms.join("\n"), json!({ "input": input, "generators": args.generators.to_string(), "algorithms": values })))
+        }
+        "blind" => {
+            let execution = Execution::new(&args.from.read(input)?, &LetterScheme::speffz(), args.edges)?;
+            let memo = &execution.memo;
+            let letters = |targets: &[rustbik::bld::Target]| targets.iter().map(|t| t.letter).collect::<String>();
+            let solution = Reconstruction { steps: execution.steps.clone(), ..Default::default() }.steps_text();
+            let value = json!({
+                "state": input,
+                "edges": letters(&memo.edges),
+                "corners": letters(&memo.corners),
+                "parity": memo.parity(),
+                "solution": Move::to_sequence(&execution.moves()),
+            });
+            Ok((format!("{}\n\n{}", memo, solution), value))
+        }
+        "fmc" => {
+            let scramble = parse_moves(args.scramble.as_deref().ok_or("fmc needs --scramble")?)?;
+            let niss: Niss = input.parse()?;
+            let skeleton = niss.solution();
+            let leftover = Leftover::new(&scramble, &skeleton)?;
+            let insertions = if leftover.is_solved() {
+                vec![]
+            } else {
+                find_insertions(&scramble, &skeleton, MAX_INSERTIONS, args.count.unwrap_or(5))?
+            };
+            let mut text = format!("{}\n{}", Move::to_sequence(&skeleton), leftover);
+            for insertion in &insertions {
+                text += &format!(
+                    "\n{} ({} moves, {} cancelled)",
+                    Move::to_sequence(&insertion.moves),
+                    insertion.moves.len(),
+                    insertion.cancelled
+                );
+            }
+            let values: Vec<Value> = insertions.iter().map(|insertion| json!({
+                "solution": Move::to_sequence(&insertion.moves),
+                "length": insertion.moves.len(),
+                "positions": insertion.positions,
+                "commutators": insertion.commutators.iter().map(|c| Move::to_sequence(c)).collect::<Vec<_>>(),
+                "cancelled": insertion.cancelled,
+            })).collect();
+            let value = json!({
+                "moves": input,
+                "skeleton": Move::to_sequence(&skeleton),
+                "unsolved_corners": leftover.unsolved_corners(),
+                "unsolved_edges": leftover.unsolved_edges(),
+                "insertions": values,
+            });
+            Ok((text.trim_end().to_string(), value))
+        }
+        "reduce" => {
+            let cube = CubieCube::from_state(&args.from.read(input)?)?;
+            let analysis = Analysis::new(&cube);
+            let count = args.count.unwrap_or(3);
+            let [eo_length, dr_length, htr_length] = STEP_LENGTHS;
+            // The next step: HTR from DR, DR from EO, or EO.
+            let eo_axes: Vec<usize> = (0..3).filter(|&axis| analysis.bad_edges[axis] == 0).collect();
+            let steps: Vec<(String, Vec<Move>)> = if analysis.htr {
+                vec![]
+            } else if analysis.dr.contains(&true) {
+                let found = reduction::solve_htr(&cube, htr_length, count)?;
+                found.into_iter().map(|moves| ("HTR".to_string(), moves)).collect()
+            } else if !eo_axes.is_empty() {
+                (0..3).filter(|axis| !eo_axes.contains(axis) || eo_axes.len() > 1).flat_map(|axis| {
+                    let found = reduction::solve_dr(&cube, axis, dr_length, count);
+                    found.into_iter().map(move |moves| (format!("DR {}", AXIS_NAMES[axis]), moves))
+                }).collect()
+            } else {
+                (0..3).flat_map(|axis| {
+                    let found = reduction::solve_eo(&cube, axis, eo_length, count);
+                    found.into_iter().map(move |moves| (format!("EO {}", AXIS_NAMES[axis]), moves))
+                }).collect()
+            };
+            let mut text = analysis.to_string();
+            for (step, moves) in &steps {
+                text += &format!("{}: {} ({})\n", step, Move::to_sequence(moves), moves.len());
+            }
+            let values: Vec<Value> = steps.iter().map(|(step, moves)| json!({
+                "step": step,
+                "moves": Move::to_sequence(moves),
+                "length": moves.len(),
+            })).collect();
+            let value = json!({
+                "state": input,
+                "bad_edges": analysis.bad_edges,
+                "bad_corners": analysis.bad_corners,
+                "dr": (0..3).filter(|&axis| analysis.dr[axis]).map(|axis| AXIS_NAMES[axis]).collect::<Vec<_>>(),
+                "htr": analysis.htr,
+                "steps": values,
+            });
+            Ok((text.trim_end().to_string(), value))
+        }
+        "step" => {
+            let step = args.step.ok_or("step needs --step")?;
+            let orientations = if step.is_first() { Move::orientations().to_vec() } else { vec![vec![]] };
+            let found = solve_step(&parse_moves(input)?, step, &orientations, step.max_length(), args.count.unwrap_or(5))?;
+            let mut text = String::new();
+            for solution in &found {
+                let rotation = Move::to_sequence(&solution.rotation);
+                let moves = Move::to_sequence(&solution.moves);
+                text += &format!("{}: {} ({})\n", step, [rotation, moves].join(" ").trim(), solution.moves.len());
+            }
+            if found.is_empty() {
+                text = format!("No {} within {} moves", step, step.max_length());
+            }
+            let values: Vec<Value> = found.iter().map(|solution| json!({
+                "rotation": Move::to_sequence(&solution.rotation),
+                "moves": Move::to_sequence(&solution.moves),
+                "length": solution.moves.len(),
+            })).collect();
+            let value = json!({
+                "moves": input,
+                "step": step.name(),
+                "method": step.method(),
+                "solutions": values,
+            });
+            Ok((text.trim_end().to_string(), value))
+        }
+        "splits" => {
+            let scramble = parse_moves(args.scramble.as_deref().ok_or("splits needs --scramble")?)?;
+            let cfop = Cfop::new(&scramble, &TimedMove::parse_sequence(input)?)?;
+            let splits: Vec<Value> = cfop.splits.iter().map(|split| json!({
+                "step": split.stage.to_string(),
+                "moves": Move::to_sequence(&split.moves()),
+                "length": split.length(),
+                "time": split.time(),
+                "recognition": split.recognition(),
+                "tps": split.tps(),
+            })).collect();
+            let unfinished: Vec<Move> = cfop.unfinished.iter().map(|mv| mv.mv).collect();
+            let value = json!({
+                "moves": input,
+                "cross": cfop.cross.map(|color| color.to_char().to_string()),
+                "splits": splits,
+                "unfinished": Move::to_sequence(&unfinished),
+                "finished": cfop.is_finished(),
+                "length": cfop.length(),
+                "time": cfop.time(),
+                "tps": cfop.tps(),
+                "reconstruction": cfop.to_reconstruction(&scramble).steps_text(),
+            });
+            Ok((cfop.to_string().trim_end().to_string(), value))
+        }
+        "invert" => {
+            let text = Move::to_sequence(&Move::invert_sequence(&parse_moves(input)?));
+            Ok((text.clone(), json!({ "moves": input, "inverse": text })))
+        }
+        "simplify" => {
+            let simplified = Move::simplify(&parse_moves(input)?);
+            let text = Move::to_sequence(&simplified);
+            Ok((text.clone(), json!({ "moves": input, "simplified": text, "length": simplified.len() })))
+        }
+        _ => unreachable!("commands are checked when parsing arguments"),
+    }
+}
+
+fn run(args: Args) -> Result<(), String> {
+    if args.command == "scramble" {
+        let mut rng = args.seed.map(Rng::new).unwrap_or_else(Rng::from_time);
+        for _ in 0..args.count.unwrap_or(1) {
+            let scramble = random_state_scramble(&mut rng);
+            let text = Move::to_sequence(&scramble);
+            if args.json {
+                let mut state = CubeState::solved();
+                state.apply_all(scramble.iter().copied());
+                let value = json!({ "scramble": text, "length": scramble.len(), "state": args.to.write(&state)? });
+                println!("{}", value);
+            } else {
+                println!("{}", text);
+            }
+        }
+        return Ok(());
+    }
+    for input in inputs(&args)? {
+        let (text, value) = run_one(&args, &input)?;
+        if args.json {
+            println!("{}", value);
+        } else {
+            println!("{}", text);
+        }
+    }
+    Ok(())
+}
+
+fn main() {
+    if let Err(e) = parse_args().and_then(run) {
+        eprintln!("{}", e);
+        std::process::exit(1);
+    }
+}
@@ -0,0 +1,510 @@
+//! Blindfolded solving: letter schemes naming every sticker, memo of the
+//! cycles of a scrambled cube, and execution of the memo with Old Pochmann
+//! for corners and Old Pochmann or M2 for edges.
+//!
+//! Stickers are named by their face first, then the other faces of their
+//! piece: `UFR` is the U sticker of the up-front-right corner and `FU` the F
+//! sticker of the up-front edge.
+
+use std::fmt;
+use std::str::FromStr;
+
+use crate::reconstruction::Step;
+use crate::rubik::{corner_facelets, edge_facelets, CubeState, CubieCube, Move};
+
+/// Face letters in facelet string order.
+const FACE_NAMES: &str = "LUFDRB";
+
+/// The corner stickers, in the order letters are given to them: clockwise
+/// around each face from its top-left corner, faces in U, L, F, R, B, D
+/// order, as in Speffz.
+pub const CORNER_STICKERS: [&str; 24] = [
+    "UBL", "UBR", "UFR", "UFL", "LUB", "LUF", "LDF", "LDB", "FUL", "FUR", "FDR", "FDL",
+    "RUF", "RUB", "RDB", "RDF", "BUR", "BUL", "BDL", "BDR", "DFL", "DFR", "DBR", "DBL",
+];
+
+/// The edge stickers, in the order letters are given to them: top, right,
+/// bottom and left edge of each face, faces in U, L, F, R, B, D order.
+pub const EDGE_STICKERS: [&str; 24] = [
+    "UB", "UR", "UF", "UL", "LU", "LF", "LD", "LB", "FU", "FR", "FD", "FL",
+    "RU", "RB", "RD", "RF", "BU", "BL", "BD", "BR", "DF", "DR", "DB", "DL",
+];
+
+/// The Speffz letters of the stickers of [`CORNER_STICKERS`] or
+/// [`EDGE_STICKERS`].
+pub const SPEFFZ: &str = "ABCDEFGHIJKLMNOPQRSTUVWX";
+
+/// The facelet of a sticker named by its face, then the other faces of its
+/// piece in any order, such as `UFR` or `FU`.
+pub fn sticker_facelet(name: &str) -> Result<usize, String> {
+    let faces = name.chars()
+        .map(|c| FACE_NAMES.find(c.to_ascii_uppercase()))
+        .collect::<Option<Vec<usize>>>()
+        .ok_or(format!("Invalid sticker {}", name))?;
+    let same_piece = |facelets: &[usize]| {
+        facelets.len() == faces.len() && faces.iter().all(|&face| facelets.iter().any(|&f| f / 9 == face))
+    };
+    let piece = corner_facelets().iter().map(|c| &c[..])
+        .chain(edge_facelets().iter().map(|e| &e[..]))
+        .find(|facelets| same_piece(facelets))
+        .ok_or(format!("Invalid sticker {}", name))?;
+    Ok(*piece.iter().find(|&&f| f / 9 == faces[0]).unwrap())
+}
+
+/// The piece with a sticker, and which of the piece's stickers it is.
+fn locate(facelet: usize) -> Option<(PieceKind, usize, usize)> {
+    for (slot, facelets) in corner_facelets().iter().enumerate() {
+        if let Some(k) = facelets.iter().position(|&f| f == facelet) {
+            return Some((PieceKind::Corner, slot, k));
+        }
+    }
+    for (slot, facelets) in edge_facelets().iter().enumerate() {
+        if let Some(k) = facelets.iter().position(|&f| f == facelet) {
+            return Some((PieceKind::Edge, slot, k));
+        }
+    }
+    None
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum PieceKind {
+    Corner,
+    Edge,
+}
+
+impl PieceKind {
+    fn facelets(&self, slot: usize) -> &'static [usize] {
+        match self {
+            PieceKind::Corner => &corner_facelets()[slot],
+            PieceKind::Edge => &edge_facelets()[slot],
+        }
+    }
+}
+
+/// A letter for every corner and edge sticker.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct LetterScheme {
+    letters: [Option<char>; 54],
+}
+
+impl LetterScheme {
+    /// Letters for the stickers of [`CORNER_STICKERS`] and
+    /// [`EDGE_STICKERS`], in that order. Letters can be any characters, but
+    /// have to be different for the stickers of each kind.
+    pub fn new(corners: &str, edges: &str) -> Result<LetterScheme, String> {
+        let mut letters = [None; 54];
+        for (kind, text, names) in [("corner", corners, CORNER_STICKERS), ("edge", edges, EDGE_STICKERS)] {
+            let chars: Vec<char> = text.chars().filter(|c| !c.is_whitespace()).collect();
+            if chars.len() != 24 {
+                return Err(format!("A letter scheme needs 24 {} letters", kind));
+            }
+            for (i, &c) in chars.iter().enumerate() {
+                if chars[..i].contains(&c) {
+                    return Err(format!("The {} letter {} is used twice", kind, c));
+                }
+                letters[sticker_facelet(names[i]).unwrap()] = Some(c);
+            }
+        }
+        Ok(LetterScheme { letters })
+    }
+
+    /// The usual scheme: A to X for the corners and again for the edges.
+    pub fn speffz() -> LetterScheme {
+        LetterScheme::new(SPEFFZ, SPEFFZ).unwrap()
+    }
+
+    /// The letter of a corner or edge facelet.
+    pub fn letter(&self, facelet: usize) -> Option<char> {
+        self.letters.get(facelet).copied().flatten()
+    }
+
+    /// The facelet of a corner letter.
+    pub fn corner_facelet(&self, letter: char) -> Option<usize> {
+        corner_facelets().iter().flatten().copied().find(|&f| self.letters[f] == Some(letter))
+    }
+
+    /// The facelet of an edge letter.
+    pub fn edge_facelet(&self, letter: char) -> Option<usize> {
+        edge_facelets().iter().flatten().copied().find(|&f| self.letters[f] == Some(letter))
+    }
+}
+
+impl Default for LetterScheme {
+    fn default() -> Self {
+        LetterScheme::speffz()
+    }
+}
+
+/// A sticker in a memo.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Target {
+    pub facelet: usize,
+    pub letter: char,
+    /// Whether the target starts a new cycle, because the buffer got its own
+    /// piece back before every piece was solved.
+    pub cycle_break: bool,
+}
+
+/// The targets of a blindfolded solve: the stickers the piece in the buffer
+/// is sent to, one after another, until every piece is solved.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Memo {
+    pub corner_buffer: usize,
+    pub edge_buffer: usize,
+    pub corners: Vec<Target>,
+    pub edges: Vec<Target>,
+    /// Corners in their slot but twisted, each given by the sticker showing
+    /// its U or D color.
+    pub twisted_corners: Vec<Target>,
+    /// Edges in their slot but flipped, each given by the sticker showing
+    /// the color of the U or D face (or of F or B, for the middle layer).
+    pub flipped_edges: Vec<Target>,
+}
+
+/// Where each sticker of each slot belongs: `at[slot][k]` is the piece and
+/// sticker of that piece on the `k`th facelet of the slot.
+struct Pieces {
+    kind: PieceKind,
+    at: Vec<Vec<(usize, usize)>>,
+}
+
+impl Pieces {
+    fn new(cube: &CubieCube, kind: PieceKind) -> Pieces {
+        let at = match kind {
+            PieceKind::Corner => (0..8).map(|s| {
+                (0..3).map(|k| (cube.cp[s] as usize, (k + 3 - cube.co[s] as usize) % 3)).collect()
+            }).collect(),
+            PieceKind::Edge => (0..12).map(|s| {
+                (0..2).map(|k| (cube.ep[s] as usize, (k + cube.eo[s] as usize) % 2)).collect()
+            }).collect(),
+        };
+        Pieces { kind, at }
+    }
+
+    /// Swap the piece in the buffer with the piece of the target, so that the
+    /// buffer sticker lands on the target sticker.
+    fn shoot(&mut self, buffer: (usize, usize), target: (usize, usize)) {
+        let n = self.at[0].len();
+        for k in 0..n {
+            let a = self.at[buffer.0][(buffer.1 + k) % n];
+            let b = self.at[target.0][(target.1 + k) % n];
+            self.at[buffer.0][(buffer.1 + k) % n] = b;
+            self.at[target.0][(target.1 + k) % n] = a;
+        }
+    }
+
+    /// Follow the pieces from the buffer, shooting each to where it belongs
+    /// and breaking into a new cycle when the buffer gets its own piece
+    /// back. Pieces twisted in place are left alone unless `break_twisted`.
+    fn trace(&mut self, buffer: (usize, usize), break_twisted: bool) -> Vec<((usize, usize), bool)> {
+        let mut targets = vec![];
+        loop {
+            let (piece, sticker) = self.at[buffer.0][buffer.1];
+            let (target, cycle_break) = if piece != buffer.0 {
+                ((piece, sticker), false)
+            } else {
+                let next = (0..self.at.len()).filter(|&s| s != buffer.0).find(|&s| {
+                    let (piece, sticker) = self.at[s][0];
+                    piece != s || (break_twisted && sticker != 0)
+                });
+                match next {
+                    Some(slot) => ((slot, 0), true),
+                    None => return targets,
+                }
+            };
+            self.shoot(buffer, target);
+            targets.push((target, cycle_break));
+        }
+    }
+
+    /// Slots holding their own piece with the wrong orientation, and the
+    /// facelet showing the piece's first sticker.
+    fn misoriented(&self) -> Vec<usize> {
+        (0..self.at.len())
+            .filter(|&s| self.at[s][0].0 == s && self.at[s][0].1 != 0)
+            .map(|s| self.kind.facelets(s)[self.at[s].iter().position(|&(_, k)| k == 0).unwrap()])
+            .collect()
+    }
+}
+
+/// The slot and sticker index of a buffer facelet of a kind of piece.
+fn buffer_position(facelet: usize, kind: PieceKind) -> Result<(usize, usize), String> {
+    match locate(facelet) {
+        Some((k, slot, sticker)) if k == kind => Ok((slot, sticker)),
+        _ => Err(format!("Facelet {} isn't a {} sticker", facelet, if kind == PieceKind::Corner { "corner" } else { "edge" })),
+    }
+}
+
+fn targets(scheme: &LetterScheme, kind: PieceKind, traced: Vec<((usize, usize), bool)>) -> Vec<Target> {
+    traced.into_iter().map(|((slot, k), cycle_break)| {
+        let facelet = kind.facelets(slot)[k];
+        Target { facelet, letter: scheme.letter(facelet).unwrap(), cycle_break }
+    }).collect()
+}
+
+impl Memo {
+    /// Memo a state from the given buffer facelets (see [`sticker_facelet`]).
+    /// Cycle breaks go to the first unsolved piece in the order of the
+    /// slots of [`CubieCube`].
+    pub fn new(state: &CubeState, scheme: &LetterScheme, corner_buffer: usize, edge_buffer: usize) -> Result<Memo, String> {
+        Memo::trace(state, scheme, corner_buffer, edge_buffer, false)
+    }
+
+    fn trace(
+        state: &CubeState,
+        scheme: &LetterScheme,
+        corner_buffer: usize,
+        edge_buffer: usize,
+        break_twisted: bool,
+    ) -> Result<Memo, String> {
+        let cube = CubieCube::from_state(state)?;
+        let mut memo = Memo {
+            corner_buffer,
+            edge_buffer,
+            corners: vec![],
+            edges: vec![],
+            twisted_corners: vec![],
+            flipped_edges: vec![],
+        };
+        for kind in [PieceKind::Corner, PieceKind::Edge] {
+            let buffer = buffer_position(if kind == PieceKind::Corner { corner_buffer } else { edge_buffer }, kind)?;
+            let mut pieces = Pieces::new(&cube, kind);
+            let traced = targets(scheme, kind, pieces.trace(buffer, break_twisted));
+            let misoriented = pieces.misoriented().into_iter()
+                .map(|facelet| Target { facelet, letter: scheme.letter(facelet).unwrap(), cycle_break: false })
+                .collect();
+            match kind {
+                PieceKind::Corner => (memo.corners, memo.twisted_corners) = (traced, misoriented),
+                PieceKind::Edge => (memo.edges, memo.flipped_edges) = (traced, misoriented),
+            }
+        }
+        Ok(memo)
+    }
+
+    /// Whether there is an odd number of targets, so that the corners and
+    /// edges each end with two pieces swapped that have to be fixed together.
+    pub fn parity(&self) -> bool {
+        self.edges.len() % 2 == 1
+    }
+}
+
+/// Letters in pairs, with cycle breaks in parentheses.
+fn write_letters(f: &mut fmt::Formatter<'_>, title: &str, targets: &[Target]) -> fmt::Result {
+    if targets.is_empty() {
+        return Ok(());
+    }
+    write!(f, "{}:", title)?;
+    for (i, target) in targets.iter().enumerate() {
+        if i % 2 == 0 {
+            f.write_str(" ")?;
+        }
+        if target.cycle_break {
+            write!(f, "({})", target.letter)?;
+        } else {
+            write!(f, "{}", target.letter)?;
+        }
+    }
+    writeln!(f)
+}
+
+impl fmt::Display for Memo {
+    /// A line for each kind of target, such as `Edges: AB (C)D`, and a last
+    /// line saying whether there is parity.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write_letters(f, "Edges", &self.edges)?;
+        write_letters(f, "Flipped edges", &self.flipped_edges)?;
+        write_letters(f, "Corners", &self.corners)?;
+        write_letters(f, "Twisted corners", &self.twisted_corners)?;
+        f.write_str(if self.parity() { "Parity" } else { "No parity" })
+    }
+}
+
+/// How edges are solved. Corners are always solved with Old Pochmann.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum EdgeMethod {
+    /// Swaps of the UR buffer with UL, using a T perm.
+    OldPochmann,
+    /// Swaps of the DF buffer with UB, using M2.
+    M2,
+}
+
+impl EdgeMethod {
+    pub fn name(&self) -> &'static str {
+        match self {
+            EdgeMethod::OldPochmann => "OP",
+            EdgeMethod::M2 => "M2",
+        }
+    }
+
+    pub fn buffer(&self) -> usize {
+        match self {
+            EdgeMethod::OldPochmann => sticker_facelet("UR").unwrap(),
+            EdgeMethod::M2 => sticker_facelet("DF").unwrap(),
+        }
+    }
+}
+
+impl FromStr for EdgeMethod {
+    type Err = String;
+
+    /// Parse `OP` (or `Old Pochmann`) or `M2`, in any case.
+    fn from_str(input: &str) -> Result<EdgeMethod, String> {
+        match input.to_ascii_lowercase().replace([' ', '-'], "").as_str() {
+            "op" | "oldpochmann" => Ok(EdgeMethod::OldPochmann),
+            "m2" => Ok(EdgeMethod::M2),
+            _ => Err(format!("Unknown edge method {}", input)),
+        }
+    }
+}
+
+/// The buffer of Old Pochmann corners.
+pub fn corner_buffer() -> usize {
+    sticker_facelet("UBL").unwrap()
+}
+
+/// The Y perm swapping the UBL and RDF corners, and UL and UB.
+const OP_CORNER_SWAP: &str = "R U' R' U' R U R' F' R U R' U' R' F R";
+/// The T perm swapping UR and UL, and UFR and UBR.
+const OP_EDGE_SWAP: &str = "R U R' U' R' F R2 U' R' U' R U R' F'";
+
+/// The setup moves bringing each corner target to RDF.
+const OP_CORNER_SETUPS: [(&str, &str); 21] = [
+    ("UBR", "R D'"), ("UFR", "F"), ("UFL", "F R'"), ("LUF", "F2"), ("LDF", "D2 R"), ("LDB", "D2"),
+    ("FUL", "F' D"), ("FUR", "R2 D'"), ("FDR", "R F"), ("FDL", "D"), ("RUF", "R'"), ("RUB", "R2"),
+    ("RDB", "R"), ("RDF", ""), ("BUR", "R' F"), ("BDL", "D' R"), ("BDR", "D'"), ("DFL", "F'"),
+    ("DFR", "R' D'"), ("DBR", "R2 F"), ("DBL", "D F'"),
+];
+
+/// The setup moves bringing each edge target to UL.
+const OP_EDGE_SETUPS: [(&str, &str); 22] = [
+    ("UB", "R2 U' R2"), ("UF", "R2 U R2"), ("UL", ""), ("LU", "L U' F U"), ("LF", "U' F U"),
+    ("LD", "L U B' U'"), ("LB", "U B' U'"), ("FU", "R F' L' R'"), ("FR", "U2 R U2"), ("FD", "F L' F'"),
+    ("FL", "L'"), ("RB", "U B U'"), ("RD", "D B' L B"), ("RF", "U' F' U"), ("BU", "R' B L R"),
+    ("BL", "L"), ("BD", "B' L B"), ("BR", "U2 R' U2"), ("DF", "D' L2"), ("DR", "D2 L2"),
+    ("DB", "D L2"), ("DL", "L2"),
+];
+
+/// The setup moves bringing each edge target off the M slice to UB.
+const M2_SETUPS: [(&str, &str); 16] = [
+    ("UR", "F U' F'"), ("UL", "F U F'"), ("LU", "B L' B'"), ("LF", "F U2 F'"), ("LD", "B L B'"),
+    ("LB", "D B' D'"), ("FR", "U R U'"), ("FL", "U' L' U"), ("RU", "B' R B"), ("RB", "D B D'"),
+    ("RD", "B' R' B"), ("RF", "F' U2 F"), ("BL", "U' L U"), ("BR", "U R' U'"), ("DR", "U R2 U'"),
+    ("DL", "U' L2 U"),
+];
+
+/// The algorithms for the edge targets on the M slice, which also swap UF
+/// and DB like M2.
+const M2_SLICE: [(&str, &str); 6] = [
+    ("UB", "M2"),
+    ("BU", "U B' R U' B M2 B' U R' B U'"),
+    ("UF", "U2 M' U2 M'"),
+    ("FU", "D M' U R2 U' M U R2 U' D' M2"),
+    ("DB", "M U2 M U2"),
+    ("BD", "M2 D U R2 U' M' U R2 U' M D'"),
+];
+
+/// Swaps UFR and UBR back after an odd number of T perms, and UL and UB
+/// ahead of an odd number of Y perms.
+const OP_PARITY: &str = "R U' R' U' R U R D R' U' R D' R' U2 R' U'";
+/// Turns the M slice back after an odd number of M2 targets, and swaps UL
+/// and UB ahead of an odd number of Y perms.
+const M2_PARITY: &str = "D' L2 D M2 D' L2 D";
+
+fn moves(sequence: &str) -> Vec<Move> {
+    Move::from_sequence(sequence).unwrap().collect()
+}
+
+/// `setup swap setup'` for the target, from a table of setups, with the
+/// moves cancelling across the joins taken out.
+fn setup_swap(setups: &[(&str, &str)], swap: &str, facelet: usize) -> Vec<Move> {
+    let (_, setup) = setups.iter().find(|(name, _)| sticker_facelet(name).unwrap() == facelet).unwrap();
+    let setup = moves(setup);
+    Move::simplify(&[&setup[..], &moves(swap), &Move::invert_sequence(&setup)].concat())
+}
+
+/// The algorithm for the `index`th M2 target (from 0). After an odd number
+/// of targets the M slice is turned half way, so UF and DB are exchanged.
+fn m2_target(facelet: usize, index: usize) -> Vec<Move> {
+    let mut facelet = facelet;
+    if index % 2 == 1 {
+        let swapped = [("UF", "DB"), ("FU", "BD"), ("DB", "UF"), ("BD", "FU")];
+        if let Some((_, other)) = swapped.iter().find(|(name, _)| sticker_facelet(name).unwrap() == facelet) {
+            facelet = sticker_facelet(other).unwrap();
+        }
+    }
+    match M2_SLICE.iter().find(|(name, _)| sticker_facelet(name).unwrap() == facelet) {
+        Some((_, alg)) => moves(alg),
+        None => setup_swap(&M2_SETUPS, "M2", facelet),
+    }
+}
+
+/// A blindfolded solution, with a step for each target.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Execution {
+    /// The memo executed, with twisted and flipped pieces solved as cycle
+    /// breaks.
+    pub memo: Memo,
+    /// Steps commented with their target letter, or `parity`.
+    pub steps: Vec<Step>,
+}
+
+impl Execution {
+    /// The memo and moves solving a state blindfolded: edges first, then a
+    /// parity algorithm if there is an odd number of targets, then corners.
+    pub fn new(state: &CubeState, scheme: &LetterScheme, method: EdgeMethod) -> Result<Execution, String> {
+        let memo = Memo::trace(state, scheme, corner_buffer(), method.buffer(), true)?;
+        let mut steps = vec![];
+        for (i, target) in memo.edges.iter().enumerate() {
+            let moves = match method {
+                EdgeMethod::OldPochmann => setup_swap(&OP_EDGE_SETUPS, OP_EDGE_SWAP, target.facelet),
+                EdgeMethod::M2 => m2_target(target.facelet, i),
+            };
+            steps.push(Step::new(moves, Some(&target.letter.to_string())));
+        }
+        if memo.parity() {
+            let parity = match method {
+                EdgeMethod::OldPochmann => OP_PARITY,
+                EdgeMethod::M2 => M2_PARITY,
+            };
+            steps.push(Step::new(moves(parity), Some("parity")));
+        }
+        for target in &memo.corners {
+            let moves = setup_swap(&OP_CORNER_SETUPS, OP_CORNER_SWAP, target.facelet);
+            steps.push(Step::new(moves, Some(&target.letter.to_string())));
+        }
+        Ok(Execution { memo, steps })
+    }
+
+    /// Every move of the solution, ready to be queued on a cube.
+    pub fn moves(&self) -> Vec<Move> {
+        self.steps.iter().flat_map(|step| step.moves.iter().copied()).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scramble::{random_cube, Rng};
+
+    #[test]
+    fn executions_solve() {
+        let mut rng = Rng::new(46);
+        for i in 0..600 {
+            let state = random_cube(&mut rng).to_state();
+            let method = if i % 2 == 0 { EdgeMethod::OldPochmann } else { EdgeMethod::M2 };
+            let execution = Execution::new(&state, &LetterScheme::speffz(), method).unwrap();
+            for step in &execution.steps {
+                assert_eq!(step.moves, Move::simplify(&step.moves));
+            }
+            let mut solved = state.clone();
+            solved.apply_all(execution.moves());
+            assert!(solved.is_solved(), "{:?} with {}", method, Move::to_sequence(&execution.moves()));
+        }
+    }
+
+    #[test]
+    fn setups_cancel() {
+        let ufl = setup_swap(&OP_CORNER_SETUPS, OP_CORNER_SWAP, sticker_facelet("UFL").unwrap());
+        assert_eq!(Move::to_sequence(&ufl), "F U' R' U' R U R' F' R U R' U' R' F R2 F'");
+    }
+}
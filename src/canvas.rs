@@ -1,13 +1,17 @@
-use std::cell::RefCell;
+use std::cell::{Cell, RefCell};
+use std::fmt;
 use std::rc::Rc;
 use std::sync::Arc;
 
+use js_sys::{Object, Reflect};
 use three_d::{context, Context, Viewport};
 use wasm_bindgen::closure::Closure;
 use wasm_bindgen::JsValue;
-use web_sys::{HtmlCanvasElement, Performance, WebGl2RenderingContext};
+use web_sys::{Event, HtmlCanvasElement, Performance, ResizeObserver, ResizeObserverEntry, WebGl2RenderingContext};
 use wasm_bindgen::JsCast;
 
+use crate::clock::Clock;
+
 fn request_animation_frame(closure: &Closure<dyn FnMut()>) {
     web_sys::window()
         .expect("Global window object should exist")
@@ -15,55 +19,306 @@ fn request_animation_frame(closure: &Closure<dyn FnMut()>) {
         .unwrap();
 }
 
-fn performance() -> Performance {
+pub(crate) fn performance() -> Performance {
     web_sys::window()
        .expect("Global window object should exist")
        .performance()
        .expect("Performance should exist")
 }
 
+fn device_pixel_ratio() -> f64 {
+    web_sys::window()
+        .expect("Global window object should exist")
+        .device_pixel_ratio()
+}
+
 pub struct ProgramInput {
+    /// The current rendering context. It is replaced when a lost context is
+    /// restored, and everything created with the old one must be recreated.
+    pub context: Context,
+    /// Whether the context was restored since the last frame.
+    pub context_restored: bool,
+    /// Milliseconds since the last frame, by the canvas clock.
     pub frame_time: f64,
+    /// The time of this frame, in milliseconds, from the canvas clock.
     pub time: f64,
+    /// Size of the drawing buffer, in physical pixels.
+    pub width: u32,
+    pub height: u32,
+    /// Physical pixels per CSS pixel.
+    pub device_pixel_ratio: f64,
+}
+
+/// Which GPU the browser should prefer for the context.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum PowerPreference {
+    #[default]
+    Default,
+    HighPerformance,
+    LowPower,
+}
+
+impl PowerPreference {
+    pub fn from_name(name: &str) -> Result<PowerPreference, String> {
+        match name {
+            "default" => Ok(PowerPreference::Default),
+            "high-performance" => Ok(PowerPreference::HighPerformance),
+            "low-power" => Ok(PowerPreference::LowPower),
+            _ => Err(format!("Unknown power preference {}", name)),
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            PowerPreference::Default => "default",
+            PowerPreference::HighPerformance => "high-performance",
+            PowerPreference::LowPower => "low-power",
+        }
+    }
+}
+
+/// WebGL context attributes, as passed to `getContext`.
+/// Defaults are the browser's.
+#[derive(Clone, PartialEq, Debug)]
+pub struct ContextOptions {
+    pub alpha: bool,
+    pub antialias: bool,
+    pub depth: bool,
+    pub stencil: bool,
+    pub premultiplied_alpha: bool,
+    pub fail_if_major_performance_caveat: bool,
+    pub desynchronized: bool,
+    pub power_preference: PowerPreference,
+    pub preserve_drawing_buffer: bool,
+}
+
+impl Default for ContextOptions {
+    fn default() -> Self {
+        ContextOptions {
+            alpha: true,
+            antialias: true,
+            depth: true,
+            stencil: false,
+            premultiplied_alpha: true,
+            fail_if_major_performance_caveat: false,
+            desynchronized: false,
+            power_preference: PowerPreference::Default,
+            preserve_drawing_buffer: false,
+        }
+    }
+}
+
+impl ContextOptions {
+    /// Extensions the enabled rendering features can't do without. The cube
+    /// itself needs none.
+    fn required_extensions(&self) -> &'static [&'static str] {
+        &[]
+    }
+
+    /// Extensions that are enabled when available, for better quality.
+    fn optional_extensions(&self) -> &'static [&'static str] {
+        &[]
+    }
+
+    /// The `WebGLContextAttributes` dictionary for these options.
+    fn to_js(&self) -> JsValue {
+        let attributes = Object::new();
+        let set = |key: &str, value: JsValue| {
+            Reflect::set(&attributes, &key.into(), &value).expect("Setting a property of a plain object should work");
+        };
+        set("alpha", self.alpha.into());
+        set("antialias", self.antialias.into());
+        set("depth", self.depth.into());
+        set("stencil", self.stencil.into());
+        set("premultipliedAlpha", self.premultiplied_alpha.into());
+        set("failIfMajorPerformanceCaveat", self.fail_if_major_performance_caveat.into());
+        set("desynchronized", self.desynchronized.into());
+        set("powerPreference", self.power_preference.name().into());
+        set("preserveDrawingBuffer", self.preserve_drawing_buffer.into());
+        attributes.into()
+    }
+}
+
+/// Why a canvas couldn't be set up or drawn to.
+///
+/// Converted to a JS `Error` with a `code` property, so pages can react to
+/// each case, for example by showing a static picture without WebGL2.
+#[derive(Clone, PartialEq, Debug)]
+pub enum CanvasError {
+    /// The browser or device doesn't support WebGL2.
+    WebGl2Unsupported(String),
+    /// A rendering feature was enabled that needs an unavailable extension.
+    MissingExtension(&'static str),
+    /// The WebGL2 context exists but couldn't be set up for rendering.
+    Context(String),
+    /// The options were malformed.
+    InvalidOptions(String),
+    /// Listening to canvas size or context events failed.
+    Events(String),
+}
+
+impl CanvasError {
+    /// A stable identifier of the kind of error.
+    pub fn code(&self) -> &'static str {
+        match self {
+            CanvasError::WebGl2Unsupported(_) => "webgl2-unsupported",
+            CanvasError::MissingExtension(_) => "missing-extension",
+            CanvasError::Context(_) => "context",
+            CanvasError::InvalidOptions(_) => "invalid-options",
+            CanvasError::Events(_) => "events",
+        }
+    }
+}
+
+impl fmt::Display for CanvasError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CanvasError::WebGl2Unsupported(reason) => write!(f, "WebGL2 is not supported: {}", reason),
+            CanvasError::MissingExtension(name) => write!(f, "WebGL2 extension {} is not available", name),
+            CanvasError::Context(reason) => write!(f, "Failed to set up the WebGL2 context: {}", reason),
+            CanvasError::InvalidOptions(reason) => write!(f, "Invalid options: {}", reason),
+            CanvasError::Events(reason) => write!(f, "Failed to listen to canvas events: {}", reason),
+        }
+    }
+}
+
+impl std::error::Error for CanvasError {}
+
+impl From<CanvasError> for JsValue {
+    fn from(error: CanvasError) -> JsValue {
+        let js_error = js_sys::Error::new(&error.to_string());
+        js_error.set_name("RustbikError");
+        let _ = Reflect::set(&js_error, &"code".into(), &error.code().into());
+        if let CanvasError::MissingExtension(name) = error {
+            let _ = Reflect::set(&js_error, &"extension".into(), &name.into());
+        }
+        js_error.into()
+    }
+}
+
+/// Turn on the extensions needed by the options. Extensions have to be
+/// enabled again after the context is restored.
+fn enable_extensions(gl: &WebGl2RenderingContext, opts: &ContextOptions) -> Result<(), CanvasError> {
+    for &name in opts.required_extensions() {
+        match gl.get_extension(name) {
+            Ok(Some(_)) => (),
+            _ => return Err(CanvasError::MissingExtension(name)),
+        }
+    }
+    for &name in opts.optional_extensions() {
+        let _ = gl.get_extension(name);
+    }
+    Ok(())
+}
+
+// three_d requires an Arc even though WebGL contexts are single-threaded.
+#[allow(clippy::arc_with_non_send_sync)]
+fn three_d_context(gl: &WebGl2RenderingContext) -> Result<Context, CanvasError> {
+    Context::from_gl_context(Arc::new(context::Context::from_webgl2_context(gl.clone())))
+        .map_err(|e| CanvasError::Context(format!("{:?}", e)))
+}
+
+/// Listen to an event of the canvas for as long as the returned closure lives.
+fn listen(canvas: &HtmlCanvasElement, event: &str, handler: impl FnMut(Event) + 'static) -> Result<Closure<dyn FnMut(Event)>, CanvasError> {
+    let closure = Closure::<dyn FnMut(Event)>::new(handler);
+    canvas
+        .add_event_listener_with_callback(event, closure.as_ref().unchecked_ref())
+        .map_err(|e| CanvasError::Events(format!("{:?}", e)))?;
+    Ok(closure)
+}
+
+/// Whether the context is usable, as told by the canvas' context events.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum ContextStatus {
+    Ready,
+    Lost,
+    Restored,
+}
+
+/// A handle telling whether a [`Canvas`]' context is lost, that stays
+/// usable after the canvas is moved into [`Canvas::run`].
+#[derive(Clone)]
+pub struct ContextWatch(Rc<Cell<ContextStatus>>);
+
+impl ContextWatch {
+    pub fn is_lost(&self) -> bool {
+        self.0.get() == ContextStatus::Lost
+    }
 }
 
 /// A WebGL2 wrapper for a canvas element.
+///
+/// The drawing buffer follows the size of the element on the page,
+/// in physical pixels, so rendering stays sharp on HiDPI screens.
+///
+/// When the browser loses the context, for example after a GPU reset,
+/// drawing pauses until it is restored, and then continues with a new
+/// [`Context`] (see [`ProgramInput::context_restored`]).
 pub struct Canvas {
     canvas: HtmlCanvasElement,
+    gl: WebGl2RenderingContext,
+    options: ContextOptions,
     context: Context,
+    /// Size of the element's content box in CSS pixels, kept up to date by `observer`.
+    css_size: Rc<Cell<(f64, f64)>>,
+    observer: ResizeObserver,
+    _on_resize: Closure<dyn FnMut(js_sys::Array)>,
+    status: Rc<Cell<ContextStatus>>,
+    _on_context_lost: Closure<dyn FnMut(Event)>,
+    _on_context_restored: Closure<dyn FnMut(Event)>,
 }
 
 impl Canvas {
     /// Create a new Canvas with the underlying element and WebGL2 options.
-    /// Options are not type checked.
-    pub fn new(canvas: HtmlCanvasElement, opts: Option<JsValue>) -> Result<Self, String> {
-        let wglctx = if let Some(options) = opts {
-            canvas.get_context_with_context_options("webgl2", &options)
-        } else {
-            canvas.get_context("webgl2")
-        };
-        let wglctx = wglctx
-            .map_err(|e| format!("webgl2 not supported: {:?}", e))?
-            .ok_or("webgl2 not supported".to_string())?
+    pub fn new(canvas: HtmlCanvasElement, opts: &ContextOptions) -> Result<Self, CanvasError> {
+        let gl = canvas.get_context_with_context_options("webgl2", &opts.to_js())
+            .map_err(|e| CanvasError::WebGl2Unsupported(format!("{:?}", e)))?
+            .ok_or(CanvasError::WebGl2Unsupported("no webgl2 context".to_string()))?
             .dyn_into::<WebGl2RenderingContext>()
-            .map_err(|e| format!("failed to get webgl context: {:?}", e))?;
-        // Check for WebGL 2 extension support
-        wglctx
-            .get_extension("EXT_color_buffer_float")
-            .map_err(|e| format!("extension EXT_color_buffer_float not available: {:?}", e))?;
-        wglctx
-            .get_extension("OES_texture_float_linear")
-            .map_err(|e| format!("extension OES_texture_float_linear not available: {:?}", e))?;
-        wglctx
-            .get_extension("OES_texture_half_float_linear")
-            .map_err(|e| format!("extension OES_texture_half_float_linear not available: {:?}", e))?;
-        
+            .map_err(|e| CanvasError::WebGl2Unsupported(format!("{:?}", e)))?;
+        enable_extensions(&gl, opts)?;
+        let context = three_d_context(&gl)?;
+
+        let css_size = Rc::new(Cell::new((canvas.client_width() as f64, canvas.client_height() as f64)));
+        let observed_size = css_size.clone();
+        let on_resize = Closure::<dyn FnMut(js_sys::Array)>::new(move |entries: js_sys::Array| {
+            // The last entry is the newest size; there may be none.
+            let Some(last) = entries.length().checked_sub(1) else {
+                return;
+            };
+            if let Ok(entry) = entries.get(last).dyn_into::<ResizeObserverEntry>() {
+                let rect = entry.content_rect();
+                observed_size.set((rect.width(), rect.height()));
+            }
+        });
+        let observer = ResizeObserver::new(on_resize.as_ref().unchecked_ref())
+            .map_err(|e| CanvasError::Events(format!("{:?}", e)))?;
+        observer.observe(&canvas);
+
+        let status = Rc::new(Cell::new(ContextStatus::Ready));
+        let lost_status = status.clone();
+        let on_context_lost = listen(&canvas, "webglcontextlost", move |event| {
+            // Without this the browser never restores the context.
+            event.prevent_default();
+            lost_status.set(ContextStatus::Lost);
+        })?;
+        let restored_status = status.clone();
+        let on_context_restored = listen(&canvas, "webglcontextrestored", move |_| {
+            restored_status.set(ContextStatus::Restored);
+        })?;
+
         Ok(
             Self {
                 canvas,
-                context: Context::from_gl_context(Arc::new(
-                    context::Context::from_webgl2_context(wglctx),
-                )).map_err(|e| format!("three_d::core failed to create context: {:?}", e))?
+                gl,
+                options: opts.clone(),
+                context,
+                css_size,
+                observer,
+                _on_resize: on_resize,
+                status,
+                _on_context_lost: on_context_lost,
+                _on_context_restored: on_context_restored,
             }
         )
     }
@@ -72,34 +327,100 @@ impl Canvas {
         self.context.clone()
     }
 
+    /// Whether the context is lost and drawing is paused.
+    pub fn is_context_lost(&self) -> bool {
+        self.status.get() == ContextStatus::Lost
+    }
+
+    pub fn context_watch(&self) -> ContextWatch {
+        ContextWatch(self.status.clone())
+    }
+
+    /// Size of the canvas on the page, in CSS pixels.
     pub fn logical_size(&self) -> (u32, u32) {
-        (self.canvas.client_width() as u32, self.canvas.client_height() as u32)
+        let (w, h) = self.css_size.get();
+        (w.round() as u32, h.round() as u32)
+    }
+
+    /// Size of the canvas on the page, in physical pixels.
+    pub fn physical_size(&self) -> (u32, u32) {
+        let (w, h) = self.css_size.get();
+        let dpr = device_pixel_ratio();
+        (((w * dpr).round() as u32).max(1), ((h * dpr).round() as u32).max(1))
     }
 
     pub fn viewport(&self) -> Viewport {
-        let (w, h) = self.logical_size();
+        let (w, h) = self.physical_size();
         Viewport::new_at_origo(w, h)
     }
 
-    pub fn run(&self, mut program: impl 'static + FnMut(ProgramInput)) {
+    /// Resize the drawing buffer to the current physical size of the canvas.
+    fn fit_drawing_buffer(&self) -> (u32, u32) {
+        let (w, h) = self.physical_size();
+        if self.canvas.width() != w || self.canvas.height() != h {
+            self.canvas.set_width(w);
+            self.canvas.set_height(h);
+        }
+        (w, h)
+    }
+
+    /// Set the context up again after the browser restored it.
+    /// Returns false if it can't be used, in which case drawing stays paused.
+    fn restore_context(&mut self) -> bool {
+        match enable_extensions(&self.gl, &self.options).and_then(|_| three_d_context(&self.gl)) {
+            Ok(context) => {
+                self.context = context;
+                self.status.set(ContextStatus::Ready);
+                true
+            }
+            Err(_) => {
+                self.status.set(ContextStatus::Lost);
+                false
+            }
+        }
+    }
+
+    /// Call `program` on every animation frame, with frame times taken from
+    /// a clock. The canvas is kept alive, and its drawing buffer resized to
+    /// match the page, for as long as the program runs. Frames are skipped
+    /// while the context is lost.
+    pub fn run(mut self, mut clock: impl 'static + Clock, mut program: impl 'static + FnMut(ProgramInput)) {
         let closure = Rc::new(RefCell::new(None));
         let closure2 = closure.clone();
 
-        let mut elapsed_time = 0.0;
-        let mut last_time = 0.0;
+        let mut last_time = None;
         *closure2.borrow_mut() = Some(Closure::new(move || {
             // TODO: add exit condition
             // Need to drop handle on closure with closure.borrow_mut.take()
-            let frame_time = performance().now() - last_time;
-            elapsed_time += frame_time;
-            last_time = performance().now();
-            program(ProgramInput {
-                time: elapsed_time,
-                frame_time
-            });
+            let time = clock.tick();
+            let frame_time = last_time.map_or(0.0, |last| time - last);
+            last_time = Some(time);
+            let context_restored = match self.status.get() {
+                ContextStatus::Ready => false,
+                ContextStatus::Lost => false,
+                ContextStatus::Restored => self.restore_context(),
+            };
+            if !self.is_context_lost() {
+                let (width, height) = self.fit_drawing_buffer();
+                program(ProgramInput {
+                    context: self.context.clone(),
+                    context_restored,
+                    time,
+                    frame_time,
+                    width,
+                    height,
+                    device_pixel_ratio: device_pixel_ratio(),
+                });
+            }
             request_animation_frame(closure.borrow().as_ref().unwrap());
         }));
 
         request_animation_frame(closure2.borrow().as_ref().unwrap());
     }
 }
+
+impl Drop for Canvas {
+    fn drop(&mut self) {
+        self.observer.disconnect();
+    }
+}
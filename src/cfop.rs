@@ -0,0 +1,400 @@
+//! CFOP reconstructions of timed solves: the moves of a solve, as a smart
+//! cube reports them, split where the cross, each F2L pair, OLL and PLL
+//! were finished, with the moves, time, turns per second and recognition
+//! pause of each step.
+//!
+//! Steps are found from the state after every move, holding the cube with
+//! each face down in turn, so the cross can be any color and the moves may
+//! include rotations, wide and slice turns.
+
+use std::fmt;
+use std::str::FromStr;
+
+use crate::algorithm::Metric;
+use crate::reconstruction::{Reconstruction, Step};
+use crate::rubik::{Color, CubeState, CubieCube, Move};
+
+/// Rotations bringing each face down.
+const CROSS_ROTATIONS: [Option<Move>; 6] = [
+    None, Some(Move::X), Some(Move::X2), Some(Move::XP), Some(Move::Z), Some(Move::ZP),
+];
+
+/// The facelet at the center of the D face.
+const D_CENTER: usize = 3 * 9 + 4;
+
+/// The metric move counts and turns per second are given in.
+pub const METRIC: Metric = Metric::Stm;
+
+/// A move and when it was made, in milliseconds from the start of the
+/// solve. Written as `R'@1250`, as smart cube timers export moves.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct TimedMove {
+    pub mv: Move,
+    pub time: f64,
+}
+
+impl TimedMove {
+    pub fn new(mv: Move, time: f64) -> TimedMove {
+        TimedMove { mv, time }
+    }
+
+    /// Parse timed moves separated by whitespace, such as `R@0 U'@180`.
+    pub fn parse_sequence(input: &str) -> Result<Vec<TimedMove>, String> {
+        input.split_whitespace().map(str::parse).collect()
+    }
+
+    pub fn to_sequence(moves: &[TimedMove]) -> String {
+        moves.iter().map(|mv| mv.to_string()).collect::<Vec<_>>().join(" ")
+    }
+}
+
+impl FromStr for TimedMove {
+    type Err = String;
+
+    fn from_str(input: &str) -> Result<TimedMove, String> {
+        let (mv, time) = input.split_once('@').ok_or(format!("Missing time in {}", input))?;
+        let mut moves = Move::from_sequence(mv)?;
+        let mv = match (moves.next(), moves.next()) {
+            (Some(mv), None) => mv,
+            _ => return Err(format!("Invalid move {}", mv)),
+        };
+        let time: f64 = time.parse().map_err(|_| format!("Invalid time in {}", input))?;
+        if !time.is_finite() || time < 0.0 {
+            return Err(format!("Invalid time in {}", input));
+        }
+        Ok(TimedMove::new(mv, time))
+    }
+}
+
+impl fmt::Display for TimedMove {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}@{}", self.mv, self.time)
+    }
+}
+
+/// A step of CFOP.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Stage {
+    Cross,
+    /// F2L pairs, counted from 1: usually one, or more when a step solved
+    /// several at once.
+    Pairs { first: usize, last: usize },
+    Oll,
+    Pll,
+}
+
+impl fmt::Display for Stage {
+    /// `Cross`, `F2L 1`, `F2L 2+3`, `OLL` or `PLL`.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Stage::Cross => f.write_str("Cross"),
+            Stage::Pairs { first, last } => {
+                let pairs: Vec<String> = (*first..=*last).map(|pair| pair.to_string()).collect();
+                write!(f, "F2L {}", pairs.join("+"))
+            }
+            Stage::Oll => f.write_str("OLL"),
+            Stage::Pll => f.write_str("PLL"),
+        }
+    }
+}
+
+/// A finished step. A step skipped because the one before solved it too
+/// has no moves.
+#[derive(Clone, PartialEq, Debug)]
+pub struct Split {
+    pub stage: Stage,
+    pub moves: Vec<TimedMove>,
+    /// When the step before ended, or 0 for the cross.
+    pub start: f64,
+    /// When the last move of the step was made.
+    pub end: f64,
+}
+
+impl Split {
+    pub fn moves(&self) -> Vec<Move> {
+        self.moves.iter().map(|mv| mv.mv).collect()
+    }
+
+    /// Moves in [`METRIC`].
+    pub fn length(&self) -> usize {
+        METRIC.count(&self.moves())
+    }
+
+    pub fn time(&self) -> f64 {
+        self.end - self.start
+    }
+
+    /// The pause before the first move: inspection after the start for the
+    /// cross, recognition for the other steps.
+    pub fn recognition(&self) -> f64 {
+        self.moves.first().map_or(0.0, |mv| mv.time - self.start)
+    }
+
+    /// Turns per second over the whole step, pause included.
+    pub fn tps(&self) -> f64 {
+        tps(self.length(), self.time())
+    }
+
+    /// Turns per second from the first move of the step.
+    pub fn execution_tps(&self) -> f64 {
+        tps(self.length(), self.time() - self.recognition())
+    }
+}
+
+fn tps(length: usize, time: f64) -> f64 {
+    if time > 0.0 { length as f64 * 1000.0 / time } else { 0.0 }
+}
+
+/// A solve split into CFOP steps.
+#[derive(Clone, PartialEq, Debug)]
+pub struct Cfop {
+    /// The color of the cross, if one was solved.
+    pub cross: Option<Color>,
+    /// The steps finished, in order.
+    pub splits: Vec<Split>,
+    /// Moves after the last finished step, when the solve wasn't finished.
+    pub unfinished: Vec<TimedMove>,
+}
+
+impl Cfop {
+    /// Split the moves of a solve after a scramble into steps, with the
+    /// cross on the face that gets furthest. Times must not decrease.
+    pub fn new(scramble: &[Move], moves: &[TimedMove]) -> Result<Cfop, String> {
+        if let Some(pair) = moves.windows(2).find(|pair| pair[1].time < pair[0].time) {
+            return Err(format!("Moves out of order: {} after {}", pair[1], pair[0]));
+        }
+        let mut state = CubeState::solved();
+        state.apply_all(scramble.iter().copied());
+        let mut states = vec![state.clone()];
+        for mv in moves {
+            state.apply(mv.mv);
+            states.push(state.clone());
+        }
+        let mut best: Option<(Color, Vec<(Stage, usize)>)> = None;
+        for rotation in CROSS_ROTATIONS {
+            let mut cubes = vec![];
+            let mut cross = Color::None;
+            for state in &states {
+                let mut state = state.clone();
+                state.apply_all(rotation);
+                cross = state.facelet(D_CENTER);
+                cubes.push(CubieCube::from_state(&state)?);
+            }
+            let ends = stage_ends(&cubes);
+            let reached = milestones(&ends);
+            let progress = |reached: &[usize; 7]| reached.iter().filter(|&&end| end != usize::MAX).count();
+            // The face that got furthest, then the one that got there first.
+            let better = match &best {
+                None => !ends.is_empty(),
+                Some((_, best)) => {
+                    let best = milestones(best);
+                    progress(&reached) > progress(&best) || progress(&reached) == progress(&best) && reached < best
+                }
+            };
+            if better {
+                best = Some((cross, ends));
+            }
+        }
+        let Some((cross, ends)) = best else {
+            return Ok(Cfop { cross: None, splits: vec![], unfinished: moves.to_vec() });
+        };
+        let mut splits = vec![];
+        let mut done = 0;
+        for (stage, end) in ends {
+            let start = if done == 0 { 0.0 } else { moves[done - 1].time };
+            let end_time = if end == 0 { 0.0 } else { moves[end - 1].time };
+            splits.push(Split { stage, moves: moves[done..end].to_vec(), start, end: end_time });
+            done = end;
+        }
+        Ok(Cfop { cross: Some(cross), splits, unfinished: moves[done..].to_vec() })
+    }
+
+    /// Whether every step was finished.
+    pub fn is_finished(&self) -> bool {
+        self.splits.last().is_some_and(|split| split.stage == Stage::Pll)
+    }
+
+    /// When the last step ended, or the last move for an unfinished solve.
+    pub fn time(&self) -> f64 {
+        match (self.unfinished.last(), self.splits.last()) {
+            (Some(mv), _) => mv.time,
+            (None, Some(split)) => split.end,
+            (None, None) => 0.0,
+        }
+    }
+
+    /// Moves in [`METRIC`], unfinished ones included.
+    pub fn length(&self) -> usize {
+        let unfinished: Vec<Move> = self.unfinished.iter().map(|mv| mv.mv).collect();
+        self.splits.iter().map(Split::length).sum::<usize>() + METRIC.count(&unfinished)
+    }
+
+    pub fn tps(&self) -> f64 {
+        tps(self.length(), self.time())
+    }
+
+    /// The solve as a reconstruction with a commented line for each step.
+    pub fn to_reconstruction(&self, scramble: &[Move]) -> Reconstruction {
+        let mut steps: Vec<Step> = self.splits.iter().map(|split| {
+            let comment = match split.stage {
+                Stage::Cross => format!("{} ({})", split.stage, self.cross.unwrap_or_default().to_char()),
+                stage if split.moves.is_empty() => format!("{} skip", stage),
+                stage => stage.to_string(),
+            };
+            Step::new(split.moves(), Some(&comment))
+        }).collect();
+        if !self.unfinished.is_empty() {
+            steps.push(Step::new(self.unfinished.iter().map(|mv| mv.mv).collect(), Some("Unfinished")));
+        }
+        Reconstruction { title: None, setup: scramble.to_vec(), steps }
+    }
+}
+
+/// The steps finished with the cross on D, each with the number of moves
+/// done when it ended. Pairs count when the cross is solved and more of
+/// them are solved than ever before.
+fn stage_ends(cubes: &[CubieCube]) -> Vec<(Stage, usize)> {
+    let mut ends = vec![];
+    let mut pairs = 0;
+    for (done, cube) in cubes.iter().enumerate() {
+        // A move can finish several steps, such as the last pair and OLL.
+        loop {
+            let stage = match ends.last() {
+                None if cube.is_cross_solved() => Stage::Cross,
+                Some((Stage::Cross | Stage::Pairs { .. }, _)) if pairs < 4 && cube.is_cross_solved() => {
+                    let solved = cube.solved_pairs().iter().filter(|&&solved| solved).count();
+                    if solved <= pairs {
+                        break;
+                    }
+                    let stage = Stage::Pairs { first: pairs + 1, last: solved };
+                    pairs = solved;
+                    stage
+                }
+                Some((Stage::Pairs { .. }, _)) if pairs == 4 && cube.is_f2l_solved() && cube.is_last_layer_oriented() => {
+                    Stage::Oll
+                }
+                Some((Stage::Oll, _)) if cube.is_solved() => Stage::Pll,
+                _ => break,
+            };
+            ends.push((stage, done));
+        }
+    }
+    ends
+}
+
+/// The number of moves done when the cross, each pair, OLL and PLL were
+/// finished, or `usize::MAX` for those that weren't.
+fn milestones(ends: &[(Stage, usize)]) -> [usize; 7] {
+    let mut reached = [usize::MAX; 7];
+    for &(stage, end) in ends {
+        match stage {
+            Stage::Cross => reached[0] = end,
+            Stage::Pairs { first, last } => reached[first..=last].fill(end),
+            Stage::Oll => reached[5] = end,
+            Stage::Pll => reached[6] = end,
+        }
+    }
+    reached
+}
+
+impl fmt::Display for Cfop {
+    /// A line for each step, such as
+    /// `F2L 1: U R U' R' (4 moves, 1.20 s, 3.33 TPS, 0.45 s recognition)`,
+    /// then the total.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(cross) = self.cross {
+            writeln!(f, "Cross color: {:?}", cross)?;
+        }
+        for split in &self.splits {
+            if split.moves.is_empty() {
+                writeln!(f, "{}: skip", split.stage)?;
+                continue;
+            }
+            writeln!(
+                f,
+                "{}: {} ({} move{}, {:.2} s, {:.2} TPS, {:.2} s recognition)",
+                split.stage,
+                Move::to_sequence(&split.moves()),
+                split.length(),
+                if split.length() == 1 { "" } else { "s" },
+                split.time() / 1000.0,
+                split.tps(),
+                split.recognition() / 1000.0,
+            )?;
+        }
+        if !self.unfinished.is_empty() {
+            let moves: Vec<Move> = self.unfinished.iter().map(|mv| mv.mv).collect();
+            writeln!(f, "Unfinished: {}", Move::to_sequence(&moves))?;
+        }
+        writeln!(
+            f,
+            "Total: {} moves ({}), {:.2} s, {:.2} TPS",
+            self.length(),
+            METRIC,
+            self.time() / 1000.0,
+            self.tps()
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A solve of `moves` after a scramble, with a move every 100 ms.
+    fn solve(scramble: &str, moves: &str) -> Cfop {
+        let scramble: Vec<Move> = Move::from_sequence(scramble).unwrap().collect();
+        let moves: Vec<TimedMove> = Move::from_sequence(moves).unwrap()
+            .enumerate()
+            .map(|(i, mv)| TimedMove::new(mv, 1000.0 + 100.0 * i as f64))
+            .collect();
+        Cfop::new(&scramble, &moves).unwrap()
+    }
+
+    fn stages(cfop: &Cfop) -> Vec<(String, String)> {
+        cfop.splits.iter().map(|split| (split.stage.to_string(), Move::to_sequence(&split.moves()))).collect()
+    }
+
+    #[test]
+    fn oll_on_solved_f2l() {
+        let cfop = solve("R U2 R' U' R U' R'", "R U R' U R U2 R'");
+        assert_eq!(cfop.cross, Some(Color::White));
+        assert!(cfop.is_finished());
+        assert_eq!(stages(&cfop), [
+            ("Cross".to_string(), String::new()),
+            ("F2L 1+2+3+4".to_string(), String::new()),
+            ("OLL".to_string(), "R U R' U R U2 R'".to_string()),
+            ("PLL".to_string(), String::new()),
+        ]);
+    }
+
+    #[test]
+    fn pll_on_solved_f2l() {
+        let t_perm = "R U R' U' R' F R2 U' R' U' R U R' F'";
+        let cfop = solve(t_perm, t_perm);
+        assert_eq!(cfop.cross, Some(Color::White));
+        assert_eq!(stages(&cfop).last().unwrap(), &("PLL".to_string(), t_perm.to_string()));
+        assert_eq!(cfop.splits.len(), 4);
+    }
+
+    #[test]
+    fn split_times() {
+        let scramble: Vec<Move> = Move::from_sequence("R U R' U'").unwrap().collect();
+        let cfop = Cfop::new(&scramble, &TimedMove::parse_sequence("U@300 R@500 U'@700 R'@900").unwrap()).unwrap();
+        assert!(cfop.is_finished());
+        let last = cfop.splits.last().unwrap();
+        assert_eq!((last.start, last.end, last.recognition(), last.length()), (700.0, 900.0, 200.0, 1));
+        assert_eq!(cfop.time(), 900.0);
+        assert_eq!(cfop.length(), 4);
+    }
+
+    #[test]
+    fn timed_moves() {
+        let moves = TimedMove::parse_sequence("R'@12 U2@30.5").unwrap();
+        assert_eq!(moves, [TimedMove::new(Move::RP, 12.0), TimedMove::new(Move::U2, 30.5)]);
+        assert_eq!(TimedMove::to_sequence(&moves), "R'@12 U2@30.5");
+        assert!("R'12".parse::<TimedMove>().is_err());
+        assert!("R@-1".parse::<TimedMove>().is_err());
+        assert!(Cfop::new(&[], &TimedMove::parse_sequence("R@5 U@2").unwrap()).is_err());
+    }
+}
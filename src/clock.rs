@@ -0,0 +1,185 @@
+//! Clocks driving animation. Everything that animates takes its time from
+//! a [`Clock`], so frames can follow the wall clock, advance by a fixed step
+//! for frame-exact rendering, or be stepped by hand in tests.
+
+use std::cell::Cell;
+use std::rc::Rc;
+
+/// A source of frame times, in milliseconds.
+pub trait Clock {
+    /// The time of the next frame. Never less than the time of the last one.
+    fn tick(&mut self) -> f64;
+}
+
+/// Milliseconds since some fixed point in the past.
+#[cfg(target_arch = "wasm32")]
+fn now() -> f64 {
+    crate::canvas::performance().now()
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn now() -> f64 {
+    use std::sync::OnceLock;
+    use std::time::Instant;
+
+    static START: OnceLock<Instant> = OnceLock::new();
+    START.get_or_init(Instant::now).elapsed().as_secs_f64() * 1000.0
+}
+
+/// Wall-clock time since the clock was made.
+#[derive(Clone, Debug)]
+pub struct RealTime {
+    start: f64,
+}
+
+impl RealTime {
+    pub fn new() -> RealTime {
+        RealTime { start: now() }
+    }
+}
+
+impl Default for RealTime {
+    fn default() -> Self {
+        RealTime::new()
+    }
+}
+
+impl Clock for RealTime {
+    fn tick(&mut self) -> f64 {
+        now() - self.start
+    }
+}
+
+/// Time that advances by the same step every frame, starting at zero, no
+/// matter how long frames actually take. Frame `n` is at exactly `n * step`.
+#[derive(Clone, Debug)]
+pub struct FixedStep {
+    step: f64,
+    frame: u64,
+}
+
+impl FixedStep {
+    /// A clock advancing `step` milliseconds per frame.
+    ///
+    /// # Panics
+    ///
+    /// If `step` isn't a positive, finite number.
+    pub fn new(step: f64) -> FixedStep {
+        assert!(step.is_finite() && step > 0.0, "A fixed step must be positive and finite, not {}", step);
+        FixedStep { step, frame: 0 }
+    }
+
+    /// A clock advancing one frame of the given frame rate per frame.
+    ///
+    /// # Panics
+    ///
+    /// If `frames_per_second` isn't a positive, finite number.
+    pub fn with_frame_rate(frames_per_second: f64) -> FixedStep {
+        FixedStep::new(1000.0 / frames_per_second)
+    }
+}
+
+impl Clock for FixedStep {
+    fn tick(&mut self) -> f64 {
+        let time = self.frame as f64 * self.step;
+        self.frame += 1;
+        time
+    }
+}
+
+/// Time that only moves when told to. Clones share the same time, so one
+/// can drive animation while another is advanced from elsewhere.
+#[derive(Clone, Default, Debug)]
+pub struct ManualClock {
+    time: Rc<Cell<f64>>,
+}
+
+impl ManualClock {
+    pub fn new() -> ManualClock {
+        ManualClock::default()
+    }
+
+    pub fn time(&self) -> f64 {
+        self.time.get()
+    }
+
+    /// Move the time forward by some milliseconds.
+    pub fn advance(&self, milliseconds: f64) {
+        self.time.set(self.time.get() + milliseconds.max(0.0));
+    }
+}
+
+impl Clock for ManualClock {
+    fn tick(&mut self) -> f64 {
+        self.time.get()
+    }
+}
+
+impl Clock for Box<dyn Clock> {
+    fn tick(&mut self) -> f64 {
+        (**self).tick()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rubik::{Move, MoveQueue};
+
+    #[test]
+    fn fixed_steps() {
+        let mut clock = FixedStep::new(16.6);
+        assert_eq!(clock.tick(), 0.0);
+        assert_eq!(clock.tick(), 16.6);
+        assert_eq!(clock.tick(), 33.2);
+        for frame in 3..999 {
+            assert_eq!(clock.tick(), frame as f64 * 16.6);
+        }
+        assert_eq!(clock.tick(), 16583.4);
+        let mut clock = FixedStep::with_frame_rate(50.0);
+        assert_eq!([clock.tick(), clock.tick(), clock.tick()], [0.0, 20.0, 40.0]);
+    }
+
+    #[test]
+    #[should_panic]
+    fn zero_step() {
+        FixedStep::new(0.0);
+    }
+
+    #[test]
+    #[should_panic]
+    fn nan_frame_rate() {
+        FixedStep::with_frame_rate(f64::NAN);
+    }
+
+    #[test]
+    fn manual_clocks_share_time() {
+        let clock = ManualClock::new();
+        let mut driving = clock.clone();
+        assert_eq!(driving.tick(), 0.0);
+        clock.advance(16.6);
+        clock.advance(-5.0);
+        assert_eq!(driving.tick(), 16.6);
+        driving.advance(10.0);
+        assert_eq!(clock.time(), 26.6);
+    }
+
+    #[test]
+    fn animation_follows_fixed_steps() {
+        let mut clock = FixedStep::new(100.0);
+        let mut moves = MoveQueue::new(250.0);
+        moves.extend([Move::R, Move::U]);
+        let frames: Vec<_> = (0..7).map(|_| moves.advance(clock.tick() as f32)).collect();
+        assert_eq!(frames, [
+            (vec![], Some((Move::R, 0.0))),
+            (vec![], Some((Move::R, 0.4))),
+            (vec![], Some((Move::R, 0.8))),
+            // U starts when R ends, at 250, not at this frame.
+            (vec![Move::R], Some((Move::U, 0.2))),
+            (vec![], Some((Move::U, 0.6))),
+            (vec![], Some((Move::U, 1.0))),
+            (vec![Move::U], None),
+        ]);
+        assert!(moves.is_idle());
+    }
+}
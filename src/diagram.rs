@@ -0,0 +1,229 @@
+//! Flat SVG pictures of a cube state, for printable algorithm sheets.
+//!
+//! Nothing here needs a rendering context, so diagrams can be generated
+//! natively, for example from a build script.
+
+use std::fmt::Write;
+
+use three_d::{InnerSpace, Srgba, Vec3};
+
+use crate::rubik::{Color, CubeState, Move, U};
+
+const GRAY: Srgba = Srgba::new(128, 128, 128, 255);
+
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "camelCase", default, deny_unknown_fields))]
+pub struct DiagramOptions {
+    /// Side length of one sticker, in SVG user units.
+    pub sticker_size: f32,
+    /// Space between adjacent stickers.
+    pub gap: f32,
+    /// Color drawn between stickers.
+    #[cfg_attr(feature = "serde", serde(with = "crate::serialize::rgba"))]
+    pub background: Srgba,
+    /// Only color stickers of this color and draw the rest gray,
+    /// as in OLL diagrams.
+    pub highlight: Option<Color>,
+}
+
+impl Default for DiagramOptions {
+    fn default() -> Self {
+        DiagramOptions {
+            sticker_size: 20.0,
+            gap: 2.0,
+            background: Srgba::BLACK,
+            highlight: None,
+        }
+    }
+}
+
+impl DiagramOptions {
+    fn fill(&self, color: Color) -> String {
+        let srgba = match self.highlight {
+            Some(highlight) if highlight != color => GRAY,
+            _ => color.into(),
+        };
+        hex(srgba)
+    }
+
+    fn pitch(&self) -> f32 {
+        self.sticker_size + self.gap
+    }
+}
+
+fn hex(color: Srgba) -> String {
+    format!("#{:02x}{:02x}{:02x}", color.r, color.g, color.b)
+}
+
+fn svg_open(out: &mut String, x: f32, y: f32, width: f32, height: f32) {
+    write!(
+        out,
+        r#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="{} {} {} {}" width="{}" height="{}">"#,
+        x, y, width, height, width, height
+    ).unwrap();
+}
+
+fn rect(out: &mut String, x: f32, y: f32, width: f32, height: f32, radius: f32, fill: &str) {
+    write!(
+        out,
+        r#"<rect x="{}" y="{}" width="{}" height="{}" rx="{}" fill="{}"/>"#,
+        x, y, width, height, radius, fill
+    ).unwrap();
+}
+
+fn polygon(out: &mut String, points: &[(f32, f32)], fill: &str) {
+    out.push_str(r#"<polygon points=""#);
+    for (i, (x, y)) in points.iter().enumerate() {
+        if i > 0 {
+            out.push(' ');
+        }
+        write!(out, "{},{}", x, y).unwrap();
+    }
+    write!(out, r#"" fill="{}"/>"#, fill).unwrap();
+}
+
+/// The unfolded net of the cube, with U on top of F, L, R and B in a row,
+/// and D below F.
+pub fn net(state: &CubeState, opts: &DiagramOptions) -> String {
+    // Position of each face in the net, in face sizes, in facelet string order.
+    const LAYOUT: [(f32, f32); 6] = [(0.0, 1.0), (1.0, 0.0), (1.0, 1.0), (1.0, 2.0), (2.0, 1.0), (3.0, 1.0)];
+    let pitch = opts.pitch();
+    let face_size = 3.0 * pitch + opts.gap;
+
+    let mut out = String::new();
+    svg_open(&mut out, 0.0, 0.0, 4.0 * face_size, 3.0 * face_size);
+    for (face, (fx, fy)) in LAYOUT.iter().enumerate() {
+        let (x0, y0) = (fx * face_size, fy * face_size);
+        rect(&mut out, x0, y0, face_size, face_size, 0.0, &hex(opts.background));
+        for (i, &color) in state.face(face).iter().enumerate() {
+            let x = x0 + opts.gap + (i % 3) as f32 * pitch;
+            let y = y0 + opts.gap + (i / 3) as f32 * pitch;
+            rect(&mut out, x, y, opts.sticker_size, opts.sticker_size, opts.sticker_size / 10.0, &opts.fill(color));
+        }
+    }
+    out.push_str("</svg>");
+    out
+}
+
+/// Pairs of U facelets (as indices `0..9` into the U face) that a sequence of
+/// moves sends one to the other, as drawn by the arrows of a PLL diagram.
+/// Stickers that leave the U face are ignored.
+pub fn pll_arrows(moves: impl IntoIterator<Item = Move>) -> Vec<(usize, usize)> {
+    let perm = CubeState::facelet_permutation(moves);
+    (0..9)
+        .filter_map(|i| {
+            let target = perm[U * 9 + i];
+            if target / 9 == U && target != U * 9 + i {
+                Some((i, target % 9))
+            } else {
+                None
+            }
+        })
+        .collect()
+}
+
+/// The U face seen from above, with the top row of stickers of each side face
+/// drawn around it, as in OLL and PLL diagrams. If `arrows` is given, the
+/// movement of U layer pieces under that sequence is drawn on top.
+pub fn top_view(state: &CubeState, arrows: Option<&[Move]>, opts: &DiagramOptions) -> String {
+    let pitch = opts.pitch();
+    let side = opts.sticker_size / 3.0;
+    let face_size = 3.0 * pitch + opts.gap;
+    let size = face_size + 2.0 * (side + opts.gap);
+    let radius = opts.sticker_size / 10.0;
+
+    let mut out = String::new();
+    svg_open(&mut out, 0.0, 0.0, size, size);
+    let offset = side + opts.gap;
+    rect(&mut out, offset, offset, face_size, face_size, 0.0, &hex(opts.background));
+    let center = |i: usize| {
+        (
+            offset + opts.gap + (i % 3) as f32 * pitch + opts.sticker_size / 2.0,
+            offset + opts.gap + (i / 3) as f32 * pitch + opts.sticker_size / 2.0,
+        )
+    };
+    for (i, &color) in state.face(U).iter().enumerate() {
+        let (x, y) = center(i);
+        let half = opts.sticker_size / 2.0;
+        rect(&mut out, x - half, y - half, opts.sticker_size, opts.sticker_size, radius, &opts.fill(color));
+    }
+
+    // The top row of each side face, indexed by the U face column or row it's next to.
+    let long = opts.sticker_size;
+    let near = offset + opts.gap;
+    let far = offset + face_size;
+    for k in 0..3 {
+        let along = near + k as f32 * pitch;
+        // B, read from behind, runs right to left above the U face.
+        rect(&mut out, along, 0.0, long, side, radius / 2.0, &opts.fill(state.face(5)[2 - k]));
+        // F runs left to right below it.
+        rect(&mut out, along, far, long, side, radius / 2.0, &opts.fill(state.face(2)[k]));
+        // L runs back to front on the left.
+        rect(&mut out, 0.0, along, side, long, radius / 2.0, &opts.fill(state.face(0)[k]));
+        // R runs front to back on the right.
+        rect(&mut out, far, along, side, long, radius / 2.0, &opts.fill(state.face(4)[2 - k]));
+    }
+
+    if let Some(moves) = arrows {
+        let arrows = pll_arrows(moves.iter().copied());
+        let head = opts.sticker_size / 4.0;
+        write!(
+            out,
+            r#"<defs><marker id="arrowhead" viewBox="0 0 10 10" refX="10" refY="5" markerUnits="userSpaceOnUse" markerWidth="{}" markerHeight="{}" orient="auto-start-reverse"><path d="M0,0 L10,5 L0,10 z" fill="black"/></marker></defs>"#,
+            head, head
+        ).unwrap();
+        for &(from, to) in &arrows {
+            let swap = arrows.contains(&(to, from));
+            if swap && to < from {
+                // Drawn once, with a head at each end.
+                continue;
+            }
+            let (x1, y1) = center(from);
+            let (x2, y2) = center(to);
+            write!(
+                out,
+                r#"<line x1="{}" y1="{}" x2="{}" y2="{}" stroke="black" stroke-width="{}" marker-end="url(#arrowhead)"{}/>"#,
+                x1, y1, x2, y2, opts.sticker_size / 12.0,
+                if swap { r#" marker-start="url(#arrowhead)""# } else { "" }
+            ).unwrap();
+        }
+    }
+    out.push_str("</svg>");
+    out
+}
+
+/// The cube seen from above its front-right-top corner, showing the U, F and
+/// R faces in isometric projection.
+pub fn isometric(state: &CubeState, opts: &DiagramOptions) -> String {
+    let scale = opts.pitch();
+    let cos30 = 30f32.to_radians().cos();
+    let project = |p: Vec3| ((p.x - p.z) * cos30 * scale, ((p.x + p.z) / 2.0 - p.y) * scale);
+    let quad = |center: Vec3, u: Vec3, v: Vec3, half: f32| {
+        [
+            project(center + (u + v) * half),
+            project(center + (u - v) * half),
+            project(center - (u + v) * half),
+            project(center - (u - v) * half),
+        ]
+    };
+    let half_sticker = 0.5 * opts.sticker_size / opts.pitch();
+
+    let (width, height) = (6.0 * cos30 * scale + 2.0 * opts.gap, 6.0 * scale + 2.0 * opts.gap);
+    let mut out = String::new();
+    svg_open(&mut out, -width / 2.0, -height / 2.0, width, height);
+    // U, F and R, in facelet string order.
+    for face in [1, 2, 4] {
+        let (_, n) = crate::rubik::facelet_geometry(face * 9);
+        let normal = Vec3::new(n.0 as f32, n.1 as f32, n.2 as f32);
+        let u = if normal.y == 0.0 { Vec3::unit_y() } else { Vec3::unit_x() };
+        let v = normal.cross(u).normalize();
+        polygon(&mut out, &quad(normal * 1.5, u, v, 1.5), &hex(opts.background));
+        for (i, &color) in state.face(face).iter().enumerate() {
+            let (p, _) = crate::rubik::facelet_geometry(face * 9 + i);
+            let center = Vec3::new(p.0 as f32, p.1 as f32, p.2 as f32) + normal * 0.5;
+            polygon(&mut out, &quad(center, u, v, half_sticker), &opts.fill(color));
+        }
+    }
+    out.push_str("</svg>");
+    out
+}
@@ -0,0 +1,200 @@
+//! Offscreen rendering of a cube to still images and animations.
+//!
+//! Frames are drawn into a texture instead of the screen, so this works with
+//! any context: a canvas on the web, or a `three_d::HeadlessContext` natively
+//! (enable the `headless` feature). Animations are rendered at a fixed frame
+//! rate, independently of wall-clock time.
+
+use three_d::{
+    Camera, ClearState, Context, DepthTexture2D, Interpolation, RenderTarget, Srgba, Texture2D, Viewport,
+    Wrapping,
+};
+
+use crate::clock::{Clock, FixedStep};
+use crate::rubik::Cube;
+
+/// An RGBA image, row by row from the top.
+#[derive(Clone, Debug)]
+pub struct Frame {
+    pub width: u32,
+    pub height: u32,
+    pub pixels: Vec<[u8; 4]>,
+}
+
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "camelCase", default, deny_unknown_fields))]
+pub struct ExportOptions {
+    pub width: u32,
+    pub height: u32,
+    /// Frames per second of rendered animations.
+    pub frame_rate: f32,
+    #[cfg_attr(feature = "serde", serde(with = "crate::serialize::rgba"))]
+    pub background: Srgba,
+}
+
+impl ExportOptions {
+    /// Check that the size is not empty and the frame rate is a positive
+    /// number, so that rendering ends.
+    pub fn validate(&self) -> Result<(), String> {
+        if self.width == 0 || self.height == 0 {
+            return Err(format!("Invalid export size {}x{}", self.width, self.height));
+        }
+        check_frame_rate(self.frame_rate)
+    }
+}
+
+fn check_frame_rate(frame_rate: f32) -> Result<(), String> {
+    if frame_rate.is_finite() && frame_rate > 0.0 {
+        Ok(())
+    } else {
+        Err(format!("Invalid frame rate {}", frame_rate))
+    }
+}
+
+impl Default for ExportOptions {
+    fn default() -> Self {
+        ExportOptions {
+            width: 256,
+            height: 256,
+            frame_rate: 30.0,
+            background: Srgba::WHITE,
+        }
+    }
+}
+
+/// Draw the cube as seen by the camera into an offscreen target and read it
+/// back. Fails if the options are invalid.
+pub fn render_frame(context: &Context, camera: &Camera, cube: &Cube, opts: &ExportOptions) -> Result<Frame, String> {
+    opts.validate()?;
+    let mut color = Texture2D::new_empty::<[u8; 4]>(
+        context,
+        opts.width,
+        opts.height,
+        Interpolation::Nearest,
+        Interpolation::Nearest,
+        None,
+        Wrapping::ClampToEdge,
+        Wrapping::ClampToEdge,
+    );
+    let mut depth = DepthTexture2D::new::<f32>(
+        context,
+        opts.width,
+        opts.height,
+        Wrapping::ClampToEdge,
+        Wrapping::ClampToEdge,
+    );
+    let mut camera = camera.clone();
+    camera.set_viewport(Viewport::new_at_origo(opts.width, opts.height));
+
+    let [r, g, b, a]: [f32; 4] = opts.background.into();
+    let pixels = RenderTarget::new(color.as_color_target(None), depth.as_depth_target())
+        .clear(ClearState::color_and_depth(r, g, b, a, 1.0))
+        .render(&camera, cube, &[])
+        .read_color::<[u8; 4]>();
+    Ok(Frame { width: opts.width, height: opts.height, pixels })
+}
+
+/// Play every move queued on the cube, rendering a frame every `1 / frame_rate`
+/// seconds of animation time. The first frame shows the cube before any move.
+pub fn render_animation(
+    context: &Context,
+    camera: &Camera,
+    cube: &mut Cube,
+    opts: &ExportOptions,
+) -> Result<Vec<Frame>, String> {
+    opts.validate()?;
+    render_animation_with_clock(context, camera, cube, opts, &mut FixedStep::with_frame_rate(opts.frame_rate as f64))
+}
+
+/// Play every move queued on the cube, rendering a frame at every tick of a
+/// clock until the cube is idle.
+pub fn render_animation_with_clock(
+    context: &Context,
+    camera: &Camera,
+    cube: &mut Cube,
+    opts: &ExportOptions,
+    clock: &mut impl Clock,
+) -> Result<Vec<Frame>, String> {
+    let mut frames = vec![];
+    loop {
+        cube.animate(clock.tick() as f32);
+        frames.push(render_frame(context, camera, cube, opts)?);
+        if cube.is_idle() {
+            return Ok(frames);
+        }
+    }
+}
+
+/// Encode a single frame as a PNG image.
+pub fn encode_png(frame: &Frame) -> Result<Vec<u8>, String> {
+    encode_apng(std::slice::from_ref(frame), 0.0)
+}
+
+/// Encode frames as an animated PNG that loops forever. A single frame is
+/// written as a plain PNG image.
+pub fn encode_apng(frames: &[Frame], frame_rate: f32) -> Result<Vec<u8>, String> {
+    let first = frames.first().ok_or("No frames to encode")?;
+    let mut out = vec![];
+    let mut encoder = png::Encoder::new(&mut out, first.width, first.height);
+    encoder.set_color(png::ColorType::Rgba);
+    encoder.set_depth(png::BitDepth::Eight);
+    if frames.len() > 1 {
+        check_frame_rate(frame_rate)?;
+        encoder.set_animated(frames.len() as u32, 0).map_err(|e| e.to_string())?;
+        encoder.set_frame_delay((1000.0 / frame_rate).round() as u16, 1000).map_err(|e| e.to_string())?;
+    }
+    let mut writer = encoder.write_header().map_err(|e| e.to_string())?;
+    for frame in frames {
+        writer.write_image_data(frame.pixels.as_flattened()).map_err(|e| e.to_string())?;
+    }
+    writer.finish().map_err(|e| e.to_string())?;
+    Ok(out)
+}
+
+/// Encode frames as an animated GIF that loops forever.
+pub fn encode_gif(frames: &[Frame], frame_rate: f32) -> Result<Vec<u8>, String> {
+    let first = frames.first().ok_or("No frames to encode")?;
+    let size = |n: u32| u16::try_from(n).map_err(|_| format!("GIF images are at most {} pixels wide and high", u16::MAX));
+    let (width, height) = (size(first.width)?, size(first.height)?);
+    check_frame_rate(frame_rate)?;
+    let mut out = vec![];
+    {
+        let mut encoder = gif::Encoder::new(&mut out, width, height, &[]).map_err(|e| e.to_string())?;
+        encoder.set_repeat(gif::Repeat::Infinite).map_err(|e| e.to_string())?;
+        for frame in frames {
+            let mut pixels = frame.pixels.as_flattened().to_vec();
+            let mut gif_frame = gif::Frame::from_rgba_speed(width, height, &mut pixels, 10);
+            // GIF delays are in hundredths of a second.
+            gif_frame.delay = (100.0 / frame_rate).round() as u16;
+            encoder.write_frame(&gif_frame).map_err(|e| e.to_string())?;
+        }
+    }
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn gif_size_limit() {
+        let frame = |width| Frame { width, height: 1, pixels: vec![[0, 0, 0, 255]; width as usize] };
+        assert!(encode_gif(&[frame(u16::MAX as u32)], 30.0).is_ok());
+        assert!(encode_gif(&[frame(u16::MAX as u32 + 1)], 30.0).is_err());
+    }
+
+    #[test]
+    fn frame_rates() {
+        let frame = Frame { width: 1, height: 1, pixels: vec![[0, 0, 0, 255]] };
+        for frame_rate in [0.0, -30.0, f32::NAN, f32::INFINITY] {
+            let opts = ExportOptions { frame_rate, ..ExportOptions::default() };
+            assert!(opts.validate().is_err());
+            assert!(encode_gif(std::slice::from_ref(&frame), frame_rate).is_err());
+            assert!(encode_apng(&[frame.clone(), frame.clone()], frame_rate).is_err());
+        }
+        assert!(ExportOptions::default().validate().is_ok());
+        assert!(ExportOptions { width: 0, ..ExportOptions::default() }.validate().is_err());
+        // A still image has no frame rate.
+        assert!(encode_png(&frame).is_ok());
+    }
+}
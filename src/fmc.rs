@@ -0,0 +1,461 @@
+//! Fewest-moves tools: normal/inverse scramble switching (NISS), reports of
+//! what a partial solution leaves unsolved, and an insertion finder that
+//! solves leftover 3-cycles by inserting commutators into a skeleton.
+//!
+//! Everything here works with face turns only, as in FMC competitions.
+
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::str::FromStr;
+use std::sync::OnceLock;
+
+use crate::rubik::{CubieCube, Cycle, Move, CORNER_NAMES, EDGE_NAMES};
+
+/// A solution in progress, found partly on the normal scramble and partly
+/// on the inverse scramble. Written with the moves found on the inverse in
+/// parentheses, as in `R U (F' D) L`.
+#[derive(Clone, PartialEq, Eq, Hash, Default, Debug)]
+pub struct Niss {
+    /// Moves found on the normal scramble.
+    pub normal: Vec<Move>,
+    /// Moves found on the inverse scramble.
+    pub inverse: Vec<Move>,
+}
+
+impl Niss {
+    pub fn new(normal: Vec<Move>, inverse: Vec<Move>) -> Niss {
+        Niss { normal, inverse }
+    }
+
+    /// The same moves seen from the other side.
+    pub fn switch(&self) -> Niss {
+        Niss::new(self.inverse.clone(), self.normal.clone())
+    }
+
+    /// The position to continue from on the normal side: the inverse moves,
+    /// undone, as premoves, then the scramble and the normal moves.
+    pub fn normal_scramble(&self, scramble: &[Move]) -> Vec<Move> {
+        [&Move::invert_sequence(&self.inverse)[..], scramble, &self.normal].concat()
+    }
+
+    /// The position to continue from on the inverse side: the normal moves
+    /// undone as premoves, then the inverse scramble and the inverse moves.
+    pub fn inverse_scramble(&self, scramble: &[Move]) -> Vec<Move> {
+        [&Move::invert_sequence(&self.normal)[..], &Move::invert_sequence(scramble), &self.inverse].concat()
+    }
+
+    /// The moves solving the scramble, once either side is solved: the
+    /// normal moves, then the inverse moves undone.
+    pub fn solution(&self) -> Vec<Move> {
+        [&self.normal[..], &Move::invert_sequence(&self.inverse)].concat()
+    }
+}
+
+impl fmt::Display for Niss {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&Move::to_sequence(&self.normal))?;
+        if !self.inverse.is_empty() {
+            if !self.normal.is_empty() {
+                f.write_str(" ")?;
+            }
+            write!(f, "({})", Move::to_sequence(&self.inverse))?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for Niss {
+    type Err = String;
+
+    /// Parse moves with the inverse ones in parentheses, such as
+    /// `R U (F' D) L (B)`.
+    fn from_str(input: &str) -> Result<Niss, String> {
+        let mut niss = Niss::default();
+        let mut on_inverse = false;
+        for token in input.replace('(', " ( ").replace(')', " ) ").split_whitespace() {
+            match token {
+                "(" if on_inverse => return Err("Nested parentheses".to_string()),
+                "(" => on_inverse = true,
+                ")" if !on_inverse => return Err("Unexpected )".to_string()),
+                ")" => on_inverse = false,
+                _ if on_inverse => niss.inverse.push(token.parse()?),
+                _ => niss.normal.push(token.parse()?),
+            }
+        }
+        if on_inverse {
+            return Err("Missing )".to_string());
+        }
+        Ok(niss)
+    }
+}
+
+fn check_face_turns(moves: &[Move]) -> Result<(), String> {
+    match moves.iter().find(|mv| !mv.is_face_turn()) {
+        Some(mv) => Err(format!("{} is not a face turn", mv)),
+        None => Ok(()),
+    }
+}
+
+fn cubies(moves: &[Move]) -> CubieCube {
+    let mut cube = CubieCube::SOLVED;
+    cube.apply_all(moves.iter().copied());
+    cube
+}
+
+/// What a partial solution leaves unsolved.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Leftover {
+    pub cube: CubieCube,
+    pub corner_cycles: Vec<Cycle>,
+    pub edge_cycles: Vec<Cycle>,
+}
+
+impl Leftover {
+    /// The pieces left unsolved by a scramble followed by a partial solution.
+    pub fn new(scramble: &[Move], moves: &[Move]) -> Result<Leftover, String> {
+        check_face_turns(scramble)?;
+        check_face_turns(moves)?;
+        let cube = cubies(&[scramble, moves].concat());
+        Ok(Leftover { cube, corner_cycles: cube.corner_cycles(), edge_cycles: cube.edge_cycles() })
+    }
+
+    pub fn unsolved_corners(&self) -> usize {
+        self.corner_cycles.iter().map(|c| c.slots.len()).sum()
+    }
+
+    pub fn unsolved_edges(&self) -> usize {
+        self.edge_cycles.iter().map(|c| c.slots.len()).sum()
+    }
+
+    pub fn is_solved(&self) -> bool {
+        self.cube.is_solved()
+    }
+}
+
+fn write_cycles(f: &mut fmt::Formatter<'_>, kind: &str, cycles: &[Cycle], names: &[&str]) -> fmt::Result {
+    let count: usize = cycles.iter().map(|c| c.slots.len()).sum();
+    if count == 0 {
+        return writeln!(f, "{}: solved", kind);
+    }
+    write!(f, "{}: {} unsolved,", kind, count)?;
+    for cycle in cycles {
+        let slots: Vec<&str> = cycle.slots.iter().map(|&s| names[s]).collect();
+        let description = match (cycle.slots.len(), cycle.orientation) {
+            (1, _) if kind == "Corners" => "twisted".to_string(),
+            (1, _) => "flipped".to_string(),
+            (n, 0) => format!("{}-cycle", n),
+            (n, _) => format!("{}-cycle, misoriented", n),
+        };
+        write!(f, " {} ({})", slots.join(" "), description)?;
+    }
+    writeln!(f)
+}
+
+impl fmt::Display for Leftover {
+    /// A line for corners and one for edges, such as
+    /// `Corners: 3 unsolved, URF UBR DFR (3-cycle)`.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write_cycles(f, "Corners", &self.corner_cycles, &CORNER_NAMES)?;
+        write_cycles(f, "Edges", &self.edge_cycles, &EDGE_NAMES)
+    }
+}
+
+/// A solution found by inserting commutators into a skeleton.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Insertion {
+    /// Where each commutator goes, as a number of moves of the skeleton
+    /// (with the earlier insertions already made) it comes after.
+    pub positions: Vec<usize>,
+    pub commutators: Vec<Vec<Move>>,
+    /// The whole solution, with cancelling moves merged.
+    pub moves: Vec<Move>,
+    /// How many moves cancelled.
+    pub cancelled: usize,
+}
+
+/// How many algorithms for each insertion are tried together with the
+/// algorithms for the others.
+const ALGS_PER_INSERTION: usize = 4;
+
+/// Pure 3-cycles of corners or edges, by their effect: 8 move corner
+/// commutators `[X Y X', Z]` and 10 move edge commutators `[X W Y W' X', Z]`
+/// with `X W` turning two opposite faces like a slice, each also conjugated
+/// by a face turn.
+fn three_cycles() -> &'static ThreeCycles {
+    static TABLE: OnceLock<ThreeCycles> = OnceLock::new();
+    TABLE.get_or_init(|| {
+        let turns = Move::FACE_TURNS;
+        let mut parts: Vec<Vec<Move>> = vec![];
+        for x in turns {
+            for y in turns.into_iter().filter(|y| y.axis() != x.axis()) {
+                parts.push(vec![x, y, x.inverse()]);
+                for w in turns.into_iter().filter(|w| w.axis() == x.axis() && w.base() != x.base()) {
+                    // Opposite faces turned the same way as seen from one side.
+                    if w.amount() == x.inverse().amount() {
+                        parts.push(vec![x, w, y, w.inverse(), x.inverse()]);
+                    }
+                }
+            }
+        }
+        let mut algs: HashMap<CubieCube, Vec<Vec<Move>>> = HashMap::new();
+        let mut add = |moves: Vec<Move>| {
+            let cube = cubies(&moves);
+            let moved = unsolved_mask(&cube);
+            let corners = moved & 0xff;
+            if moved.count_ones() == 3 && (corners == 0 || corners == moved) {
+                let algs = algs.entry(cube).or_default();
+                if !algs.contains(&moves) {
+                    algs.push(moves);
+                }
+            }
+        };
+        for a in &parts {
+            for z in turns.into_iter().filter(|z| z.axis() != a[0].axis()) {
+                let b = [z];
+                for (first, second) in [(&a[..], &b[..]), (&b[..], &a[..])] {
+                    let commutator = [first, second, &Move::invert_sequence(first), &Move::invert_sequence(second)].concat();
+                    add(commutator.clone());
+                    for s in turns {
+                        add([&[s][..], &commutator, &[s.inverse()]].concat());
+                    }
+                }
+            }
+        }
+        let effects = algs.keys().map(|&cube| (cube, unsolved_mask(&cube))).collect();
+        ThreeCycles { algs, effects }
+    })
+}
+
+struct ThreeCycles {
+    algs: HashMap<CubieCube, Vec<Vec<Move>>>,
+    /// Each effect with the pieces it moves, as from [`unsolved_mask`].
+    effects: Vec<(CubieCube, u32)>,
+}
+
+/// The unsolved pieces of a cube as bits: corners first, then edges.
+fn unsolved_mask(cube: &CubieCube) -> u32 {
+    let corners = (0..8).filter(|&s| !cube.is_corner_solved(s)).map(|s| 1 << s);
+    let edges = (0..12).filter(|&s| !cube.is_edge_solved(s)).map(|s| 1 << (8 + s));
+    corners.chain(edges).sum()
+}
+
+/// Search for ways to finish a skeleton by inserting up to `max_insertions`
+/// commutators between its moves, each a pure 3-cycle of corners or edges
+/// that brings the cube closer to solved. Results are sorted by length,
+/// shortest first; at most `max_results` are returned.
+///
+/// Twisted corners and flipped edges are not supported: when nothing is
+/// found and the skeleton leaves some, that is an error.
+pub fn find_insertions(
+    scramble: &[Move],
+    skeleton: &[Move],
+    max_insertions: usize,
+    max_results: usize,
+) -> Result<Vec<Insertion>, String> {
+    check_face_turns(scramble)?;
+    check_face_turns(skeleton)?;
+    let start = cubies(scramble);
+    // The cube after the scramble and the first moves of the skeleton.
+    let prefixes: Vec<CubieCube> = (0..=skeleton.len()).map(|p| start.multiply(&cubies(&skeleton[..p]))).collect();
+    let suffixes: Vec<CubieCube> = (0..=skeleton.len()).map(|p| cubies(&skeleton[p..])).collect();
+    let mut plans = vec![];
+    let search = PlanSearch { skeleton, prefixes: &prefixes, suffixes: &suffixes, max_insertions };
+    search.search(start, 0, &mut vec![], &mut plans);
+
+    // The algorithms for each insertion that cancel most with the skeleton
+    // on their own, so that only a few combinations have to be tried.
+    let mut best: HashMap<(usize, CubieCube), Vec<&Vec<Move>>> = HashMap::new();
+    let mut results: Vec<Insertion> = vec![];
+    for plan in plans {
+        let choices: Vec<Vec<&Vec<Move>>> = plan.iter().map(|&(position, effect)| {
+            best.entry((position, effect)).or_insert_with(|| {
+                let mut algs: Vec<&Vec<Move>> = three_cycles().algs[&effect].iter().collect();
+                algs.sort_by_key(|alg| {
+                    Move::simplify(&[&skeleton[..position], alg, &skeleton[position..]].concat()).len()
+                });
+                algs.truncate(ALGS_PER_INSERTION);
+                algs
+            }).clone()
+        }).collect();
+        let mut index = vec![0; plan.len()];
+        loop {
+            let mut moves = vec![];
+            let mut positions = vec![];
+            let mut last = 0;
+            for (i, &(position, _)) in plan.iter().enumerate() {
+                moves.extend(&skeleton[last..position]);
+                positions.push(moves.len());
+                moves.extend(choices[i][index[i]]);
+                last = position;
+            }
+            moves.extend(&skeleton[last..]);
+            let simplified = Move::simplify(&moves);
+            results.push(Insertion {
+                positions,
+                commutators: (0..plan.len()).map(|i| choices[i][index[i]].clone()).collect(),
+                cancelled: moves.len() - simplified.len(),
+                moves: simplified,
+            });
+            // On to the next combination.
+            let Some(i) = (0..plan.len()).rev().find(|&i| index[i] + 1 < choices[i].len()) else {
+                break;
+            };
+            index[i] += 1;
+            index[i + 1..].fill(0);
+        }
+    }
+    if results.is_empty() {
+        let end = prefixes[skeleton.len()];
+        let in_place = |cycles: Vec<Cycle>, names: &[&'static str]| -> Vec<&'static str> {
+            cycles.iter().filter(|c| c.slots.len() == 1).map(|c| names[c.slots[0]]).collect()
+        };
+        let pieces = [in_place(end.corner_cycles(), &CORNER_NAMES), in_place(end.edge_cycles(), &EDGE_NAMES)].concat();
+        if !pieces.is_empty() {
+            return Err(format!(
+                "Twisted corners and flipped edges ({}) can't be solved by inserting 3-cycles",
+                pieces.join(" ")
+            ));
+        }
+    }
+    results.sort_by_key(|r| r.moves.len());
+    let mut seen = HashSet::new();
+    results.retain(|r| seen.insert(r.moves.clone()));
+    results.truncate(max_results);
+    Ok(results)
+}
+
+/// How many pure 3-cycles it takes at least to solve a cube: a cycle of `n`
+/// pieces takes `(n - 1) / 2` if it is odd and oriented, and each even or
+/// misoriented cycle adds half a 3-cycle more.
+fn three_cycles_needed(cube: &CubieCube) -> usize {
+    let needed = |cycles: Vec<Cycle>| {
+        let moved: usize = cycles.iter().map(|c| c.slots.len()).sum();
+        let odd = cycles.iter().filter(|c| c.slots.len() % 2 == 1 && c.orientation == 0).count();
+        (moved - odd).div_ceil(2)
+    };
+    needed(cube.corner_cycles()) + needed(cube.edge_cycles())
+}
+
+/// Looks for the positions and effects of insertions solving a skeleton,
+/// without choosing algorithms yet.
+struct PlanSearch<'a> {
+    skeleton: &'a [Move],
+    prefixes: &'a [CubieCube],
+    suffixes: &'a [CubieCube],
+    max_insertions: usize,
+}
+
+impl PlanSearch<'_> {
+    /// `cube` is the cube after the scramble, the skeleton up to `position`
+    /// and the insertions of `plan`.
+    fn search(
+        &self,
+        cube: CubieCube,
+        position: usize,
+        plan: &mut Vec<(usize, CubieCube)>,
+        plans: &mut Vec<Vec<(usize, CubieCube)>>,
+    ) {
+        let before = three_cycles_needed(&cube.multiply(&self.suffixes[position]));
+        if before == 0 {
+            if !plan.is_empty() {
+                plans.push(plan.clone());
+            }
+            return;
+        }
+        if before > self.max_insertions - plan.len() {
+            return;
+        }
+        let undo_prefix = self.prefixes[position].inverse();
+        for next in position..=self.skeleton.len() {
+            // The cube just before the insertion point.
+            let at = cube.multiply(&undo_prefix.multiply(&self.prefixes[next]));
+            // Seen from the end of the skeleton, a useful 3-cycle only moves
+            // pieces that are unsolved there.
+            let unsolved = unsolved_mask(&self.suffixes[next].multiply(&at));
+            for (effect, moved) in &three_cycles().effects {
+                if moved & !unsolved != 0 {
+                    continue;
+                }
+                let after = at.multiply(effect);
+                if three_cycles_needed(&after.multiply(&self.suffixes[next])) >= before {
+                    continue;
+                }
+                plan.push((next, *effect));
+                self.search(after, next, plan, plans);
+                plan.pop();
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn moves(sequence: &str) -> Vec<Move> {
+        Move::from_sequence(sequence).unwrap().collect()
+    }
+
+    #[test]
+    fn niss_notation() {
+        let niss: Niss = "R U (F' D) L (B)".parse().unwrap();
+        assert_eq!(niss, Niss::new(moves("R U L"), moves("F' D B")));
+        assert_eq!(niss.to_string(), "R U L (F' D B)");
+        assert_eq!(niss.to_string().parse(), Ok(niss.clone()));
+        assert_eq!(niss.switch().to_string(), "F' D B (R U L)");
+        assert_eq!(Niss::new(vec![], moves("R")).to_string(), "(R)");
+        assert_eq!("".parse(), Ok(Niss::default()));
+        for invalid in ["R (U (F))", "R )", "(R", "R (X)"] {
+            assert!(invalid.parse::<Niss>().is_err(), "{}", invalid);
+        }
+    }
+
+    #[test]
+    fn niss_solution() {
+        let scramble = moves("R U F");
+        // F' on the normal scramble, then R U on the inverse.
+        let niss = Niss::new(moves("F'"), moves("R"));
+        assert_eq!(Move::simplify(&niss.inverse_scramble(&scramble)), moves("U'"));
+        let niss = Niss::new(moves("F'"), moves("R U"));
+        assert!(cubies(&niss.inverse_scramble(&scramble)).is_solved());
+        assert!(cubies(&niss.normal_scramble(&scramble)).is_solved());
+        assert_eq!(niss.solution(), moves("F' U' R'"));
+        assert!(cubies(&[&scramble[..], &niss.solution()].concat()).is_solved());
+        assert_eq!(niss.switch().solution(), Move::invert_sequence(&niss.solution()));
+    }
+
+    #[test]
+    fn leftover() {
+        let leftover = Leftover::new(&moves("R U R' D R U' R' D'"), &[]).unwrap();
+        assert_eq!((leftover.unsolved_corners(), leftover.unsolved_edges()), (3, 0));
+        assert!(leftover.to_string().contains("(3-cycle)"));
+        assert!(leftover.to_string().ends_with("Edges: solved\n"));
+        assert!(Leftover::new(&moves("R"), &moves("R'")).unwrap().is_solved());
+        assert!(Leftover::new(&moves("M"), &[]).is_err());
+    }
+
+    #[test]
+    fn insertions_solve() {
+        let skeleton = moves("F2 L B' D R2 U' F");
+        for cycles in ["R U R' D R U' R' D'", "R U R' D R U' R' D' F R' F' L' F R F' L"] {
+            let scramble = Move::invert_sequence(&[&skeleton[..2], &moves(cycles), &skeleton[2..]].concat());
+            let found = find_insertions(&scramble, &skeleton, 2, 10).unwrap();
+            assert!(!found.is_empty(), "{}", cycles);
+            for insertion in &found {
+                assert!(cubies(&[&scramble[..], &insertion.moves].concat()).is_solved());
+                assert_eq!(insertion.positions.len(), insertion.commutators.len());
+            }
+            assert!(found.windows(2).all(|pair| pair[0].moves.len() <= pair[1].moves.len()));
+        }
+    }
+
+    #[test]
+    fn twists_are_reported() {
+        let skeleton = moves("F L2 B U2");
+        let twist = moves("R' D' R D R' D' R D U D' R' D R D' R' D R U'");
+        let scramble = Move::invert_sequence(&[&skeleton[..], &twist].concat());
+        let leftover = Leftover::new(&scramble, &skeleton).unwrap();
+        assert_eq!(leftover.unsolved_corners(), 2);
+        assert!(find_insertions(&scramble, &skeleton, 2, 10).is_err());
+    }
+}
@@ -1,30 +1,41 @@
 pub mod rubik;
+#[cfg(target_arch = "wasm32")]
 pub mod canvas;
+pub mod scene;
+pub mod clock;
+pub mod diagram;
+pub mod solver;
+pub mod scramble;
+pub mod algorithm;
+pub mod library;
+pub mod trainer;
+pub mod share;
+pub mod reconstruction;
+pub mod bld;
+pub mod fmc;
+pub mod cfop;
+#[cfg(feature = "serde")]
+pub mod serialize;
+#[cfg(feature = "export")]
+pub mod export;
+#[cfg(all(feature = "viewer", not(target_arch = "wasm32")))]
+pub mod native;
+#[cfg(target_arch = "wasm32")]
+mod web;
 
-use rubik::CubeAnimationOptions;
-use three_d::{Angle, Camera, ClearState, DirectionalLight, InnerSpace, Quaternion, Rad, RenderTarget, Srgba, Vec3, Viewport, Zero};
-use wasm_bindgen::prelude::*;
-use web_sys::HtmlCanvasElement;
+use three_d::{Angle, Camera, InnerSpace, Quaternion, Rad, Srgba, Vec3};
+#[cfg(target_arch = "wasm32")]
+pub use web::*;
 
-#[wasm_bindgen(typescript_custom_section)]
-const CANVAS_OPTS: &'static str = r#"
-interface CanvasOptions {
-    alpha?: bool,
-    antialias?: bool,
-    depth?: bool,
-    stencil?: bool,
-    premultipliedAlpha?: bool,
-    failIfMajorPerformanceCaveat?: bool,
-    desynchronized?: bool,
-    powerPreference?: "default" | "high-performance" | "low-power",
-    preserveDrawingBuffer?: bool,
-}
-"#;
-
-#[wasm_bindgen]
-extern "C" {
-    #[wasm_bindgen(typescript_type = "CanvasOptions")]
-    pub type CanvasOptions;
+/// Parse a `#rrggbb` or `#rrggbbaa` color.
+pub fn parse_rgba(s: &str) -> Result<Srgba, String> {
+    let hex = s.strip_prefix('#').ok_or(format!("Invalid color {}", s))?;
+    if !(hex.len() == 6 || hex.len() == 8) || !hex.is_ascii() {
+        return Err(format!("Invalid color {}", s));
+    }
+    let channel = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16).map_err(|_| format!("Invalid color {}", s));
+    let alpha = if hex.len() == 8 { channel(6)? } else { 255 };
+    Ok(Srgba::new(channel(0)?, channel(2)?, channel(4)?, alpha))
 }
 
 pub(crate) fn ease(t: f32, a: f32) -> f32 {
@@ -57,44 +68,5 @@ pub fn rotate_around_axis(vector: Vec3, axis: Vec3, angle: f32) -> Vec3 {
     let angle = Rad(angle / 2.0);
     let q = Quaternion::from_sv(angle.cos(), angle.sin() * axis).normalize();
     let p = Quaternion::from_sv(0.0, vector).normalize();
-    let rotated = (q * p * q.conjugate()).v;
-    rotated
-}
-
-#[wasm_bindgen]
-pub fn bind(canvas_element: HtmlCanvasElement, opts: Option<CanvasOptions>) -> Result<(), JsValue> {
-    #[cfg(feature = "debug")]
-    console_error_panic_hook::set_once();
-
-    let window = canvas::Canvas::new(canvas_element, opts.map(|o| o.into()))
-        .map_err(|e| JsValue::from(e))?;
-    let (width, height) = window.logical_size();
-    let context = window.gl();
-    
-    let cube = rubik::Cube::solved(&context, CubeAnimationOptions::default());
-    let mut camera = Camera::new_perspective(
-        Viewport::new_at_origo(width, height),
-        Vec3::new(5.0, 0.0, 5.0),
-        Vec3::new(0.0, 0.0, 0.0),
-        Vec3::new(0.0, 1.0, 0.0),
-        three_d::degrees(45.0),
-        0.1,
-        50.0,
-    );
-    let light = DirectionalLight::new(&context, 100.0, Srgba::WHITE, &Vec3::new(1.0, 3.0, 2.5));
-
-    window.run(move |input| {
-        let t = input.time as f32;
-        let dt = input.frame_time as f32;
-
-        let theta_speed = (t/10000.0).sin()/5000.0;
-        let phi_speed = (t/10000.0).cos()/8000.0;
-        rotate_camera_around_target(&mut camera, Vec3::zero(), dt*theta_speed, dt*phi_speed);
-
-        RenderTarget::screen(&context, width, height)
-            .clear(ClearState::color_and_depth(0.0, 0.0, 0.0, 0.0, 1.0))
-            .render(&camera, &cube, &[&light]);
-    });
-
-    Ok(())
+    (q * p * q.conjugate()).v
 }
@@ -0,0 +1,356 @@
+//! The standard algorithm sets of the CFOP method: 41 F2L, 57 OLL and 21
+//! PLL cases, each with a name, recognition hints and algorithms, and
+//! recognition of which case a cube is in.
+//!
+//! Cases are recognized from stickers, relative to the centers, so any color
+//! scheme works as long as the last layer is on U.
+
+use std::collections::HashMap;
+use std::sync::OnceLock;
+
+use crate::rubik::{corner_facelets, edge_facelets, facelet_geometry, CubeState, Move, U};
+
+mod f2l;
+mod oll;
+mod pll;
+
+/// A set of cases solved by one step of a method.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum CaseSet {
+    /// Pairing a corner and edge and inserting them into the front-right slot.
+    F2l,
+    /// Orienting the last layer.
+    Oll,
+    /// Permuting the last layer.
+    Pll,
+}
+
+impl CaseSet {
+    pub const ALL: [CaseSet; 3] = [CaseSet::F2l, CaseSet::Oll, CaseSet::Pll];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            CaseSet::F2l => "F2L",
+            CaseSet::Oll => "OLL",
+            CaseSet::Pll => "PLL",
+        }
+    }
+
+    /// Every case of the set, in the usual order.
+    pub fn cases(&self) -> &'static [Case] {
+        match self {
+            CaseSet::F2l => &f2l::CASES,
+            CaseSet::Oll => &oll::CASES,
+            CaseSet::Pll => &pll::CASES,
+        }
+    }
+
+    /// A case by its name, such as `"OLL 27"`, `"Sune"` or `"T"`. A
+    /// nickname shared by several cases names none of them.
+    pub fn case(&self, name: &str) -> Option<&'static Case> {
+        if let Some(case) = self.cases().iter().find(|c| c.name == name) {
+            return Some(case);
+        }
+        let mut nicknamed = self.cases().iter().filter(|c| c.nickname == Some(name));
+        match (nicknamed.next(), nicknamed.next()) {
+            (Some(case), None) => Some(case),
+            _ => None,
+        }
+    }
+}
+
+/// A case of any set by its name, optionally after the name of its set, as
+/// in `"PLL H"`. Fails if the name is in several sets, such as `"H"`, which
+/// is both OLL 21 and a PLL.
+pub fn find_case(name: &str) -> Result<Option<&'static Case>, String> {
+    let name = name.trim();
+    if let Some((set, rest)) = name.split_once(' ') {
+        let set = CaseSet::ALL.into_iter().find(|s| s.name().eq_ignore_ascii_case(set));
+        if let Some(case) = set.and_then(|set| set.case(rest.trim())) {
+            return Ok(Some(case));
+        }
+    }
+    let found: Vec<&Case> = CaseSet::ALL.iter().filter_map(|set| set.case(name)).collect();
+    match found[..] {
+        [] => Ok(None),
+        [case] => Ok(Some(case)),
+        _ => Err(format!(
+            "{} could be {}; write the set first, as in \"{} {}\"",
+            name,
+            found.iter().map(|c| match c.name.starts_with(c.set.name()) {
+                true => c.name.to_string(),
+                false => format!("{} {}", c.set.name(), c.name),
+            }).collect::<Vec<_>>().join(" or "),
+            found[0].set.name(),
+            name,
+        )),
+    }
+}
+
+/// A case of an algorithm set.
+#[derive(Debug)]
+pub struct Case {
+    set: CaseSet,
+    name: &'static str,
+    nickname: Option<&'static str>,
+    group: &'static str,
+    recognition: &'static str,
+    algorithms: &'static [&'static str],
+}
+
+impl Case {
+    const fn new(
+        set: CaseSet,
+        name: &'static str,
+        nickname: Option<&'static str>,
+        group: &'static str,
+        recognition: &'static str,
+        algorithms: &'static [&'static str],
+    ) -> Case {
+        Case { set, name, nickname, group, recognition, algorithms }
+    }
+
+    pub fn set(&self) -> CaseSet {
+        self.set
+    }
+
+    /// The standard name, such as `"OLL 27"`, `"T"` or `"F2L 4"`.
+    pub fn name(&self) -> &'static str {
+        self.name
+    }
+
+    /// A common nickname, such as `"Sune"`.
+    pub fn nickname(&self) -> Option<&'static str> {
+        self.nickname
+    }
+
+    /// The group the case is usually learnt in, by shape or piece location.
+    pub fn group(&self) -> &'static str {
+        self.group
+    }
+
+    /// What to look for to tell the case apart, with the cube as left by
+    /// [`Case::setup`].
+    pub fn recognition(&self) -> &'static str {
+        self.recognition
+    }
+
+    /// The algorithms solving the case, in notation, most common first.
+    pub fn notations(&self) -> &'static [&'static str] {
+        self.algorithms
+    }
+
+    /// The algorithms solving the case, most common first.
+    pub fn algorithms(&self) -> Vec<Vec<Move>> {
+        self.algorithms.iter()
+            .map(|alg| Move::from_sequence(alg).expect("library algorithms are valid").collect())
+            .collect()
+    }
+
+    /// The main algorithm solving the case.
+    pub fn algorithm(&self) -> Vec<Move> {
+        self.algorithms().swap_remove(0)
+    }
+
+    /// Moves reaching the case from a solved cube: the inverse of the main
+    /// algorithm.
+    pub fn setup(&self) -> Vec<Move> {
+        Move::invert_sequence(&self.algorithm())
+    }
+
+    /// The case, reached from a solved cube.
+    pub fn setup_state(&self) -> CubeState {
+        let mut state = CubeState::solved();
+        state.apply_all(self.setup());
+        state
+    }
+}
+
+/// A case found on a cube, with the moves to do before its algorithm.
+#[derive(Clone, Copy, Debug)]
+pub struct Recognized {
+    pub case: &'static Case,
+    /// For F2L, the rotation (`y`, `y2` or `y'`) bringing the slot to the
+    /// front-right.
+    pub rotation: Option<Move>,
+    /// The U turn to do before the algorithm.
+    pub pre_auf: Option<Move>,
+    /// For PLL, the U turn that finishes the solve after the algorithm.
+    pub post_auf: Option<Move>,
+}
+
+/// For every facelet, the face whose center has its color, or `None` if
+/// two centers have the same color.
+fn faces(state: &CubeState) -> Option<[usize; 54]> {
+    let centers: Vec<_> = (0..6).map(|face| state.facelet(face * 9 + 4)).collect();
+    if (0..6).any(|i| centers[i + 1..].contains(&centers[i])) {
+        return None;
+    }
+    let mut out = [0; 54];
+    for (f, face) in out.iter_mut().enumerate() {
+        *face = centers.iter().position(|&c| c == state.facelet(f))?;
+    }
+    Some(out)
+}
+
+/// Facelets of the pieces in the U layer, in index order.
+fn top_layer() -> impl Iterator<Item = usize> {
+    (0..54).filter(|&f| facelet_geometry(f).0 .1 == 1)
+}
+
+/// Whether every facelet below the U layer is solved, or with `only_cross`,
+/// every facelet of the D layer edges.
+fn bottom_solved(faces: &[usize; 54], only_cross: bool) -> bool {
+    (0..54).all(|f| {
+        let ((x, y, z), _) = facelet_geometry(f);
+        let checked = if only_cross { y == -1 && x * z == 0 } else { y < 1 };
+        !checked || faces[f] == f / 9
+    })
+}
+
+/// What identifies a case, up to pre-AUF.
+fn key(set: CaseSet, faces: &[usize; 54]) -> Vec<usize> {
+    match set {
+        // Which last layer stickers show the U color.
+        CaseSet::Oll => top_layer().map(|f| (faces[f] == U) as usize).collect(),
+        CaseSet::Pll => top_layer().map(|f| faces[f]).collect(),
+        // Where the stickers of the front-right corner and edge are.
+        CaseSet::F2l => {
+            let pair = [2, 3, 4]; // F, D, R
+            let corner = corner_facelets().iter()
+                .find(|fs| pair.iter().all(|p| fs.iter().any(|&f| faces[f] == *p)))
+                .unwrap();
+            let edge = edge_facelets().iter()
+                .find(|fs| fs.iter().all(|&f| faces[f] == 2 || faces[f] == 4))
+                .unwrap();
+            corner.iter().chain(edge.iter()).flat_map(|&f| [f, faces[f]]).collect()
+        }
+    }
+}
+
+fn u_turn(quarters: usize) -> Option<Move> {
+    Move::from_face(U, quarters as u8)
+}
+
+type Recognition = HashMap<Vec<usize>, (&'static Case, Option<Move>, Option<Move>)>;
+
+/// For every key of a case set, the case and its pre- and post-AUF.
+fn recognition_table(set: CaseSet) -> &'static Recognition {
+    static TABLES: OnceLock<Vec<Recognition>> = OnceLock::new();
+    let tables = TABLES.get_or_init(|| {
+        CaseSet::ALL.iter().map(|&set| {
+            let mut table = HashMap::new();
+            for case in set.cases() {
+                // The cube after an AUF, the setup and another AUF; the
+                // first only matters for PLL.
+                let afters = if set == CaseSet::Pll { 4 } else { 1 };
+                for after in 0..afters {
+                    for before in 0..4 {
+                        let mut state = CubeState::solved();
+                        state.apply_all(u_turn(after));
+                        state.apply_all(case.setup());
+                        state.apply_all(u_turn(before));
+                        let key = key(set, &faces(&state).unwrap());
+                        table.entry(key).or_insert((case, u_turn(4 - before), u_turn(4 - after)));
+                    }
+                }
+            }
+            table
+        }).collect()
+    });
+    &tables[CaseSet::ALL.iter().position(|&s| s == set).unwrap()]
+}
+
+/// Whether the step solving a case set is done: for F2L the first two
+/// layers are solved, for OLL also the last layer is oriented, and for PLL
+/// the cube is solved up to an AUF.
+pub fn is_step_solved(set: CaseSet, state: &CubeState) -> bool {
+    let Some(faces) = faces(state) else {
+        return false;
+    };
+    match set {
+        CaseSet::F2l => bottom_solved(&faces, false),
+        CaseSet::Oll => bottom_solved(&faces, false) && (0..9).all(|i| faces[U * 9 + i] == U),
+        CaseSet::Pll => (0..4).any(|quarters| {
+            let mut state = state.clone();
+            state.apply_all(u_turn(quarters));
+            state.is_solved()
+        }),
+    }
+}
+
+/// Find the case of a set that a cube is in, if any.
+///
+/// OLL and PLL need the first two layers solved, and PLL the last layer
+/// oriented. F2L needs the cross solved, and looks at the slots in FR, FL,
+/// BL, BR order for a pair that is in one of the cases.
+pub fn identify(set: CaseSet, state: &CubeState) -> Option<Recognized> {
+    let table = recognition_table(set);
+    let rotations: &[Option<Move>] = match set {
+        CaseSet::F2l => &[None, Some(Move::YP), Some(Move::Y2), Some(Move::Y)],
+        _ => &[None],
+    };
+    for &rotation in rotations {
+        let mut state = state.clone();
+        state.apply_all(rotation);
+        let faces = faces(&state)?;
+        let ready = match set {
+            CaseSet::F2l => bottom_solved(&faces, true),
+            CaseSet::Oll => bottom_solved(&faces, false),
+            CaseSet::Pll => bottom_solved(&faces, false) && (0..9).all(|i| faces[U * 9 + i] == U),
+        };
+        if !ready {
+            return None;
+        }
+        if let Some(&(case, pre_auf, post_auf)) = table.get(&key(set, &faces)) {
+            return Some(Recognized {
+                case,
+                rotation,
+                pre_auf,
+                post_auf: if set == CaseSet::Pll { post_auf } else { None },
+            });
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn names() {
+        assert_eq!(CaseSet::Oll.case("Sune").map(Case::name), Some("OLL 27"));
+        assert_eq!(CaseSet::Oll.case("H").map(Case::name), Some("OLL 21"));
+        assert_eq!(CaseSet::Pll.case("H").map(Case::name), Some("H"));
+        for set in CaseSet::ALL {
+            for case in set.cases() {
+                assert!(std::ptr::eq(set.case(case.name).unwrap(), case));
+                if let Some(nickname) = case.nickname {
+                    assert!(std::ptr::eq(set.case(nickname).unwrap(), case), "{}", nickname);
+                }
+            }
+        }
+        assert!(find_case("H").is_err());
+        assert_eq!(find_case("PLL H").unwrap().map(Case::set), Some(CaseSet::Pll));
+        assert_eq!(find_case("oll H").unwrap().map(Case::name), Some("OLL 21"));
+        assert_eq!(find_case("OLL 21").unwrap().map(Case::name), Some("OLL 21"));
+        assert_eq!(find_case("T").unwrap().map(Case::set), Some(CaseSet::Pll));
+        assert_eq!(find_case("UUUUUUUUU").unwrap().map(Case::name), None);
+    }
+
+    #[test]
+    fn algorithms_solve_their_cases() {
+        for set in CaseSet::ALL {
+            for case in set.cases() {
+                let recognized = identify(set, &case.setup_state()).unwrap();
+                assert!(std::ptr::eq(recognized.case, case), "{}", case.name);
+                for alg in case.algorithms() {
+                    let mut state = case.setup_state();
+                    state.apply_all(alg);
+                    assert!(is_step_solved(set, &state), "{}", case.name);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,48 @@
+//! The 41 cases of inserting a corner and edge pair into the front-right
+//! slot, with the cross and other slots solved, in the standard numbering.
+
+use super::{Case, CaseSet::F2l};
+
+pub(super) const CASES: [Case; 41] = [
+    Case::new(F2l, "F2L 1", None, "Basic inserts", "Corner in UFR with the D color facing front; edge in UR with its front color facing up", &["U R U' R'"]),
+    Case::new(F2l, "F2L 2", None, "Basic inserts", "Corner in UFR with the D color facing right; edge in UF with its front color facing front", &["U' F' U F"]),
+    Case::new(F2l, "F2L 3", None, "Basic inserts", "Corner in UFR with the D color facing front; edge in UL with its front color facing left", &["F' U' F"]),
+    Case::new(F2l, "F2L 4", None, "Basic inserts", "Corner in UFR with the D color facing right; edge in UB with its front color facing up", &["R U R'"]),
+    Case::new(F2l, "F2L 5", None, "Corner and edge in U, the D color facing the side", "Corner in UFR with the D color facing front; edge in UB with its front color facing up", &["U' R U R' U2 R U' R'"]),
+    Case::new(F2l, "F2L 6", None, "Corner and edge in U, the D color facing the side", "Corner in UFR with the D color facing right; edge in UL with its front color facing left", &["U F' U' F U2 F' U F"]),
+    Case::new(F2l, "F2L 7", None, "Corner and edge in U, the D color facing the side", "Corner in UFR with the D color facing front; edge in UL with its front color facing up", &["U' R U2 R' U2 R U' R'"]),
+    Case::new(F2l, "F2L 8", None, "Corner and edge in U, the D color facing the side", "Corner in UFR with the D color facing right; edge in UB with its front color facing back", &["U F' U2 F U2 F' U F"]),
+    Case::new(F2l, "F2L 9", None, "Corner and edge in U, the D color facing the side", "Corner in UFR with the D color facing front; edge in UB with its front color facing back", &["U' R U' R' U F' U' F"]),
+    Case::new(F2l, "F2L 10", None, "Corner and edge in U, the D color facing the side", "Corner in UFR with the D color facing right; edge in UL with its front color facing up", &["U' R U R' U R U R'"]),
+    Case::new(F2l, "F2L 11", None, "Corner and edge in U, the D color facing the side", "Corner in UFR with the D color facing front; edge in UR with its front color facing right", &["U' R U2 R' U F' U' F"]),
+    Case::new(F2l, "F2L 12", None, "Corner and edge in U, the D color facing the side", "Corner in UFR with the D color facing right; edge in UF with its front color facing up", &["R U' R' U R U' R' U2 R U' R'"]),
+    Case::new(F2l, "F2L 13", None, "Corner and edge in U, the D color facing the side", "Corner in UFR with the D color facing front; edge in UF with its front color facing front", &["U F' U F U' F' U' F"]),
+    Case::new(F2l, "F2L 14", None, "Corner and edge in U, the D color facing the side", "Corner in UFR with the D color facing right; edge in UR with its front color facing up", &["U' R U' R' U R U R'"]),
+    Case::new(F2l, "F2L 15", None, "Corner and edge in U, the D color facing the side", "Corner in UFR with the D color facing front; edge in UF with its front color facing up", &["R' D' R U' R' D R U R U' R'"]),
+    Case::new(F2l, "F2L 16", None, "Corner and edge in U, the D color facing the side", "Corner in UFR with the D color facing right; edge in UR with its front color facing right", &["R U' R' U2 F' U' F"]),
+    Case::new(F2l, "F2L 17", None, "Corner and edge in U, the D color facing up", "Corner in UFR with the D color facing up; edge in UR with its front color facing up", &["R U2 R' U' R U R'"]),
+    Case::new(F2l, "F2L 18", None, "Corner and edge in U, the D color facing up", "Corner in UFR with the D color facing up; edge in UF with its front color facing front", &["F' U2 F U F' U' F"]),
+    Case::new(F2l, "F2L 19", None, "Corner and edge in U, the D color facing up", "Corner in UFR with the D color facing up; edge in UB with its front color facing up", &["U R U2 R' U R U' R'"]),
+    Case::new(F2l, "F2L 20", None, "Corner and edge in U, the D color facing up", "Corner in UFR with the D color facing up; edge in UL with its front color facing left", &["U' F' U2 F U' F' U F"]),
+    Case::new(F2l, "F2L 21", None, "Corner and edge in U, the D color facing up", "Corner in UFR with the D color facing up; edge in UL with its front color facing up", &["U2 R U R' U R U' R'"]),
+    Case::new(F2l, "F2L 22", None, "Corner and edge in U, the D color facing up", "Corner in UFR with the D color facing up; edge in UB with its front color facing back", &["U2 F' U' F U' F' U F"]),
+    Case::new(F2l, "F2L 23", None, "Corner and edge in U, the D color facing up", "Corner in UFR with the D color facing up; edge in UF with its front color facing up", &["U R U' R' U' R U' R' U R U' R'"]),
+    Case::new(F2l, "F2L 24", None, "Corner and edge in U, the D color facing up", "Corner in UFR with the D color facing up; edge in UR with its front color facing right", &["U' F' U F U F' U F U' F' U F"]),
+    Case::new(F2l, "F2L 25", None, "Corner in slot, edge in U", "Corner in DFR with the D color facing down; edge in UF with its front color facing up", &["U' R' F R F' R U R'"]),
+    Case::new(F2l, "F2L 26", None, "Corner in slot, edge in U", "Corner in DFR with the D color facing down; edge in UF with its front color facing front", &["U R U' R' F R' F' R"]),
+    Case::new(F2l, "F2L 27", None, "Corner in slot, edge in U", "Corner in DFR with the D color facing front; edge in UF with its front color facing up", &["R U' R' U R U' R'"]),
+    Case::new(F2l, "F2L 28", None, "Corner in slot, edge in U", "Corner in DFR with the D color facing right; edge in UF with its front color facing front", &["F' U F U' F' U F"]),
+    Case::new(F2l, "F2L 29", None, "Corner in slot, edge in U", "Corner in DFR with the D color facing front; edge in UF with its front color facing front", &["F' U' F U F' U' F"]),
+    Case::new(F2l, "F2L 30", None, "Corner in slot, edge in U", "Corner in DFR with the D color facing right; edge in UF with its front color facing up", &["R U R' U' R U R'"]),
+    Case::new(F2l, "F2L 31", None, "Corner in U, edge in slot", "Corner in UFR with the D color facing up; edge in FR, flipped", &["U' R' F R F' R U' R'"]),
+    Case::new(F2l, "F2L 32", None, "Corner in U, edge in slot", "Corner in UFR with the D color facing up; edge in FR, oriented", &["R U R' U' R U R' U' R U R'"]),
+    Case::new(F2l, "F2L 33", None, "Corner in U, edge in slot", "Corner in UFR with the D color facing front; edge in FR, oriented", &["U' R U' R' U2 R U' R'"]),
+    Case::new(F2l, "F2L 34", None, "Corner in U, edge in slot", "Corner in UFR with the D color facing right; edge in FR, oriented", &["U R U R' U2 R U R'"]),
+    Case::new(F2l, "F2L 35", None, "Corner in U, edge in slot", "Corner in UFR with the D color facing front; edge in FR, flipped", &["U2 R U' R' U' F' U' F"]),
+    Case::new(F2l, "F2L 36", None, "Corner in U, edge in slot", "Corner in UFR with the D color facing right; edge in FR, flipped", &["U F' U' F U' R U R'"]),
+    Case::new(F2l, "F2L 37", None, "Corner and edge in slot", "Corner in DFR with the D color facing down; edge in FR, flipped", &["R2 U2 F R2 F' U2 R' U R'"]),
+    Case::new(F2l, "F2L 38", None, "Corner and edge in slot", "Corner in DFR with the D color facing front; edge in FR, oriented", &["R U' R' U' R U R' U2 R U' R'"]),
+    Case::new(F2l, "F2L 39", None, "Corner and edge in slot", "Corner in DFR with the D color facing right; edge in FR, oriented", &["R U' R' U R U2 R' U R U' R'"]),
+    Case::new(F2l, "F2L 40", None, "Corner and edge in slot", "Corner in DFR with the D color facing front; edge in FR, flipped", &["r U' r' U2 r U r' R U R'"]),
+    Case::new(F2l, "F2L 41", None, "Corner and edge in slot", "Corner in DFR with the D color facing right; edge in FR, flipped", &["R U' R' r U' r' U2 r U r'"]),
+];
@@ -0,0 +1,63 @@
+//! The 57 cases of orienting the last layer.
+
+use super::{Case, CaseSet::Oll};
+
+pub(super) const CASES: [Case; 57] = [
+    Case::new(Oll, "OLL 1", None, "Dot", "Edges oriented: none; corners oriented: none; U color on the sides: front (middle), right (all), back (middle), left (all)", &["R U2 R2 F R F' U2 R' F R F'"]),
+    Case::new(Oll, "OLL 2", None, "Dot", "Edges oriented: none; corners oriented: none; U color on the sides: front (middle and right), right (middle), back (left and middle), left (all)", &["F R U R' U' F' f R U R' U' f'"]),
+    Case::new(Oll, "OLL 3", None, "Dot", "Edges oriented: none; corners oriented: front-right; U color on the sides: front (middle), right (middle and right), back (middle and right), left (middle and right)", &["f R U R' U' f' U' F R U R' U' F'"]),
+    Case::new(Oll, "OLL 4", None, "Dot", "Edges oriented: none; corners oriented: back-right; U color on the sides: front (left and middle), right (left and middle), back (middle), left (left and middle)", &["f R U R' U' f' U F R U R' U' F'"]),
+    Case::new(Oll, "OLL 5", None, "Square", "Edges oriented: right and front; corners oriented: front-right; U color on the sides: right (right), back (middle and right), left (middle and right)", &["r' U2 R U R' U r"]),
+    Case::new(Oll, "OLL 6", None, "Square", "Edges oriented: back and right; corners oriented: back-right; U color on the sides: front (left and middle), right (left), left (left and middle)", &["r U2 R' U' R U' r'"]),
+    Case::new(Oll, "OLL 7", None, "Small lightning", "Edges oriented: back and left; corners oriented: front-left; U color on the sides: front (middle and right), right (middle and right), back (right)", &["r U R' U R U2 r'"]),
+    Case::new(Oll, "OLL 8", None, "Small lightning", "Edges oriented: front and left; corners oriented: back-left; U color on the sides: front (left), right (left and middle), back (left and middle)", &["r' U' R U' R' U2 r"]),
+    Case::new(Oll, "OLL 9", None, "Fish", "Edges oriented: back and left; corners oriented: front-right; U color on the sides: front (left and middle), right (middle), back (left), left (left)", &["R U R' U' R' F R2 U R' U' F'"]),
+    Case::new(Oll, "OLL 10", None, "Fish", "Edges oriented: front and left; corners oriented: back-right; U color on the sides: front (right), right (middle), back (middle and right), left (right)", &["R U R' U R' F R F' R U2 R'"]),
+    Case::new(Oll, "OLL 11", None, "Small lightning", "Edges oriented: back and left; corners oriented: back-right; U color on the sides: front (middle and right), right (middle), back (right), left (right)", &["r U R' U R' F R F' R U2 r'"]),
+    Case::new(Oll, "OLL 12", None, "Small lightning", "Edges oriented: back and right; corners oriented: back-left; U color on the sides: front (left and middle), right (left), back (left), left (middle)", &["M' R' U' R U' R' U2 R U' M"]),
+    Case::new(Oll, "OLL 13", None, "Knight move", "Edges oriented: right and left; corners oriented: front-left; U color on the sides: front (middle and right), right (right), back (middle and right)", &["F U R U' R2 F' R U R U' R'"]),
+    Case::new(Oll, "OLL 14", None, "Knight move", "Edges oriented: right and left; corners oriented: front-right; U color on the sides: front (left and middle), back (left and middle), left (left)", &["R' F R U R' F' R F U' F'"]),
+    Case::new(Oll, "OLL 15", None, "Knight move", "Edges oriented: right and left; corners oriented: front-right; U color on the sides: front (middle), right (right), back (middle and right), left (right)", &["r' U' r R' U' R U r' U r"]),
+    Case::new(Oll, "OLL 16", None, "Knight move", "Edges oriented: right and left; corners oriented: back-right; U color on the sides: front (left and middle), right (left), back (middle), left (left)", &["r U r' R U R' U' r U' r'"]),
+    Case::new(Oll, "OLL 17", None, "Dot", "Edges oriented: none; corners oriented: front-right and back-left; U color on the sides: front (middle), right (middle), back (left and middle), left (middle and right)", &["R U R' U R' F R F' U2 R' F R F'"]),
+    Case::new(Oll, "OLL 18", None, "Dot", "Edges oriented: none; corners oriented: back-right and back-left; U color on the sides: front (all), right (middle), back (middle), left (middle)", &["r U R' U R U2 r2 U' R U' R' U2 r"]),
+    Case::new(Oll, "OLL 19", None, "Dot", "Edges oriented: none; corners oriented: back-right and back-left; U color on the sides: front (middle), right (left and middle), back (middle), left (middle and right)", &["M U R U R' U' M' R' F R F'"]),
+    Case::new(Oll, "OLL 20", None, "Dot", "Edges oriented: none; corners oriented: all; U color on the sides: front (middle), right (middle), back (middle), left (middle)", &["r U R' U' M2 U R U' R' U' M'"]),
+    Case::new(Oll, "OLL 21", Some("H"), "Corners not oriented", "Edges oriented: all; corners oriented: none; U color on the sides: front (left and right), back (left and right)", &["R U2 R' U' R U R' U' R U' R'"]),
+    Case::new(Oll, "OLL 22", Some("Pi"), "Corners not oriented", "Edges oriented: all; corners oriented: none; U color on the sides: front (right), back (left), left (left and right)", &["R U2 R2 U' R2 U' R2 U2 R"]),
+    Case::new(Oll, "OLL 23", Some("Headlights"), "Corners not oriented", "Edges oriented: all; corners oriented: front-right and front-left; U color on the sides: back (left and right)", &["R2 D' R U2 R' D R U2 R"]),
+    Case::new(Oll, "OLL 24", Some("Chameleon"), "Corners not oriented", "Edges oriented: all; corners oriented: front-right and back-right; U color on the sides: front (left), back (right)", &["r U R' U' r' F R F'"]),
+    Case::new(Oll, "OLL 25", Some("Bowtie"), "Corners not oriented", "Edges oriented: all; corners oriented: back-right and front-left; U color on the sides: front (right), left (left)", &["F' r U R' U' r' F R"]),
+    Case::new(Oll, "OLL 26", Some("Antisune"), "Corners not oriented", "Edges oriented: all; corners oriented: back-right; U color on the sides: front (left), right (left), left (left)", &["R U2 R' U' R U' R'"]),
+    Case::new(Oll, "OLL 27", Some("Sune"), "Corners not oriented", "Edges oriented: all; corners oriented: front-left; U color on the sides: front (right), right (right), back (right)", &["R U R' U R U2 R'"]),
+    Case::new(Oll, "OLL 28", Some("Stealth"), "Corners oriented", "Edges oriented: back and left; corners oriented: all; U color on the sides: front (middle), right (middle)", &["r U R' U' M U R U' R'"]),
+    Case::new(Oll, "OLL 29", None, "Awkward", "Edges oriented: back and left; corners oriented: front-right and back-right; U color on the sides: front (left and middle), right (middle), back (right)", &["R U R' U' R U' R' F' U' F R U R'"]),
+    Case::new(Oll, "OLL 30", None, "Awkward", "Edges oriented: back and left; corners oriented: front-right and front-left; U color on the sides: front (middle), right (middle and right), left (left)", &["F R' F R2 U' R' U' R U R' F2"]),
+    Case::new(Oll, "OLL 31", None, "P", "Edges oriented: back and right; corners oriented: front-right and back-right; U color on the sides: front (left and middle), back (right), left (middle)", &["R' U' F U R U' R' F' R"]),
+    Case::new(Oll, "OLL 32", None, "P", "Edges oriented: back and left; corners oriented: back-left and front-left; U color on the sides: front (middle and right), right (middle), back (left)", &["L U F' U' L' U L F L'"]),
+    Case::new(Oll, "OLL 33", None, "T", "Edges oriented: right and left; corners oriented: front-right and back-right; U color on the sides: front (left and middle), back (middle and right)", &["R U R' U' R' F R F'"]),
+    Case::new(Oll, "OLL 34", None, "C", "Edges oriented: right and left; corners oriented: front-right and front-left; U color on the sides: front (middle), right (right), back (middle), left (left)", &["R U R2 U' R' F R U R U' F'"]),
+    Case::new(Oll, "OLL 35", None, "Fish", "Edges oriented: right and front; corners oriented: front-right and back-left; U color on the sides: front (left), right (right), back (middle), left (middle)", &["R U2 R2 F R F' R U2 R'"]),
+    Case::new(Oll, "OLL 36", None, "W", "Edges oriented: back and right; corners oriented: front-right and back-left; U color on the sides: front (middle), back (left), left (middle and right)", &["L' U' L U' L' U L U L F' L' F"]),
+    Case::new(Oll, "OLL 37", None, "Fish", "Edges oriented: back and left; corners oriented: front-right and back-left; U color on the sides: front (left and middle), right (middle and right)", &["F R' F' R U R U' R'"]),
+    Case::new(Oll, "OLL 38", None, "W", "Edges oriented: back and left; corners oriented: back-right and front-left; U color on the sides: front (middle), right (left and middle), back (right)", &["R U R' U R U' R' U' R' F R F'"]),
+    Case::new(Oll, "OLL 39", None, "Big lightning", "Edges oriented: right and left; corners oriented: back-right and front-left; U color on the sides: front (middle), right (left), back (middle and right)", &["L F' L' U' L U F U' L'"]),
+    Case::new(Oll, "OLL 40", None, "Big lightning", "Edges oriented: right and left; corners oriented: front-right and back-left; U color on the sides: front (middle), back (left and middle), left (right)", &["R' F R U R' U' F' U R"]),
+    Case::new(Oll, "OLL 41", None, "Awkward", "Edges oriented: back and left; corners oriented: front-right and front-left; U color on the sides: front (middle), right (middle), back (left and right)", &["R U R' U R U2 R' F R U R' U' F'"]),
+    Case::new(Oll, "OLL 42", None, "Awkward", "Edges oriented: front and left; corners oriented: back-right and back-left; U color on the sides: front (left and right), right (middle), back (middle)", &["R' U' R U' R' U2 R F R U R' U' F'"]),
+    Case::new(Oll, "OLL 43", None, "P", "Edges oriented: back and right; corners oriented: front-right and back-right; U color on the sides: front (middle), left (all)", &["F' U' L' U L F"]),
+    Case::new(Oll, "OLL 44", None, "P", "Edges oriented: back and left; corners oriented: back-left and front-left; U color on the sides: front (middle), right (all)", &["F U R U' R' F'"]),
+    Case::new(Oll, "OLL 45", None, "T", "Edges oriented: right and left; corners oriented: front-right and back-right; U color on the sides: front (middle), back (middle), left (left and right)", &["F R U R' U' F'"]),
+    Case::new(Oll, "OLL 46", None, "C", "Edges oriented: back and front; corners oriented: back-left and front-left; U color on the sides: right (all), left (middle)", &["R' U' R' F R F' U R"]),
+    Case::new(Oll, "OLL 47", None, "Small L", "Edges oriented: back and right; corners oriented: none; U color on the sides: front (left and middle), right (left and right), back (right), left (middle)", &["F' L' U' L U L' U' L U F"]),
+    Case::new(Oll, "OLL 48", None, "Small L", "Edges oriented: back and left; corners oriented: none; U color on the sides: front (middle and right), right (middle), back (left), left (left and right)", &["F R U R' U' R U R' U' F'"]),
+    Case::new(Oll, "OLL 49", None, "Small L", "Edges oriented: back and right; corners oriented: none; U color on the sides: front (middle and right), back (left), left (all)", &["r U' r2 U r2 U r2 U' r"]),
+    Case::new(Oll, "OLL 50", None, "Small L", "Edges oriented: right and front; corners oriented: none; U color on the sides: front (right), back (left and middle), left (all)", &["r' U r2 U' r2 U' r2 U r'"]),
+    Case::new(Oll, "OLL 51", None, "Line", "Edges oriented: right and left; corners oriented: none; U color on the sides: front (left and middle), right (left and right), back (middle and right)", &["F U R U' R' U R U' R' F'"]),
+    Case::new(Oll, "OLL 52", None, "Line", "Edges oriented: back and front; corners oriented: none; U color on the sides: front (left), right (all), back (right), left (middle)", &["R U R' U R U' B U' B' R'"]),
+    Case::new(Oll, "OLL 53", None, "Small L", "Edges oriented: right and front; corners oriented: none; U color on the sides: right (left and right), back (middle), left (all)", &["r' U' R U' R' U R U' R' U2 r"]),
+    Case::new(Oll, "OLL 54", None, "Small L", "Edges oriented: back and right; corners oriented: none; U color on the sides: front (middle), right (left and right), left (all)", &["r U R' U R U' R' U R U2 r'"]),
+    Case::new(Oll, "OLL 55", None, "Line", "Edges oriented: back and front; corners oriented: none; U color on the sides: right (all), left (all)", &["R U2 R2 U' R U' R' U2 F R F'"]),
+    Case::new(Oll, "OLL 56", None, "Line", "Edges oriented: right and left; corners oriented: none; U color on the sides: front (middle), right (left and right), back (middle), left (left and right)", &["r U r' U R U' R' U R U' R' r U' r'"]),
+    Case::new(Oll, "OLL 57", None, "Corners oriented", "Edges oriented: right and left; corners oriented: all; U color on the sides: front (middle), back (middle)", &["R U R' U' M' U R U' r'"]),
+];
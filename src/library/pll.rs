@@ -0,0 +1,27 @@
+//! The 21 cases of permuting the last layer.
+
+use super::{Case, CaseSet::Pll};
+
+pub(super) const CASES: [Case; 21] = [
+    Case::new(Pll, "Aa", None, "Corners only", "Front: block on the left; right: no matching stickers; back: headlights; left: block on the right", &["x R' U R' D2 R U' R' D2 R2 x'"]),
+    Case::new(Pll, "Ab", None, "Corners only", "Front: block on the left; right: headlights; back: no matching stickers; left: block on the right", &["x R2 D2 R U R' D2 R U' R x'"]),
+    Case::new(Pll, "E", None, "Corners only", "Front: no matching stickers; right: no matching stickers; back: no matching stickers; left: no matching stickers", &["x' R U' R' D R U R' D' R U R' D R U' R' D' x"]),
+    Case::new(Pll, "F", None, "Adjacent corner swap", "Front: no matching stickers; right: no matching stickers; back: no matching stickers; left: solved bar", &["R' U' F' R U R' U' R' F R2 U' R' U' R U R' U R"]),
+    Case::new(Pll, "Ga", None, "G perms", "Front: block on the right; right: no matching stickers; back: no matching stickers; left: headlights", &["R2 U R' U R' U' R U' R2 U' D R' U R D'"]),
+    Case::new(Pll, "Gb", None, "G perms", "Front: no matching stickers; right: block on the right; back: no matching stickers; left: headlights", &["R' U' R U D' R2 U R' U R U' R U' R2 D"]),
+    Case::new(Pll, "Gc", None, "G perms", "Front: no matching stickers; right: no matching stickers; back: block on the left; left: headlights", &["R2 U' R U' R U R' U R2 U D' R U' R' D"]),
+    Case::new(Pll, "Gd", None, "G perms", "Front: no matching stickers; right: block on the left; back: no matching stickers; left: headlights", &["R U R' U' D R2 U' R U' R' U R' U R2 D'"]),
+    Case::new(Pll, "H", None, "Edges only", "Front: headlights; right: headlights; back: headlights; left: headlights. Each edge has the color of the opposite side: opposite edges are swapped", &["M2 U M2 U2 M2 U M2"]),
+    Case::new(Pll, "Ja", None, "Adjacent corner swap", "Front: solved bar; right: block on the left; back: block on the left; left: block on the left", &["R' U L' U2 R U' R' U2 R L"]),
+    Case::new(Pll, "Jb", None, "Adjacent corner swap", "Front: block on the right; right: block on the right; back: block on the right; left: solved bar", &["R U R' F' R U R' U' R' F R2 U' R'"]),
+    Case::new(Pll, "Na", None, "Diagonal corner swap", "Front: block on the right; right: block on the right; back: block on the right; left: block on the right", &["R U R' U R U R' F' R U R' U' R' F R2 U' R' U2 R U' R'"]),
+    Case::new(Pll, "Nb", None, "Diagonal corner swap", "Front: block on the left; right: block on the left; back: block on the left; left: block on the left", &["R' U R U' R' F' U' F R U R' F R' F' R U' R"]),
+    Case::new(Pll, "Ra", None, "Adjacent corner swap", "Front: block on the left; right: no matching stickers; back: no matching stickers; left: headlights", &["R U' R' U' R U R D R' U' R D' R' U2 R'"]),
+    Case::new(Pll, "Rb", None, "Adjacent corner swap", "Front: no matching stickers; right: no matching stickers; back: block on the right; left: headlights", &["R2 F R U R U' R' F' R U2 R' U2 R"]),
+    Case::new(Pll, "T", None, "Adjacent corner swap", "Front: block on the left; right: no matching stickers; back: block on the right; left: headlights", &["R U R' U' R' F R2 U' R' U' R U R' F'"]),
+    Case::new(Pll, "Ua", None, "Edges only", "Front: headlights; right: headlights; back: solved bar; left: headlights. The front edge has the right side's color: the edges go front to right to left, counterclockwise seen from above", &["M2 U M U2 M' U M2"]),
+    Case::new(Pll, "Ub", None, "Edges only", "Front: headlights; right: headlights; back: solved bar; left: headlights. The front edge has the left side's color: the edges go front to left to right, clockwise seen from above", &["M2 U' M U2 M' U' M2"]),
+    Case::new(Pll, "V", None, "Diagonal corner swap", "Front: no matching stickers; right: no matching stickers; back: block on the right; left: block on the left", &["R U' R U R' D R D' R U' D R2 U R2 D' R2"]),
+    Case::new(Pll, "Y", None, "Diagonal corner swap", "Front: block on the left; right: block on the right; back: no matching stickers; left: no matching stickers", &["F R U' R' U' R U R' F' R U R' U' R' F R F'"]),
+    Case::new(Pll, "Z", None, "Edges only", "Front: headlights; right: headlights; back: headlights; left: headlights. Each edge has the color of a neighboring side: two pairs of adjacent edges are swapped", &["M' U M2 U M2 U M' U2 M2"]),
+];
@@ -0,0 +1,192 @@
+//! A native windowed front end, for debugging rendering and animation
+//! outside a browser and running the simulator offline.
+
+use three_d::{Event, FrameOutput, Key, OrbitControl, Vec3, Window, WindowSettings};
+
+use crate::clock::{Clock, RealTime};
+use crate::library;
+use crate::reconstruction::Reconstruction;
+use crate::rubik::{Cube, CubeAnimationOptions, CubeState, Move};
+use crate::scene::{CubeView, Scene};
+use crate::solver;
+use crate::trainer::{Outcome, Trainer};
+
+pub struct ViewerOptions {
+    pub title: String,
+    /// Largest size of the window, in logical pixels.
+    pub max_size: Option<(u32, u32)>,
+    pub state: CubeState,
+    /// Moves queued as soon as the window opens.
+    pub moves: Vec<Move>,
+    pub animation: CubeAnimationOptions,
+    pub auto_rotate: bool,
+    /// Drill cases instead of showing `state`.
+    pub trainer: Option<Trainer>,
+    /// Where animation and drill times come from.
+    pub clock: Box<dyn Clock>,
+}
+
+impl Default for ViewerOptions {
+    fn default() -> Self {
+        ViewerOptions {
+            title: "rustbik".to_string(),
+            max_size: Some((800, 800)),
+            state: CubeState::solved(),
+            moves: vec![],
+            animation: CubeAnimationOptions::default(),
+            auto_rotate: true,
+            trainer: None,
+            clock: Box::new(RealTime::new()),
+        }
+    }
+}
+
+/// The move queued by pressing a key: the face letter turns that face
+/// clockwise, and with shift held counterclockwise.
+fn key_move(key: Key, shift: bool) -> Option<Move> {
+    let (cw, ccw) = match key {
+        Key::L => (Move::L, Move::LP),
+        Key::R => (Move::R, Move::RP),
+        Key::U => (Move::U, Move::UP),
+        Key::D => (Move::D, Move::DP),
+        Key::F => (Move::F, Move::FP),
+        Key::B => (Move::B, Move::BP),
+        _ => return None,
+    };
+    Some(if shift { ccw } else { cw })
+}
+
+/// Set up the trainer's next case on the cube.
+fn next_drill(trainer: &mut Trainer, cube: &mut Cube, context: &three_d::Context, anim: CubeAnimationOptions, time: f64) {
+    let drill = trainer.next(time);
+    let theme = *cube.theme();
+    *cube = Cube::from_state(context, drill.state.clone(), anim);
+    cube.set_theme(theme);
+}
+
+/// Report how a drill went on standard output.
+fn report(trainer: &Trainer, outcome: Outcome, name: &str) {
+    match outcome {
+        Outcome::Solved(time) => {
+            let stats = trainer.stats(name).unwrap();
+            println!(
+                "{}: {:.2} s (best {:.2} s, mean {:.2} s over {})",
+                name,
+                time / 1000.0,
+                stats.best().unwrap() / 1000.0,
+                stats.mean().unwrap() / 1000.0,
+                stats.times.len(),
+            );
+        }
+        Outcome::Unsolved(Some(other)) => println!("Not solved: the cube is now in {}", other.name()),
+        Outcome::Unsolved(None) => println!("Not solved"),
+    }
+}
+
+/// Print an alg.cubing.net link to the moves made since `start`.
+fn print_link(start: &CubeState, moves: &[Move]) {
+    match solver::solve(start) {
+        Ok(solution) => {
+            let reconstruction = Reconstruction::new(Move::invert_sequence(&solution), moves.to_vec());
+            println!("{}", reconstruction.to_alg_cubing_url());
+        }
+        Err(e) => println!("No link: {}", e),
+    }
+}
+
+/// Open a window showing a single cube and run until it is closed.
+///
+/// Dragging with the mouse orbits the camera, the face letters queue moves
+/// (hold shift for counterclockwise turns) and space toggles auto-rotation.
+/// P prints an alg.cubing.net link to the moves made so far.
+///
+/// With a trainer, the cube shows its cases one after another. A case is
+/// checked as soon as it is solved or when enter is pressed, and N skips to
+/// the next one; results are printed to standard output.
+pub fn run_viewer(mut opts: ViewerOptions) -> Result<(), String> {
+    let window = Window::new(WindowSettings {
+        title: opts.title,
+        max_size: opts.max_size,
+        ..Default::default()
+    }).map_err(|e| format!("failed to open window: {:?}", e))?;
+    let context = window.gl();
+
+    let mut cube = Cube::from_state(&context, opts.state, opts.animation);
+    if let Some(trainer) = opts.trainer.as_mut() {
+        next_drill(trainer, &mut cube, &context, opts.animation, 0.0);
+    }
+    // The moves made since the cube was in `start`.
+    let mut start = cube.state().clone();
+    let mut session = opts.moves.clone();
+    cube.queue(opts.moves.into_iter());
+    let mut view = CubeView::full(cube);
+    view.auto_rotate = opts.auto_rotate;
+    let mut scene = Scene::new(&context);
+    let id = scene.add(view);
+    let mut control = OrbitControl::new(Vec3::new(0.0, 0.0, 0.0), 3.0, 30.0);
+    let mut trainer = opts.trainer;
+    // Whether the trainer has seen the cube since the last move.
+    let mut checked = true;
+    let mut clock = opts.clock;
+
+    window.render_loop(move |mut frame_input| {
+        let time = scene.tick(&mut clock);
+        let view = scene.view_mut(id).unwrap();
+        control.handle_events(&mut view.camera, &mut frame_input.events);
+        for event in frame_input.events.iter() {
+            if let Event::KeyPress { kind, modifiers, handled: false } = event {
+                if *kind == Key::Space {
+                    view.auto_rotate = !view.auto_rotate;
+                } else if *kind == Key::P {
+                    print_link(&start, &session);
+                } else if let Some(mv) = key_move(*kind, modifiers.shift) {
+                    view.cube.queue(mv.into_iter());
+                    session.push(mv);
+                    checked = false;
+                } else if let Some(trainer) = trainer.as_mut() {
+                    match kind {
+                        Key::Enter if view.cube.is_idle() => {
+                            let name = trainer.drill().map(|drill| drill.case.name());
+                            if let (Some(name), Ok(outcome)) = (name, trainer.check(view.cube.state(), time)) {
+                                report(trainer, outcome, name);
+                            }
+                            checked = true;
+                        }
+                        Key::N => {
+                            next_drill(trainer, &mut view.cube, &context, opts.animation, time);
+                            start = view.cube.state().clone();
+                            session.clear();
+                            checked = true;
+                        }
+                        _ => {}
+                    }
+                }
+            }
+        }
+        if let Some(trainer) = trainer.as_mut() {
+            if !checked && view.cube.is_idle() {
+                checked = true;
+                if library::is_step_solved(trainer.set(), view.cube.state()) {
+                    let name = trainer.drill().map(|drill| drill.case.name());
+                    if let (Some(name), Ok(outcome)) = (name, trainer.check(view.cube.state(), time)) {
+                        report(trainer, outcome, name);
+                    }
+                }
+            }
+            if trainer.drill().is_none() {
+                next_drill(trainer, &mut view.cube, &context, opts.animation, time);
+                start = view.cube.state().clone();
+                session.clear();
+            }
+        }
+
+        scene.render(
+            &frame_input.screen(),
+            frame_input.viewport.width,
+            frame_input.viewport.height,
+            frame_input.device_pixel_ratio,
+        );
+        FrameOutput::default()
+    });
+    Ok(())
+}
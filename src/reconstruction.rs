@@ -0,0 +1,276 @@
+//! Reconstructions in the text format of alg.cubing.net and twisty-player:
+//! one line per step, with `//` comments, and links to both sites.
+//!
+//! alg.cubing.net escapes spaces in its links as `_` and primes as `-`
+//! before percent-encoding; Twizzle links are plain form-encoded. Both kinds
+//! of link are read and written.
+
+use crate::algorithm::Algorithm;
+use crate::rubik::Move;
+
+const ALG_CUBING_NET: &str = "https://alg.cubing.net/";
+const TWIZZLE: &str = "https://alpha.twizzle.net/edit/";
+
+/// The sites whose links are read and written.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Site {
+    AlgCubingNet,
+    Twizzle,
+}
+
+impl Site {
+    /// The site a link points to, by its host, such as `alg.cubing.net` or
+    /// `alpha.twizzle.net`.
+    pub fn of_url(url: &str) -> Option<Site> {
+        let rest = url.split_once("://").map_or(url, |(_, rest)| rest);
+        let authority = rest.split(['/', '?', '#']).next().unwrap_or("");
+        let host = authority.rsplit_once('@').map_or(authority, |(_, host)| host);
+        let host = host.split_once(':').map_or(host, |(host, _)| host).to_ascii_lowercase();
+        if host == "alg.cubing.net" {
+            Some(Site::AlgCubingNet)
+        } else if host == "twizzle.net" || host.ends_with(".twizzle.net") {
+            Some(Site::Twizzle)
+        } else {
+            None
+        }
+    }
+}
+
+/// A line of a reconstruction: moves, a comment, or both.
+#[derive(Clone, PartialEq, Eq, Debug, Default)]
+pub struct Step {
+    pub moves: Vec<Move>,
+    /// The text after `//`, trimmed.
+    pub comment: Option<String>,
+}
+
+impl Step {
+    pub fn new(moves: Vec<Move>, comment: Option<&str>) -> Step {
+        Step { moves, comment: comment.map(str::to_string) }
+    }
+}
+
+/// Setup moves from solved, then the moves of a solve or algorithm split
+/// into commented steps.
+#[derive(Clone, PartialEq, Eq, Debug, Default)]
+pub struct Reconstruction {
+    pub title: Option<String>,
+    pub setup: Vec<Move>,
+    pub steps: Vec<Step>,
+}
+
+impl Reconstruction {
+    /// A reconstruction with all the moves in a single step.
+    pub fn new(setup: Vec<Move>, moves: Vec<Move>) -> Reconstruction {
+        let steps = if moves.is_empty() { vec![] } else { vec![Step::new(moves, None)] };
+        Reconstruction { title: None, setup, steps }
+    }
+
+    /// The moves of every step, in order.
+    pub fn moves(&self) -> Vec<Move> {
+        self.steps.iter().flat_map(|step| step.moves.iter().copied()).collect()
+    }
+
+    /// Read algorithm text: every line with moves or a comment is a step.
+    /// Moves may use commutator, conjugate and repetition notation (see
+    /// [`Algorithm`]); the pauses `.` are ignored.
+    pub fn parse_steps(text: &str) -> Result<Vec<Step>, String> {
+        let mut steps = vec![];
+        for line in text.lines() {
+            let (moves, comment) = match line.split_once("//") {
+                Some((moves, comment)) => (moves, Some(comment.trim())),
+                None => (line, None),
+            };
+            let moves: Algorithm = moves.replace('.', " ").parse()?;
+            if !moves.is_empty() || comment.is_some() {
+                steps.push(Step::new(moves.moves().to_vec(), comment));
+            }
+        }
+        Ok(steps)
+    }
+
+    /// The steps as algorithm text, one line each.
+    pub fn steps_text(&self) -> String {
+        let lines: Vec<String> = self.steps.iter().map(|step| {
+            let moves = Move::to_sequence(&step.moves);
+            match &step.comment {
+                Some(comment) if moves.is_empty() => format!("// {}", comment),
+                Some(comment) => format!("{} // {}", moves, comment),
+                None => moves,
+            }
+        }).collect();
+        lines.join("\n")
+    }
+
+    /// Read an alg.cubing.net or Twizzle link. Links whose algorithm solves
+    /// the setup (`type=alg` on alg.cubing.net, `setup-anchor=end` on
+    /// Twizzle) are turned into ones whose setup leads to the case.
+    pub fn from_url(url: &str) -> Result<Reconstruction, String> {
+        let site = Site::of_url(url).ok_or(format!("{} isn't an alg.cubing.net or Twizzle link", url))?;
+        let query = url.split_once('?').map_or("", |(_, query)| query);
+        let query = query.split_once('#').map_or(query, |(query, _)| query);
+        let mut reconstruction = Reconstruction::default();
+        let mut solves_setup = false;
+        for pair in query.split('&').filter(|pair| !pair.is_empty()) {
+            let (key, value) = pair.split_once('=').unwrap_or((pair, ""));
+            let value = if site == Site::Twizzle { percent_decode(&value.replace('+', " "))? } else { unescape(&percent_decode(value)?) };
+            match key {
+                "alg" => reconstruction.steps = Reconstruction::parse_steps(&value)?,
+                "setup" | "setup-alg" => {
+                    let steps = Reconstruction::parse_steps(&value)?;
+                    reconstruction.setup = steps.into_iter().flat_map(|step| step.moves).collect();
+                }
+                "title" => reconstruction.title = Some(value),
+                "type" => solves_setup = value == "alg",
+                "setup-anchor" => solves_setup = value == "end",
+                _ => {}
+            }
+        }
+        if solves_setup {
+            reconstruction.setup.extend(Move::invert_sequence(&reconstruction.moves()));
+        }
+        Ok(reconstruction)
+    }
+
+    /// A link showing the reconstruction on alg.cubing.net.
+    pub fn to_alg_cubing_url(&self) -> String {
+        let mut params = vec![];
+        if let Some(title) = &self.title {
+            params.push(format!("title={}", percent_encode(&escape(title))));
+        }
+        if !self.setup.is_empty() {
+            params.push(format!("setup={}", percent_encode(&escape(&Move::to_sequence(&self.setup)))));
+        }
+        params.push(format!("alg={}", percent_encode(&escape(&self.steps_text()))));
+        format!("{}?{}", ALG_CUBING_NET, params.join("&"))
+    }
+
+    /// A link showing the reconstruction in the Twizzle editor.
+    pub fn to_twizzle_url(&self) -> String {
+        let encode = |text: &str| percent_encode(text).replace("%20", "+");
+        let mut params = vec![];
+        if !self.setup.is_empty() {
+            params.push(format!("setup-alg={}", encode(&Move::to_sequence(&self.setup))));
+        }
+        params.push(format!("alg={}", encode(&self.steps_text())));
+        format!("{}?{}", TWIZZLE, params.join("&"))
+    }
+}
+
+/// alg.cubing.net's escaping of text in links.
+fn escape(text: &str) -> String {
+    text.replace('_', "&#95;").replace(' ', "_").replace('+', "&#2b;").replace('-', "&#45;").replace('\'', "-")
+}
+
+fn unescape(text: &str) -> String {
+    text.replace('-', "'").replace("&#45;", "-").replace('+', " ").replace("&#2b;", "+").replace('_', " ").replace("&#95;", "_")
+}
+
+/// Percent-encode every byte but letters, digits and `-._~`.
+fn percent_encode(text: &str) -> String {
+    let mut out = String::new();
+    for byte in text.bytes() {
+        if byte.is_ascii_alphanumeric() || b"-._~".contains(&byte) {
+            out.push(byte as char);
+        } else {
+            out.push_str(&format!("%{:02X}", byte));
+        }
+    }
+    out
+}
+
+fn percent_decode(text: &str) -> Result<String, String> {
+    let bytes = text.as_bytes();
+    let mut out = vec![];
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%' {
+            let hex = text.get(i + 1..i + 3).ok_or("Truncated percent-encoding")?;
+            out.push(u8::from_str_radix(hex, 16).map_err(|_| format!("Invalid percent-encoding %{}", hex))?);
+            i += 3;
+        } else {
+            out.push(bytes[i]);
+            i += 1;
+        }
+    }
+    String::from_utf8(out).map_err(|_| "Percent-encoded text isn't UTF-8".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn moves(sequence: &str) -> Vec<Move> {
+        Move::from_sequence(sequence).unwrap().collect()
+    }
+
+    fn example() -> Reconstruction {
+        Reconstruction {
+            title: Some("Sune + J-perm_1 'x'".to_string()),
+            setup: moves("F R U R' U' F'"),
+            steps: vec![
+                Step::new(moves("R U R' U R U2 R'"), Some("Sune")),
+                Step::new(vec![], Some("skip")),
+                Step::new(moves("y2"), None),
+            ],
+        }
+    }
+
+    #[test]
+    fn escaping() {
+        assert_eq!(escape("R U R' U'"), "R_U_R-_U-");
+        assert_eq!(escape("a_b c+d-e'"), "a&#95;b_c&#2b;d&#45;e-");
+        assert_eq!(unescape("a&#95;b_c&#2b;d&#45;e-"), "a_b c+d-e'");
+        assert_eq!(unescape("R+U"), "R U");
+        assert_eq!(percent_encode("R U'\n// é~"), "R%20U%27%0A%2F%2F%20%C3%A9~");
+        assert_eq!(percent_decode("R%20U%27%0A%2F%2F%20%C3%A9~"), Ok("R U'\n// é~".to_string()));
+        for invalid in ["%G1", "R%4", "%FF"] {
+            assert!(percent_decode(invalid).is_err(), "{}", invalid);
+        }
+    }
+
+    #[test]
+    fn sites() {
+        let alg_cubing = "https://alg.cubing.net/?alg=R_U_R-_U-&title=twizzle_test";
+        assert_eq!(Site::of_url(alg_cubing), Some(Site::AlgCubingNet));
+        let reconstruction = Reconstruction::from_url(alg_cubing).unwrap();
+        assert_eq!(reconstruction.title.as_deref(), Some("twizzle test"));
+        assert_eq!(reconstruction.moves(), moves("R U R' U'"));
+        assert_eq!(Site::of_url("https://alpha.twizzle.net/edit/?alg=R"), Some(Site::Twizzle));
+        assert_eq!(Site::of_url("HTTPS://user@Twizzle.net:443/explore"), Some(Site::Twizzle));
+        for other in ["https://example.com/?alg=R&title=twizzle", "https://twizzle.net.example.com/", "alg=R"] {
+            assert_eq!(Site::of_url(other), None, "{}", other);
+            assert!(Reconstruction::from_url(other).is_err(), "{}", other);
+        }
+    }
+
+    #[test]
+    fn alg_cubing_links() {
+        let url = example().to_alg_cubing_url();
+        assert_eq!(url, "https://alg.cubing.net/?title=Sune_%26%232b%3B_J%26%2345%3Bperm%26%2395%3B1_-x-\
+            &setup=F_R_U_R-_U-_F-&alg=R_U_R-_U_R_U2_R-_%2F%2F_Sune%0A%2F%2F_skip%0Ay2");
+        assert_eq!(Reconstruction::from_url(&url), Ok(example()));
+    }
+
+    #[test]
+    fn twizzle_links() {
+        let url = example().to_twizzle_url();
+        assert_eq!(url, "https://alpha.twizzle.net/edit/?setup-alg=F+R+U+R%27+U%27+F%27\
+            &alg=R+U+R%27+U+R+U2+R%27+%2F%2F+Sune%0A%2F%2F+skip%0Ay2");
+        assert_eq!(Reconstruction::from_url(&url), Ok(Reconstruction { title: None, ..example() }));
+    }
+
+    #[test]
+    fn links_solving_the_setup() {
+        for url in [
+            "https://alg.cubing.net/?alg=R_U_R-_U-&setup=F&type=alg",
+            "https://alpha.twizzle.net/edit/?alg=R+U+R%27+U%27&setup-alg=F&setup-anchor=end",
+        ] {
+            let reconstruction = Reconstruction::from_url(url).unwrap();
+            assert_eq!(reconstruction.setup, moves("F U R U' R'"), "{}", url);
+            assert_eq!(reconstruction.moves(), moves("R U R' U'"), "{}", url);
+        }
+        let reconstruction = Reconstruction::from_url("https://alg.cubing.net/?alg=R&type=reconstruction").unwrap();
+        assert_eq!(reconstruction.setup, vec![]);
+    }
+}
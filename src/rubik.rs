@@ -1,12 +1,21 @@
-use std::collections::VecDeque;
+use std::fmt;
+use std::str::FromStr;
 
-use three_d::{CpuMesh, Mat3, Mat4, Mesh, Object, Rad, SquareMatrix, Srgba, Vec3};
+use three_d::{Gm, InstancedMesh, Instances, Mat3, Mat4, Object, Rad, SquareMatrix, Srgba};
 
 mod piece;
 mod graphics;
+mod state;
+mod cubie;
+mod queue;
 
 use piece::Piece;
-use graphics::cube_uvs;
+use graphics::{sticker_mesh, RubikMaterial};
+pub use state::CubeState;
+pub use queue::MoveQueue;
+pub use cubie::{CubieCube, Cycle, CORNER_NAMES, EDGE_NAMES};
+pub(crate) use cubie::{corner_facelets, edge_facelets, permutation_parity};
+pub(crate) use state::{facelet_geometry, U};
 
 const COLORS: [Srgba; 6] = [
     Srgba::new(31, 68, 166, 255), // blue
@@ -64,7 +73,7 @@ pub const ROT_YZ_CCW: Mat3 = Mat3::new(
 
 /// Rubik's cube standard colors.
 /// None represents the color between cubelet faces that aren't visible.
-#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, Default)]
 pub enum Color {
     Blue,
     Yellow,
@@ -72,30 +81,114 @@ pub enum Color {
     White,
     Green,
     Orange,
+    #[default]
     None
 }
 
-impl Default for Color {
-    fn default() -> Self {
-        Color::None
+impl Color {
+    /// The color of each face of a solved cube, in facelet string order.
+    pub const FACES: [Color; 6] = [
+        Color::Blue,
+        Color::Yellow,
+        Color::Red,
+        Color::White,
+        Color::Green,
+        Color::Orange,
+    ];
+
+    /// Parse the letter used for a color in facelet strings.
+    pub fn from_char(c: char) -> Result<Color, String> {
+        match c {
+            'B' => Ok(Color::Blue),
+            'Y' => Ok(Color::Yellow),
+            'R' => Ok(Color::Red),
+            'W' => Ok(Color::White),
+            'G' => Ok(Color::Green),
+            'O' => Ok(Color::Orange),
+            c => Err(format!("Invalid char {}", c)),
+        }
     }
-}
 
-impl Into<Srgba> for Color {
-    fn into(self) -> Srgba {
+    /// The letter used for a color in facelet strings.
+    pub fn to_char(self) -> char {
         match self {
-            Color::Blue => COLORS[0],
-            Color::Yellow => COLORS[1],
-            Color::Red => COLORS[2],
-            Color::White => COLORS[3],
-            Color::Green => COLORS[4],
-            Color::Orange => COLORS[5],
-            Color::None => Srgba::BLACK,
+            Color::Blue => 'B',
+            Color::Yellow => 'Y',
+            Color::Red => 'R',
+            Color::White => 'W',
+            Color::Green => 'G',
+            Color::Orange => 'O',
+            Color::None => '-',
+        }
+    }
+}
+
+impl From<Color> for Srgba {
+    fn from(color: Color) -> Srgba {
+        Theme::CLASSIC.color(color)
+    }
+}
+
+/// The colors a cube is drawn with.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Theme {
+    /// Sticker colors, in the order of [`Color::FACES`].
+    pub stickers: [Srgba; 6],
+    /// Color of the plastic between stickers.
+    pub body: Srgba,
+}
+
+impl Theme {
+    /// Standard sticker colors on a black body.
+    pub const CLASSIC: Theme = Theme { stickers: COLORS, body: Srgba::BLACK };
+
+    /// Standard sticker colors on a white body.
+    pub const WHITE: Theme = Theme { stickers: COLORS, body: Srgba::new(235, 235, 235, 255) };
+
+    /// Saturated, easily told apart sticker colors on a black body.
+    pub const BRIGHT: Theme = Theme {
+        stickers: [
+            Srgba::new(0, 90, 255, 255),
+            Srgba::new(255, 235, 0, 255),
+            Srgba::new(230, 0, 30, 255),
+            Srgba::new(255, 255, 255, 255),
+            Srgba::new(0, 200, 60, 255),
+            Srgba::new(255, 120, 0, 255),
+        ],
+        body: Srgba::BLACK,
+    };
+
+    /// A theme by name: `"classic"`, `"white"` or `"bright"`.
+    pub fn from_name(name: &str) -> Result<Theme, String> {
+        match name {
+            "classic" => Ok(Theme::CLASSIC),
+            "white" => Ok(Theme::WHITE),
+            "bright" => Ok(Theme::BRIGHT),
+            _ => Err(format!("Unknown theme {}", name)),
+        }
+    }
+
+    pub fn color(&self, color: Color) -> Srgba {
+        match Color::FACES.iter().position(|&c| c == color) {
+            Some(i) => self.stickers[i],
+            None => self.body,
         }
     }
 }
 
-#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+impl Default for Theme {
+    fn default() -> Self {
+        Theme::CLASSIC
+    }
+}
+
+/// A move in standard notation: a face turn, a wide turn of a face and the
+/// slice next to it, a slice turn, or a rotation of the whole cube.
+///
+/// Moves come in threes (clockwise, counterclockwise and half turn), face
+/// turns first, so `mv as usize` indexes [`Move::ALL`] and, for face turns,
+/// [`Move::FACE_TURNS`].
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
 pub enum Move {
     L, LP, L2,
     R, RP, R2,
@@ -103,72 +196,292 @@ pub enum Move {
     D, DP, D2,
     F, FP, F2,
     B, BP, B2,
+    Lw, LwP, Lw2,
+    Rw, RwP, Rw2,
+    Uw, UwP, Uw2,
+    Dw, DwP, Dw2,
+    Fw, FwP, Fw2,
+    Bw, BwP, Bw2,
+    M, MP, M2,
+    E, EP, E2,
+    S, SP, S2,
+    X, XP, X2,
+    Y, YP, Y2,
+    Z, ZP, Z2,
+}
+
+/// The layers a family of moves turns, and around which axis.
+struct Layers {
+    name: &'static str,
+    /// Lowercase name of wide moves, such as `r` for `Rw`.
+    short: Option<&'static str>,
+    axis: usize,
+    /// Which layers turn, by coordinate along the axis (-1, 0, 1).
+    layers: [bool; 3],
+    /// Direction of a clockwise turn, by the right-hand rule around the
+    /// positive axis.
+    sign: i32,
 }
 
+const fn layers(name: &'static str, short: Option<&'static str>, axis: usize, layers: [bool; 3], sign: i32) -> Layers {
+    Layers { name, short, axis, layers, sign }
+}
+
+/// The layers of each family of moves, in declaration order.
+const LAYERS: [Layers; 18] = [
+    layers("L", None, 0, [true, false, false], 1),
+    layers("R", None, 0, [false, false, true], -1),
+    layers("U", None, 1, [false, false, true], -1),
+    layers("D", None, 1, [true, false, false], 1),
+    layers("F", None, 2, [false, false, true], -1),
+    layers("B", None, 2, [true, false, false], 1),
+    layers("Lw", Some("l"), 0, [true, true, false], 1),
+    layers("Rw", Some("r"), 0, [false, true, true], -1),
+    layers("Uw", Some("u"), 1, [false, true, true], -1),
+    layers("Dw", Some("d"), 1, [true, true, false], 1),
+    layers("Fw", Some("f"), 2, [false, true, true], -1),
+    layers("Bw", Some("b"), 2, [true, true, false], 1),
+    layers("M", None, 0, [false, true, false], 1),
+    layers("E", None, 1, [false, true, false], 1),
+    layers("S", None, 2, [false, true, false], -1),
+    layers("x", None, 0, [true, true, true], -1),
+    layers("y", None, 1, [true, true, true], -1),
+    layers("z", None, 2, [true, true, true], -1),
+];
+
+/// Index in [`LAYERS`] of each face, in facelet string order.
+const FACE_LAYERS: [usize; 6] = [0, 2, 4, 3, 1, 5];
+
 impl Move {
-    pub fn face(&self) -> usize {
-        match self {
-            Move::L | Move::LP | Move::L2 => 0,
-            Move::U | Move::UP | Move::U2 => 1,
-            Move::F | Move::FP | Move::F2 => 2,
-            Move::D | Move::DP | Move::D2 => 3,
-            Move::R | Move::RP | Move::R2 => 4,
-            Move::B | Move::BP | Move::B2 => 5,
+    /// Every move, in declaration order.
+    pub const ALL: [Move; 54] = [
+        Move::L, Move::LP, Move::L2,
+        Move::R, Move::RP, Move::R2,
+        Move::U, Move::UP, Move::U2,
+        Move::D, Move::DP, Move::D2,
+        Move::F, Move::FP, Move::F2,
+        Move::B, Move::BP, Move::B2,
+        Move::Lw, Move::LwP, Move::Lw2,
+        Move::Rw, Move::RwP, Move::Rw2,
+        Move::Uw, Move::UwP, Move::Uw2,
+        Move::Dw, Move::DwP, Move::Dw2,
+        Move::Fw, Move::FwP, Move::Fw2,
+        Move::Bw, Move::BwP, Move::Bw2,
+        Move::M, Move::MP, Move::M2,
+        Move::E, Move::EP, Move::E2,
+        Move::S, Move::SP, Move::S2,
+        Move::X, Move::XP, Move::X2,
+        Move::Y, Move::YP, Move::Y2,
+        Move::Z, Move::ZP, Move::Z2,
+    ];
+
+    /// The 18 face turns, in declaration order. These are the moves the
+    /// solvers search with.
+    pub const FACE_TURNS: [Move; 18] = [
+        Move::L, Move::LP, Move::L2,
+        Move::R, Move::RP, Move::R2,
+        Move::U, Move::UP, Move::U2,
+        Move::D, Move::DP, Move::D2,
+        Move::F, Move::FP, Move::F2,
+        Move::B, Move::BP, Move::B2,
+    ];
+
+    /// The family of the move, such as `R` for `R'`, as an index into the
+    /// families in declaration order. Moves of the same family can be merged.
+    pub fn base(&self) -> usize {
+        *self as usize / 3
+    }
+
+    /// The face turned, in facelet string order, or `None` for wide turns,
+    /// slice turns and rotations.
+    pub fn face(&self) -> Option<usize> {
+        FACE_LAYERS.iter().position(|&l| l == self.base())
+    }
+
+    pub fn is_face_turn(&self) -> bool {
+        self.base() < 6
+    }
+
+    /// Whether the move turns only a middle layer: M, E or S.
+    pub fn is_slice(&self) -> bool {
+        (12..15).contains(&self.base())
+    }
+
+    pub fn is_rotation(&self) -> bool {
+        self.base() >= 15
+    }
+
+    /// The axis the move turns around: 0 for L and R (x), 1 for U and D (y),
+    /// 2 for F and B (z).
+    pub fn axis(&self) -> usize {
+        LAYERS[self.base()].axis
+    }
+
+    /// Whether the move turns the cubelet at a position.
+    pub fn affects(&self, position: (i32, i32, i32)) -> bool {
+        let coordinate = [position.0, position.1, position.2][self.axis()];
+        LAYERS[self.base()].layers[(coordinate + 1) as usize]
+    }
+
+    /// Clockwise quarter turns: 1, 2 or 3.
+    pub fn amount(&self) -> u8 {
+        [1, 3, 2][*self as usize % 3]
+    }
+
+    /// Quarter turns around the positive axis by the right-hand rule:
+    /// -2, -1, 1 or 2. Half turns keep the direction of the clockwise turn,
+    /// which only matters for animation.
+    fn quarter_turns(&self) -> i32 {
+        LAYERS[self.base()].sign * [1, -1, 2][*self as usize % 3]
+    }
+
+    /// The move of a family (see [`Move::base`]) turning by a number of
+    /// clockwise quarter turns, or `None` for a multiple of four.
+    pub fn from_base(base: usize, amount: u8) -> Option<Move> {
+        match amount % 4 {
+            0 => None,
+            a => Some(Move::ALL[base * 3 + [0, 2, 1][a as usize - 1]]),
+        }
+    }
+
+    /// The move turning a face (in facelet string order) by a number of
+    /// clockwise quarter turns, or `None` for a multiple of four.
+    pub fn from_face(face: usize, amount: u8) -> Option<Move> {
+        Move::from_base(FACE_LAYERS[face], amount)
+    }
+
+    /// The move that undoes this one.
+    pub fn inverse(&self) -> Move {
+        Move::from_base(self.base(), 4 - self.amount()).unwrap()
+    }
+
+    /// The move turning the layers of a family around the positive axis by
+    /// a number of quarter turns, by the right-hand rule.
+    fn from_layers(axis: usize, layers: [bool; 3], quarter_turns: i32) -> Option<Move> {
+        let base = LAYERS.iter().position(|l| l.axis == axis && l.layers == layers)?;
+        Move::from_base(base, (quarter_turns * LAYERS[base].sign).rem_euclid(4) as u8)
+    }
+
+    /// The move seen in a mirror perpendicular to an axis (see
+    /// [`Move::axis`]): mirroring across the M slice (axis 0) turns `R` into
+    /// `L'` and `U` into `U'`.
+    pub fn mirror(&self, axis: usize) -> Move {
+        let mut layers = LAYERS[self.base()].layers;
+        let mut quarter_turns = self.quarter_turns();
+        if self.axis() == axis {
+            // Turns around the mirror's axis keep their direction but
+            // swap sides.
+            layers.reverse();
+        } else {
+            quarter_turns = -quarter_turns;
         }
+        Move::from_layers(self.axis(), layers, quarter_turns).unwrap()
+    }
+
+    /// The move with the same effect as the rotation, this move and the
+    /// rotation undone: `R` rotated by `y` is `B`.
+    ///
+    /// Panics if `rotation` isn't a rotation.
+    pub fn rotate(&self, rotation: Move) -> Move {
+        assert!(rotation.is_rotation(), "{} is not a rotation", rotation);
+        let effect = CubeState::facelet_permutation([rotation, *self, rotation.inverse()]);
+        *Move::ALL.iter().find(|&&mv| CubeState::facelet_permutation([mv]) == effect).unwrap()
+    }
+
+    /// The 24 ways to hold a cube, as rotations from the standard
+    /// orientation: each of six faces on top, then turned by `y`.
+    pub fn orientations() -> [Vec<Move>; 24] {
+        let tops = [vec![], vec![Move::X], vec![Move::X2], vec![Move::XP], vec![Move::Z], vec![Move::ZP]];
+        std::array::from_fn(|i| {
+            let mut moves = tops[i / 4].clone();
+            moves.extend(Move::from_base(Move::Y.base(), (i % 4) as u8));
+            moves
+        })
+    }
+
+    /// Undo a sequence: the inverse of each move, in reverse order.
+    pub fn invert_sequence(moves: &[Move]) -> Vec<Move> {
+        moves.iter().rev().map(|mv| mv.inverse()).collect()
+    }
+
+    /// Merge turns of the same layers that are only separated by turns
+    /// around the same axis, and drop turns that cancel out.
+    pub fn simplify(moves: &[Move]) -> Vec<Move> {
+        let mut out: Vec<Move> = vec![];
+        for &mv in moves {
+            // An earlier turn of the same layers that mv commutes back to.
+            let merge = out.iter().rposition(|m| m.axis() != mv.axis() || m.base() == mv.base())
+                .filter(|&i| out[i].base() == mv.base());
+            match merge {
+                Some(i) => match Move::from_base(mv.base(), out[i].amount() + mv.amount()) {
+                    Some(merged) => out[i] = merged,
+                    None => {
+                        out.remove(i);
+                    }
+                },
+                None => out.push(mv),
+            }
+        }
+        out
     }
 
     pub fn transform(&self, t: f32) -> Mat4 {
-        match self {
-            Move::L | Move::RP => Mat4::from_angle_x(Rad(t * std::f32::consts::FRAC_PI_2)),
-            Move::LP | Move::R => Mat4::from_angle_x(Rad(-t * std::f32::consts::FRAC_PI_2)),
-            Move::U | Move::DP => Mat4::from_angle_y(Rad(-t * std::f32::consts::FRAC_PI_2)),
-            Move::UP | Move::D => Mat4::from_angle_y(Rad(t * std::f32::consts::FRAC_PI_2)),
-            Move::F | Move::BP => Mat4::from_angle_z(Rad(-t * std::f32::consts::FRAC_PI_2)),
-            Move::FP | Move::B => Mat4::from_angle_z(Rad(t * std::f32::consts::FRAC_PI_2)),
-            Move::L2 => Mat4::from_angle_x(Rad(t * std::f32::consts::PI)),
-            Move::R2 => Mat4::from_angle_x(Rad(-t * std::f32::consts::PI)),
-            Move::U2 => Mat4::from_angle_y(Rad(-t * std::f32::consts::PI)),
-            Move::D2 => Mat4::from_angle_y(Rad(t * std::f32::consts::PI)),
-            Move::F2 => Mat4::from_angle_z(Rad(-t * std::f32::consts::PI)),
-            Move::B2 => Mat4::from_angle_z(Rad(t * std::f32::consts::PI)),
+        let angle = Rad(t * self.quarter_turns() as f32 * std::f32::consts::FRAC_PI_2);
+        match self.axis() {
+            0 => Mat4::from_angle_x(angle),
+            1 => Mat4::from_angle_y(angle),
+            _ => Mat4::from_angle_z(angle),
         }
     }
 
-    pub fn from_str(input: &str) -> Result<Move, String> {
-        match input {
-            "L" => Ok(Move::L),
-            "L'" => Ok(Move::LP),
-            "L2" => Ok(Move::L2),
-            "R" => Ok(Move::R),
-            "R'" => Ok(Move::RP),
-            "R2" => Ok(Move::R2),
-            "U" => Ok(Move::U),
-            "U'" => Ok(Move::UP),
-            "U2" => Ok(Move::U2),
-            "D" => Ok(Move::D),
-            "D'" => Ok(Move::DP),
-            "D2" => Ok(Move::D2),
-            "F" => Ok(Move::F),
-            "F'" => Ok(Move::FP),
-            "F2" => Ok(Move::F2),
-            "B" => Ok(Move::B),
-            "B'" => Ok(Move::BP),
-            "B2" => Ok(Move::B2),
-            _ => Err(format!("Invalid move: {}", input))
+    /// The exact rotation of one quarter turn in the direction of the move.
+    fn quarter_matrix(&self) -> Mat3 {
+        match (self.axis(), self.quarter_turns() > 0) {
+            (0, true) => ROT_XY_CW,
+            (0, false) => ROT_XY_CCW,
+            (1, true) => ROT_XZ_CCW,
+            (1, false) => ROT_XZ_CW,
+            (_, true) => ROT_YZ_CCW,
+            (_, false) => ROT_YZ_CW,
         }
     }
 
     pub fn from_sequence(input: &str) -> Result<impl Iterator<Item = Move>, String> {
         let mut output: Vec<Move> = vec![];
         for m in input.split_ascii_whitespace() {
-            let mv = Move::from_str(m)?;
+            let mv = m.parse::<Move>()?;
             output.push(mv);
         }
         Ok(output.into_iter())
     }
+
+    /// Write a sequence in standard notation, separated by spaces.
+    pub fn to_sequence(moves: &[Move]) -> String {
+        moves.iter().map(|mv| mv.to_string()).collect::<Vec<_>>().join(" ")
+    }
+}
+
+impl FromStr for Move {
+    type Err = String;
+
+    /// Parse a move such as `R`, `U'`, `F2`, `Rw`, `r'`, `M2` or `y`.
+    /// `2'` is taken as `2`.
+    fn from_str(input: &str) -> Result<Move, String> {
+        let (name, amount) = if let Some(name) = input.strip_suffix("2'").or(input.strip_suffix('2')) {
+            (name, 2)
+        } else if let Some(name) = input.strip_suffix('\'') {
+            (name, 3)
+        } else {
+            (input, 1)
+        };
+        LAYERS.iter()
+            .position(|l| l.name == name || l.short == Some(name))
+            .and_then(|base| Move::from_base(base, amount))
+            .ok_or(format!("Invalid move: {}", input))
+    }
 }
 
-impl <'a> IntoIterator for Move {
+impl IntoIterator for Move {
     type Item = Move;
     type IntoIter = std::vec::IntoIter<Move>;
 
@@ -179,16 +492,21 @@ impl <'a> IntoIterator for Move {
 
 pub struct Cube {
     pub(crate) pieces: Vec<Piece>,
-    current_move: Option<Move>,
-    current_face: Option<[usize; 9]>,
-    move_start: f32,
-    move_queue: VecDeque<Move>,
-    move_time: f32,
+    gm: Gm<InstancedMesh, RubikMaterial>,
+    state: CubeState,
+    moves: MoveQueue,
     move_slope: f32,
+    theme: Theme,
 }
 
+/// How a cube animates its moves.
+#[derive(Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "camelCase", default, deny_unknown_fields))]
 pub struct CubeAnimationOptions {
+    /// Duration of one move, in milliseconds.
     pub move_time: f32,
+    /// How strongly moves ease in and out; 1 turns at constant speed.
     pub move_smoothing: f32,
 }
 
@@ -203,23 +521,17 @@ impl Default for CubeAnimationOptions {
 
 impl Cube {
     pub fn from_facelet_str(ctx: &three_d::Context, fstr: String, anim: CubeAnimationOptions) -> Result<Cube, String> {
+        Ok(Self::from_state(ctx, CubeState::from_facelet_str(&fstr)?, anim))
+    }
+
+    pub fn from_state(ctx: &three_d::Context, state: CubeState, anim: CubeAnimationOptions) -> Cube {
         let mut pieces = vec![];
         for i in 0..27 {
-            let position = (i as i32 / 9 - 1, 2 - (i as i32 / 3) % 3 - 1, i as i32 % 3 - 1);
+            let position = state::cubelet_position(i);
             pieces.push((position, (Color::None, Color::None, Color::None)));
         }
-        for facelet in 0..54 {
+        for (facelet, &color) in state.facelets().iter().enumerate() {
             let cubelet = FACELETS[facelet];
-            let color = match fstr.chars().nth(facelet) {
-                Some('B') => Color::Blue,
-                Some('Y') => Color::Yellow,
-                Some('R') => Color::Red,
-                Some('W') => Color::White,
-                Some('G') => Color::Green,
-                Some('O') => Color::Orange,
-                Some(c) => return Err(format!("Invalid char {}", c)),
-                None => return Err("Invalid string length".to_string())
-            };
             match facelet / 9 {
                 0 | 4 => {
                     pieces[cubelet].1.0 = color;
@@ -227,148 +539,131 @@ impl Cube {
                 1 | 3 => {
                     pieces[cubelet].1.1 = color;
                 },
-                2 | 5 => {
+                _ => {
                     pieces[cubelet].1.2 = color;
                 },
-                _ => { return Err(format!("Error in facelet conversion: {} / 9 = {}", facelet, facelet/9)) }
             }
         }
-        let pieces = pieces.into_iter().enumerate().map(|(_i, (position, color))| {
-            // let calculated_index = (position.0 + 1) * 9 + (1 - position.1) * 3 + (position.2 + 1);
-            // println!("{} -> {}: {:?}, {:?}", _i, calculated_index, position, color);
-            let mut mesh = CpuMesh::cube();
-            mesh.uvs = Some(cube_uvs());
-            mesh.transform(&Mat4::from_scale(0.5)).unwrap();
-            mesh.transform(
-                &Mat4::from_translation(Vec3::new(position.0 as f32, position.1 as f32, position.2 as f32))
-            ).unwrap();
-            let mut face_colors = vec![Srgba::BLACK; 36];
-            for i in 0..6 {
-                let face_color = match i {
-                    0 => if position.0 == -1 { color.0 } else { Color::None },
-                    1 => if position.1 ==  1 { color.1 } else { Color::None },
-                    2 => if position.2 ==  1 { color.2 } else { Color::None },
-                    3 => if position.1 == -1 { color.1 } else { Color::None },
-                    4 => if position.0 ==  1 { color.0 } else { Color::None },
-                    5 => if position.2 == -1 { color.2 } else { Color::None },
-                    _ => Color::None,
-                };
-                for j in 0..6 {
-                    // CpuMesh::cube() has a different face order
-                    face_colors[[5,0,3,1,4,2][i] * 6 + j] = face_color.into();
-                }
-            }
-            mesh.colors = Some(face_colors);
-            Piece::new(position, color, Mesh::new(&ctx, &mesh))
-        }).collect::<Vec<_>>();
-        Ok(Cube {
+        let pieces = pieces.into_iter()
+            .map(|(position, color)| Piece::new(position, color))
+            .collect::<Vec<_>>();
+        let theme = Theme::default();
+        let instances = Self::instances(&pieces, &theme);
+        let gm = Gm::new(InstancedMesh::new(ctx, &instances, &sticker_mesh()), RubikMaterial::default());
+        Cube {
             pieces,
-            current_move: None,
-            current_face: None,
-            move_start: 0.0,
-            move_queue: VecDeque::new(),
-            move_time: anim.move_time,
+            gm,
+            state,
+            moves: MoveQueue::new(anim.move_time),
             move_slope: anim.move_smoothing,
-        })
+            theme,
+        }
     }
 
-    fn face_iter(&mut self, face: usize) -> impl Iterator<Item=&mut Piece> {
-        let face_cis: Vec<&usize> = FACELETS.iter().skip(face * 9).take(9).collect();
-        self.pieces.iter_mut()
-            .filter(move |p| face_cis.contains(&&p.cubelet()))
+    /// The state of the cube after all moves that have finished animating.
+    pub fn state(&self) -> &CubeState {
+        &self.state
     }
 
-    fn face(&self, face: usize) -> [usize; 9] {
-        let face_cis: Vec<&usize> = FACELETS.iter().skip(face * 9).take(9).collect();
-        self.pieces.iter().enumerate()
-            .filter_map(|(i, p)| if face_cis.contains(&&p.cubelet()) { Some(i) } else { None })
-            .collect::<Vec<_>>()
-            .try_into()
-            .unwrap()
+    /// The color of a sticker of a face (in L, U, F, D, R, B order), by its
+    /// index on the face, reading rows as seen from outside the cube. None
+    /// if there's no such face (0 to 5) or index (0 to 8).
+    pub fn sticker(&self, face: usize, index: usize) -> Option<Color> {
+        (face < 6 && index < 9).then(|| self.state.facelet(face * 9 + index))
+    }
+
+    /// The pieces of the cube, for queries such as which piece is in a slot,
+    /// what is solved and which cycles the pieces form. Fails if the stickers
+    /// don't make up a real cube.
+    pub fn cubies(&self) -> Result<CubieCube, String> {
+        CubieCube::from_state(&self.state)
     }
 
-    fn rotate_face(&mut self, face: usize, mat: Mat3) {
-        self.face_iter(face).for_each(|piece| {
-            // let prev = piece.position;
-            piece.rotate(mat).unwrap();
-            // trace!("rot {}: {:?} -> {:?}", face, prev, piece.position);
-        });
+    /// Per-sticker instance data for all pieces: six instances per piece,
+    /// all sharing a single sticker mesh.
+    fn instances(pieces: &[Piece], theme: &Theme) -> Instances {
+        Instances {
+            transformations: pieces.iter().flat_map(|p| p.sticker_transforms()).collect(),
+            colors: Some(pieces.iter().flat_map(|p| p.stickers(theme)).collect()),
+            ..Default::default()
+        }
+    }
+
+    pub fn theme(&self) -> &Theme {
+        &self.theme
+    }
+
+    /// Draw the cube with other colors.
+    pub fn set_theme(&mut self, theme: Theme) {
+        self.theme = theme;
+        self.update_instances();
+    }
+
+    fn update_instances(&mut self) {
+        let instances = Self::instances(&self.pieces, &self.theme);
+        self.gm.geometry.set_instances(&instances);
+    }
+
+    /// Indices of the pieces a move turns.
+    fn affected(&self, mv: Move) -> Vec<usize> {
+        self.pieces.iter().enumerate()
+            .filter_map(|(i, p)| if mv.affects(state::cubelet_position(p.cubelet())) { Some(i) } else { None })
+            .collect()
     }
 
     fn apply_move(&mut self, mv: Move) {
-        match mv {
-            Move::L => self.rotate_face(0, ROT_XY_CW),
-            Move::LP => self.rotate_face(0, ROT_XY_CCW),
-            Move::L2 => {
-                self.rotate_face(0, ROT_XY_CW);
-                self.rotate_face(0, ROT_XY_CW);
-            },
-            Move::R => self.rotate_face(4, ROT_XY_CCW),
-            Move::RP => self.rotate_face(4, ROT_XY_CW),
-            Move::R2 => {
-                self.rotate_face(4, ROT_XY_CCW);
-                self.rotate_face(4, ROT_XY_CCW);
-            },
-            Move::U => self.rotate_face(1, ROT_XZ_CW),
-            Move::UP => self.rotate_face(1, ROT_XZ_CCW),
-            Move::U2 => {
-                self.rotate_face(1, ROT_XZ_CW);
-                self.rotate_face(1, ROT_XZ_CW);
-            },
-            Move::D => self.rotate_face(3, ROT_XZ_CCW),
-            Move::DP => self.rotate_face(3, ROT_XZ_CW),
-            Move::D2 => {
-                self.rotate_face(3, ROT_XZ_CCW);
-                self.rotate_face(3, ROT_XZ_CCW);
-            },
-            Move::F => self.rotate_face(2, ROT_YZ_CW),
-            Move::FP => self.rotate_face(2, ROT_YZ_CCW),
-            Move::F2 => {
-                self.rotate_face(2, ROT_YZ_CW);
-                self.rotate_face(2, ROT_YZ_CW);
-            },
-            Move::B => self.rotate_face(5, ROT_YZ_CCW),
-            Move::BP => self.rotate_face(5, ROT_YZ_CW),
-            Move::B2 => {
-                self.rotate_face(5, ROT_YZ_CCW);
-                self.rotate_face(5, ROT_YZ_CCW);
-            },
+        self.state.apply(mv);
+        let mat = mv.quarter_matrix();
+        let quarters = if mv.amount() == 2 { 2 } else { 1 };
+        for _ in 0..quarters {
+            for i in self.affected(mv) {
+                self.pieces[i].rotate(mat).unwrap();
+            }
         }
     }
 
     pub fn queue(&mut self, mv: impl Iterator<Item = Move>) {
-        self.move_queue.extend(mv);
+        self.moves.extend(mv);
+    }
+
+    /// Whether there are no moves animating or waiting in the queue.
+    pub fn is_idle(&self) -> bool {
+        self.moves.is_idle()
     }
 
     pub fn animate(&mut self, time: f32) {
-        if let (Some(mv), Some(cface)) = (self.current_move, self.current_face) {
-            let elapsed = time - self.move_start;
-            if elapsed > self.move_time {
-                for ci in cface {
-                    let piece = &mut self.pieces[ci];
-                    piece.transform = mv.transform(1.0) * piece.transform;
-                    piece.transform(Mat4::identity());
-                }
-                self.apply_move(mv);
-                // trace!("Applied move {:?}", mv);
-                self.current_move = None;
-            } else {
-                let x = crate::ease(elapsed / self.move_time, self.move_slope);
-                for ci in cface {
-                    let piece = &mut self.pieces[ci];
-                    piece.transform(mv.transform(x));
-                }
+        let (finished, turning) = self.moves.advance(time);
+        for &mv in &finished {
+            for ci in self.affected(mv) {
+                let piece = &mut self.pieces[ci];
+                piece.transform = mv.transform(1.0) * piece.transform;
+                piece.transform(Mat4::identity());
+            }
+            self.apply_move(mv);
+        }
+        if let Some((mv, done)) = turning {
+            let x = crate::ease(done, self.move_slope);
+            for ci in self.affected(mv) {
+                self.pieces[ci].transform(mv.transform(x));
             }
-        } else if let Some(nmv) = self.move_queue.pop_front() {
-            self.current_move = Some(nmv);
-            self.current_face = Some(self.face(nmv.face()));
-            self.move_start = time;
-            // trace!("New move {:?} will affect {:?}", nmv, self.face_iter(nmv.face()).map(|c| c.cubelet()).collect::<Vec<_>>());
+        }
+        if !finished.is_empty() || turning.is_some() {
+            self.update_instances();
         }
     }
 
     pub fn solved(ctx: &three_d::Context, anim: CubeAnimationOptions) -> Cube {
-        Self::from_facelet_str(ctx, "BBBBBBBBBYYYYYYYYYRRRRRRRRRWWWWWWWWWGGGGGGGGGOOOOOOOOO".to_string(), anim).unwrap()
+        Self::from_state(ctx, CubeState::solved(), anim)
+    }
+
+    /// A copy of this cube using another context, for example after the
+    /// previous one was lost. The move being animated starts over.
+    pub fn recreate(&self, ctx: &three_d::Context) -> Cube {
+        let anim = CubeAnimationOptions { move_time: self.moves.move_time(), move_smoothing: self.move_slope };
+        let mut cube = Self::from_state(ctx, self.state.clone(), anim);
+        cube.set_theme(self.theme);
+        cube.queue(self.moves.moves());
+        cube
     }
 }
 
@@ -377,9 +672,52 @@ impl<'a> IntoIterator for &'a Cube {
     type IntoIter = std::vec::IntoIter<&'a dyn Object>;
 
     fn into_iter(self) -> Self::IntoIter {
-        self.pieces.iter()
-            .map(|p| p as &dyn Object)
-            .collect::<Vec<_>>()
-            .into_iter()
+        vec![&self.gm as &dyn Object].into_iter()
+    }
+}
+
+impl fmt::Display for Move {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let suffix = ["", "2", "'"][self.amount() as usize - 1];
+        write!(f, "{}{}", LAYERS[self.base()].name, suffix)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn move_notation_round_trips() {
+        for mv in Move::ALL {
+            assert_eq!(mv.to_string().parse::<Move>(), Ok(mv));
+        }
+        let moves: Vec<Move> = Move::from_sequence("R U' F2 Rw r' M2 E S' x y2 z'").unwrap().collect();
+        assert_eq!(moves.len(), 11);
+        assert_eq!(Move::from_sequence(&Move::to_sequence(&moves)).unwrap().collect::<Vec<_>>(), moves);
+        assert_eq!("U2'".parse::<Move>(), Ok(Move::U2));
+        assert!("Q".parse::<Move>().is_err());
+    }
+
+    #[test]
+    fn inverse_undoes() {
+        for mv in Move::ALL {
+            assert_eq!(CubeState::facelet_permutation([mv, mv.inverse()]), CubeState::facelet_permutation([]));
+        }
+        let moves: Vec<Move> = Move::from_sequence("R U Rw' M2 y F").unwrap().collect();
+        let mut state = CubeState::solved();
+        state.apply_all(moves.iter().copied());
+        state.apply_all(Move::invert_sequence(&moves));
+        assert_eq!(state, CubeState::solved());
+    }
+
+    #[test]
+    fn orientations_are_distinct() {
+        let mut seen: Vec<[usize; 54]> = Move::orientations().iter()
+            .map(|rotation| CubeState::facelet_permutation(rotation.iter().copied()))
+            .collect();
+        seen.sort();
+        seen.dedup();
+        assert_eq!(seen.len(), 24);
     }
 }
@@ -0,0 +1,457 @@
+use std::sync::OnceLock;
+
+use super::state::facelet_at;
+use super::{Color, CubeState, Move};
+
+type Point = (i32, i32, i32);
+
+/// Positions of the corner slots, in the order URF, UFL, ULB, UBR, DFR, DLF,
+/// DBL, DRB, and the normals of their facelets, starting with the U or D
+/// facelet and going clockwise.
+const CORNERS: [(Point, [Point; 3]); 8] = [
+    ((1, 1, 1), [(0, 1, 0), (1, 0, 0), (0, 0, 1)]),
+    ((-1, 1, 1), [(0, 1, 0), (0, 0, 1), (-1, 0, 0)]),
+    ((-1, 1, -1), [(0, 1, 0), (-1, 0, 0), (0, 0, -1)]),
+    ((1, 1, -1), [(0, 1, 0), (0, 0, -1), (1, 0, 0)]),
+    ((1, -1, 1), [(0, -1, 0), (0, 0, 1), (1, 0, 0)]),
+    ((-1, -1, 1), [(0, -1, 0), (-1, 0, 0), (0, 0, 1)]),
+    ((-1, -1, -1), [(0, -1, 0), (0, 0, -1), (-1, 0, 0)]),
+    ((1, -1, -1), [(0, -1, 0), (1, 0, 0), (0, 0, -1)]),
+];
+
+/// Positions of the edge slots, in the order UR, UF, UL, UB, DR, DF, DL, DB,
+/// FR, FL, BL, BR, and the normals of their facelets, starting with the U or
+/// D facelet, or the F or B facelet for middle layer edges.
+const EDGES: [(Point, [Point; 2]); 12] = [
+    ((1, 1, 0), [(0, 1, 0), (1, 0, 0)]),
+    ((0, 1, 1), [(0, 1, 0), (0, 0, 1)]),
+    ((-1, 1, 0), [(0, 1, 0), (-1, 0, 0)]),
+    ((0, 1, -1), [(0, 1, 0), (0, 0, -1)]),
+    ((1, -1, 0), [(0, -1, 0), (1, 0, 0)]),
+    ((0, -1, 1), [(0, -1, 0), (0, 0, 1)]),
+    ((-1, -1, 0), [(0, -1, 0), (-1, 0, 0)]),
+    ((0, -1, -1), [(0, -1, 0), (0, 0, -1)]),
+    ((1, 0, 1), [(0, 0, 1), (1, 0, 0)]),
+    ((-1, 0, 1), [(0, 0, 1), (-1, 0, 0)]),
+    ((-1, 0, -1), [(0, 0, -1), (-1, 0, 0)]),
+    ((1, 0, -1), [(0, 0, -1), (1, 0, 0)]),
+];
+
+pub const CORNER_NAMES: [&str; 8] = ["URF", "UFL", "ULB", "UBR", "DFR", "DLF", "DBL", "DRB"];
+pub const EDGE_NAMES: [&str; 12] = ["UR", "UF", "UL", "UB", "DR", "DF", "DL", "DB", "FR", "FL", "BL", "BR"];
+
+/// The facelets of each corner slot, in the order of [`CORNERS`].
+pub(crate) fn corner_facelets() -> &'static [[usize; 3]; 8] {
+    static FACELETS: OnceLock<[[usize; 3]; 8]> = OnceLock::new();
+    FACELETS.get_or_init(|| {
+        CORNERS.map(|(position, normals)| normals.map(|n| facelet_at(position, n).unwrap()))
+    })
+}
+
+/// The facelets of each edge slot, in the order of [`EDGES`].
+pub(crate) fn edge_facelets() -> &'static [[usize; 2]; 12] {
+    static FACELETS: OnceLock<[[usize; 2]; 12]> = OnceLock::new();
+    FACELETS.get_or_init(|| {
+        EDGES.map(|(position, normals)| normals.map(|n| facelet_at(position, n).unwrap()))
+    })
+}
+
+/// A cycle of misplaced or misoriented pieces.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Cycle {
+    /// Slots of the cycle: the piece in each slot belongs in the next one,
+    /// and the piece in the last slot in the first.
+    pub slots: Vec<usize>,
+    /// Net twist (corners, clockwise) or flip (edges) of the pieces of the
+    /// cycle, which is left once they are all moved to their slots.
+    pub orientation: u8,
+}
+
+/// Cycles of a permutation where piece `perm[i]` is in slot `i`, leaving out
+/// pieces that are solved.
+fn cycles(perm: &[u8], orientation: &[u8], modulus: u8) -> Vec<Cycle> {
+    let mut seen = vec![false; perm.len()];
+    let mut out = vec![];
+    for start in 0..perm.len() {
+        if seen[start] {
+            continue;
+        }
+        let mut slots = vec![];
+        let mut slot = start;
+        while !seen[slot] {
+            seen[slot] = true;
+            slots.push(slot);
+            slot = perm[slot] as usize;
+        }
+        let orientation = slots.iter().map(|&s| orientation[s]).sum::<u8>() % modulus;
+        if slots.len() > 1 || orientation != 0 {
+            out.push(Cycle { slots, orientation });
+        }
+    }
+    out
+}
+
+/// A cube described by where each corner and edge piece is and how it's
+/// twisted or flipped, as used by the solvers.
+///
+/// `cp[i]` is the corner piece in slot `i` (in URF, UFL, ULB, UBR, DFR, DLF,
+/// DBL, DRB order) and `co[i]` its twist: which of the slot's facelets,
+/// counting clockwise from the U or D facelet, shows the piece's U or D
+/// color. Edges work the same way with `ep` and `eo`, in UR, UF, UL, UB, DR,
+/// DF, DL, DB, FR, FL, BL, BR order.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub struct CubieCube {
+    pub cp: [u8; 8],
+    pub co: [u8; 8],
+    pub ep: [u8; 12],
+    pub eo: [u8; 12],
+}
+
+impl Default for CubieCube {
+    fn default() -> Self {
+        CubieCube::SOLVED
+    }
+}
+
+impl CubieCube {
+    pub const SOLVED: CubieCube = CubieCube {
+        cp: [0, 1, 2, 3, 4, 5, 6, 7],
+        co: [0; 8],
+        ep: [0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11],
+        eo: [0; 12],
+    };
+
+    /// Identify every piece of a sticker state. Faces are recognised by the
+    /// color of their center, so any color scheme works, but the state must
+    /// be reachable by turning faces of a real cube.
+    pub fn from_state(state: &CubeState) -> Result<CubieCube, String> {
+        let centers: Vec<Color> = (0..6).map(|face| state.facelet(face * 9 + 4)).collect();
+        let face_of = |color: Color| {
+            centers.iter().position(|&c| c == color).ok_or(format!("No center with color {:?}", color))
+        };
+        let mut count = [0; 6];
+        for &color in state.facelets() {
+            count[face_of(color)?] += 1;
+        }
+        if count.iter().any(|&n| n != 9) {
+            return Err("Every color must appear on exactly 9 stickers".to_string());
+        }
+
+        let solved_corners = corner_facelets().map(|fs| fs.map(|f| f / 9));
+        let solved_edges = edge_facelets().map(|fs| fs.map(|f| f / 9));
+        let mut cube = CubieCube::SOLVED;
+        for (slot, facelets) in corner_facelets().iter().enumerate() {
+            let faces = facelets.map(|f| face_of(state.facelet(f)).unwrap());
+            let twist = (0..3).find(|&i| faces[i] == 1 || faces[i] == 3)
+                .ok_or(format!("Corner {} has no U or D sticker", CORNER_NAMES[slot]))?;
+            let piece = solved_corners.iter()
+                .position(|s| (0..3).all(|i| s[i] == faces[(i + twist) % 3]))
+                .ok_or(format!("Corner {} doesn't exist", CORNER_NAMES[slot]))?;
+            cube.cp[slot] = piece as u8;
+            cube.co[slot] = twist as u8;
+        }
+        for (slot, facelets) in edge_facelets().iter().enumerate() {
+            let faces = facelets.map(|f| face_of(state.facelet(f)).unwrap());
+            let (piece, flip) = solved_edges.iter().enumerate()
+                .find_map(|(piece, s)| {
+                    if *s == faces {
+                        Some((piece, 0))
+                    } else if s[0] == faces[1] && s[1] == faces[0] {
+                        Some((piece, 1))
+                    } else {
+                        None
+                    }
+                })
+                .ok_or(format!("Edge {} doesn't exist", EDGE_NAMES[slot]))?;
+            cube.ep[slot] = piece as u8;
+            cube.eo[slot] = flip;
+        }
+        cube.verify()?;
+        Ok(cube)
+    }
+
+    /// The sticker state of this cube, with the standard color scheme.
+    pub fn to_state(&self) -> CubeState {
+        let mut facelets: [Color; 54] = std::array::from_fn(|f| Color::FACES[f / 9]);
+        let corners = corner_facelets();
+        let edges = edge_facelets();
+        for slot in 0..8 {
+            let piece = &corners[self.cp[slot] as usize];
+            for i in 0..3 {
+                facelets[corners[slot][(i + self.co[slot] as usize) % 3]] = Color::FACES[piece[i] / 9];
+            }
+        }
+        for slot in 0..12 {
+            let piece = &edges[self.ep[slot] as usize];
+            for i in 0..2 {
+                facelets[edges[slot][(i + self.eo[slot] as usize) % 2]] = Color::FACES[piece[i] / 9];
+            }
+        }
+        CubeState::from_facelets(facelets)
+    }
+
+    /// Check that every piece appears once, that twists and flips add up,
+    /// and that corner and edge permutations have the same parity.
+    pub fn verify(&self) -> Result<(), String> {
+        let mut corners = [false; 8];
+        for &c in &self.cp {
+            corners[c as usize] = true;
+        }
+        let mut edges = [false; 12];
+        for &e in &self.ep {
+            edges[e as usize] = true;
+        }
+        if corners.contains(&false) {
+            return Err("Some corner appears twice".to_string());
+        }
+        if edges.contains(&false) {
+            return Err("Some edge appears twice".to_string());
+        }
+        if self.co.iter().map(|&o| o as u32).sum::<u32>() % 3 != 0 {
+            return Err("Corner twist doesn't add up: one corner is twisted".to_string());
+        }
+        if self.eo.iter().map(|&o| o as u32).sum::<u32>() % 2 != 0 {
+            return Err("Edge flip doesn't add up: one edge is flipped".to_string());
+        }
+        if permutation_parity(&self.cp) != permutation_parity(&self.ep) {
+            return Err("Permutation parity doesn't match: two pieces are swapped".to_string());
+        }
+        Ok(())
+    }
+
+    /// The cube after doing `self` and then `other`.
+    pub fn multiply(&self, other: &CubieCube) -> CubieCube {
+        let mut out = CubieCube::SOLVED;
+        for i in 0..8 {
+            let from = other.cp[i] as usize;
+            out.cp[i] = self.cp[from];
+            out.co[i] = (self.co[from] + other.co[i]) % 3;
+        }
+        for i in 0..12 {
+            let from = other.ep[i] as usize;
+            out.ep[i] = self.ep[from];
+            out.eo[i] = (self.eo[from] + other.eo[i]) % 2;
+        }
+        out
+    }
+
+    /// The cube that undoes `self`.
+    pub fn inverse(&self) -> CubieCube {
+        let mut out = CubieCube::SOLVED;
+        for i in 0..8 {
+            let piece = self.cp[i] as usize;
+            out.cp[piece] = i as u8;
+            out.co[piece] = (3 - self.co[i]) % 3;
+        }
+        for i in 0..12 {
+            let piece = self.ep[i] as usize;
+            out.ep[piece] = i as u8;
+            out.eo[piece] = self.eo[i];
+        }
+        out
+    }
+
+    /// The effect of a single face turn.
+    ///
+    /// Panics for other moves: they move the centers, which a `CubieCube`
+    /// doesn't track.
+    pub fn from_move(mv: Move) -> CubieCube {
+        assert!(mv.is_face_turn(), "{} is not a face turn", mv);
+        static MOVES: OnceLock<Vec<CubieCube>> = OnceLock::new();
+        MOVES.get_or_init(|| {
+            Move::FACE_TURNS.iter().map(|&mv| {
+                let mut state = CubeState::solved();
+                state.apply(mv);
+                CubieCube::from_state(&state).unwrap()
+            }).collect()
+        })[mv as usize]
+    }
+
+    /// Apply a face turn; see [`CubieCube::from_move`].
+    pub fn apply(&mut self, mv: Move) {
+        *self = self.multiply(&CubieCube::from_move(mv));
+    }
+
+    pub fn apply_all(&mut self, moves: impl IntoIterator<Item = Move>) {
+        for mv in moves {
+            self.apply(mv);
+        }
+    }
+
+    pub fn is_solved(&self) -> bool {
+        *self == CubieCube::SOLVED
+    }
+
+    /// The corner piece in a slot and its twist, or None if there's no
+    /// such slot (0 to 7).
+    pub fn corner(&self, slot: usize) -> Option<(usize, u8)> {
+        Some((*self.cp.get(slot)? as usize, self.co[slot]))
+    }
+
+    /// The edge piece in a slot and its flip, or None if there's no such
+    /// slot (0 to 11).
+    pub fn edge(&self, slot: usize) -> Option<(usize, u8)> {
+        Some((*self.ep.get(slot)? as usize, self.eo[slot]))
+    }
+
+    /// Whether a corner slot holds its own piece, untwisted.
+    pub fn is_corner_solved(&self, slot: usize) -> bool {
+        self.corner(slot) == Some((slot, 0))
+    }
+
+    /// Whether an edge slot holds its own piece, unflipped.
+    pub fn is_edge_solved(&self, slot: usize) -> bool {
+        self.edge(slot) == Some((slot, 0))
+    }
+
+    /// How many of the 20 corners and edges are solved.
+    pub fn solved_pieces(&self) -> usize {
+        (0..8).filter(|&slot| self.is_corner_solved(slot)).count()
+            + (0..12).filter(|&slot| self.is_edge_solved(slot)).count()
+    }
+
+    /// Whether the four D layer edges are solved.
+    pub fn is_cross_solved(&self) -> bool {
+        (4..8).all(|slot| self.is_edge_solved(slot))
+    }
+
+    /// For each first two layers slot (FR, FL, BL, BR), whether its corner
+    /// and edge are solved. The cross doesn't have to be.
+    pub fn solved_pairs(&self) -> [bool; 4] {
+        std::array::from_fn(|i| self.is_corner_solved(4 + i) && self.is_edge_solved(8 + i))
+    }
+
+    /// Whether the cross and all four pairs are solved.
+    pub fn is_f2l_solved(&self) -> bool {
+        self.is_cross_solved() && self.solved_pairs().iter().all(|&solved| solved)
+    }
+
+    /// Whether every U layer piece shows its U color on the U face.
+    pub fn is_last_layer_oriented(&self) -> bool {
+        self.co[..4].iter().all(|&o| o == 0) && self.eo[..4].iter().all(|&o| o == 0)
+    }
+
+    /// Whether every U layer piece is solved.
+    pub fn is_last_layer_solved(&self) -> bool {
+        (0..4).all(|slot| self.is_corner_solved(slot) && self.is_edge_solved(slot))
+    }
+
+    /// The cycles the corners form, starting from the lowest slot of each.
+    /// Corners twisted in place are cycles of one slot.
+    pub fn corner_cycles(&self) -> Vec<Cycle> {
+        cycles(&self.cp, &self.co, 3)
+    }
+
+    /// The cycles the edges form, starting from the lowest slot of each.
+    /// Edges flipped in place are cycles of one slot.
+    pub fn edge_cycles(&self) -> Vec<Cycle> {
+        cycles(&self.ep, &self.eo, 2)
+    }
+}
+
+/// Whether a permutation is odd.
+pub(crate) fn permutation_parity(perm: &[u8]) -> bool {
+    let mut odd = false;
+    for i in 0..perm.len() {
+        for j in i + 1..perm.len() {
+            if perm[i] > perm[j] {
+                odd = !odd;
+            }
+        }
+    }
+    odd
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scramble::{random_cube, Rng};
+
+    #[test]
+    fn state_round_trips() {
+        let mut rng = Rng::new(1);
+        for _ in 0..200 {
+            let cube = random_cube(&mut rng);
+            let state = cube.to_state();
+            assert_eq!(CubieCube::from_state(&state), Ok(cube));
+            assert_eq!(CubieCube::from_state(&state).unwrap().to_state(), state);
+        }
+    }
+
+    #[test]
+    fn moves_match_stickers() {
+        let mut cube = CubieCube::SOLVED;
+        let mut state = CubeState::solved();
+        for mv in Move::FACE_TURNS.iter().cycle().step_by(7).take(60) {
+            cube.apply(*mv);
+            state.apply(*mv);
+            assert_eq!(cube.to_state(), state);
+        }
+    }
+
+    #[test]
+    fn invalid_states_are_rejected() {
+        let mut cube = CubieCube::SOLVED;
+        cube.co[0] = 1;
+        assert!(CubieCube::from_state(&cube.to_state()).is_err());
+        let mut cube = CubieCube::SOLVED;
+        cube.ep.swap(0, 1);
+        assert!(CubieCube::from_state(&cube.to_state()).is_err());
+    }
+
+    fn cubies(sequence: &str) -> CubieCube {
+        let mut cube = CubieCube::SOLVED;
+        cube.apply_all(Move::from_sequence(sequence).unwrap());
+        cube
+    }
+
+    #[test]
+    fn cycles() {
+        let t_perm = cubies("R U R' U' R' F R2 U' R' U' R U R' F'");
+        assert_eq!(t_perm.corner_cycles(), [Cycle { slots: vec![0, 3], orientation: 0 }]);
+        assert_eq!(t_perm.edge_cycles(), [Cycle { slots: vec![0, 2], orientation: 0 }]);
+        let sune = cubies("R U R' U R U2 R'");
+        assert_eq!(sune.edge_cycles(), [Cycle { slots: vec![0, 2, 3], orientation: 0 }]);
+        assert_eq!(sune.corner_cycles(), [
+            Cycle { slots: vec![0, 2], orientation: 2 },
+            Cycle { slots: vec![1, 3], orientation: 1 },
+        ]);
+        let twist = cubies("R' D' R D R' D' R D U D' R' D R D' R' D R U'");
+        assert_eq!(twist.corner_cycles(), [
+            Cycle { slots: vec![0], orientation: 2 },
+            Cycle { slots: vec![3], orientation: 1 },
+        ]);
+        assert_eq!(CubieCube::SOLVED.corner_cycles(), []);
+    }
+
+    #[test]
+    fn solved_parts() {
+        assert_eq!(CubieCube::SOLVED.solved_pieces(), 20);
+        assert_eq!(cubies("R").solved_pieces(), 12);
+        assert_eq!(cubies("R U R' U' R' F R2 U' R' U' R U R' F'").solved_pieces(), 16);
+        assert_eq!(cubies("R U R'").solved_pairs(), [false, true, true, true]);
+        assert_eq!(cubies("L' U' L").solved_pairs(), [true, false, true, true]);
+        assert_eq!(cubies("D").solved_pairs(), [false; 4]);
+    }
+
+    #[test]
+    fn last_layer() {
+        assert!(cubies("R U R' U' R' F R2 U' R' U' R U R' F'").is_last_layer_oriented());
+        assert!(cubies("U").is_last_layer_oriented());
+        assert!(!cubies("U").is_last_layer_solved());
+        assert!(!cubies("R U R' U R U2 R'").is_last_layer_oriented());
+        assert!(!cubies("F R U R' U' F'").is_last_layer_oriented());
+        assert!(cubies("R U R' U R U2 R'").is_f2l_solved());
+        assert!(!cubies("R U R'").is_f2l_solved());
+    }
+
+    #[test]
+    fn pieces() {
+        let cube = cubies("R");
+        assert_eq!(cube.corner(0), Some((4, 2)));
+        assert_eq!(cube.edge(0), Some((8, 0)));
+        assert_eq!(cube.corner(8), None);
+        assert_eq!(cube.edge(12), None);
+        assert!(!cube.is_corner_solved(8));
+    }
+}
@@ -1,48 +1,28 @@
 use three_d::*;
 
-pub fn cube_uvs() -> Vec<Vec2> {vec![
-    Vec2::new(1.0, 1.0),
-    Vec2::new(0.0, 1.0),
-    Vec2::new(1.0, 0.0),
-    Vec2::new(0.0, 0.0),
-    Vec2::new(1.0, 0.0),
-    Vec2::new(0.0, 1.0),
-
-    Vec2::new(0.0, 1.0),
-    Vec2::new(1.0, 1.0),
-    Vec2::new(1.0, 0.0),
-    Vec2::new(1.0, 0.0),
-    Vec2::new(0.0, 0.0),
-    Vec2::new(0.0, 1.0),
-
-    Vec2::new(1.0, 0.0),
-    Vec2::new(0.0, 0.0),
-    Vec2::new(1.0, 1.0),
-    Vec2::new(0.0, 1.0),
-    Vec2::new(1.0, 1.0),
-    Vec2::new(0.0, 0.0),
-
-    Vec2::new(0.0, 0.0),
-    Vec2::new(1.0, 0.0),
-    Vec2::new(1.0, 1.0),
-    Vec2::new(1.0, 1.0),
-    Vec2::new(0.0, 1.0),
-    Vec2::new(0.0, 0.0),
-
-    Vec2::new(1.0, 0.0),
-    Vec2::new(1.0, 1.0),
-    Vec2::new(0.0, 1.0),
-    Vec2::new(0.0, 1.0),
-    Vec2::new(0.0, 0.0),
-    Vec2::new(1.0, 0.0),
+/// The mesh shared by every sticker instance: a unit cubie face at `z = 0.5`,
+/// facing outwards. The sticker itself is drawn by the fragment shader.
+pub fn sticker_mesh() -> CpuMesh {
+    let mut mesh = CpuMesh::square();
+    mesh.normals = None;
+    mesh.tangents = None;
+    mesh.transform(&(Mat4::from_translation(Vec3::new(0.0, 0.0, 0.5)) * Mat4::from_scale(0.5)))
+        .unwrap();
+    mesh
+}
 
-    Vec2::new(1.0, 1.0),
-    Vec2::new(1.0, 0.0),
-    Vec2::new(0.0, 1.0),
-    Vec2::new(0.0, 0.0),
-    Vec2::new(0.0, 1.0),
-    Vec2::new(1.0, 0.0),
-]}
+/// Rotations taking the `+z` sticker mesh to each cubie face, in the order
+/// `-x, +y, +z, -y, +x, -z` (the same order as the faces of a facelet string).
+pub fn face_transforms() -> [Mat4; 6] {
+    [
+        Mat4::from_angle_y(degrees(-90.0)),
+        Mat4::from_angle_x(degrees(-90.0)),
+        Mat4::identity(),
+        Mat4::from_angle_x(degrees(90.0)),
+        Mat4::from_angle_y(degrees(90.0)),
+        Mat4::from_angle_y(degrees(180.0)),
+    ]
+}
 
 #[derive(Clone)]
 pub struct RubikMaterial {
@@ -81,7 +61,7 @@ impl Material for RubikMaterial {
         }
     }
 
-    fn use_uniforms(&self, program: &Program, camera: &Camera, _: &[&dyn Light]) {
+    fn use_uniforms(&self, program: &Program, _camera: &Camera, _: &[&dyn Light]) {
         // camera.color_mapping.use_uniforms(program);
         program.use_uniform("stickerData", Vec2::new(1.0 - self.margin, self.radius));
         // program.use_uniform("cameraPosition", camera.position());
@@ -1,25 +1,43 @@
-use three_d::{Geometry, Gm, Mat3, Mat4, Mesh, Object, SquareMatrix, Vec3};
+use three_d::{Mat3, Mat4, SquareMatrix, Srgba, Vec3};
 
-use super::graphics::RubikMaterial;
-use super::Color;
-
-pub type PieceMaterial = RubikMaterial;
+use super::graphics::face_transforms;
+use super::{Color, Theme};
 
 /// A single rubik's cube piece. There are 27 in a cube.
+///
+/// Pieces don't own any GPU data: they are drawn as six sticker instances of
+/// the cube's shared sticker mesh.
 pub struct Piece {
     position: (i32, i32, i32),
     color: (Color, Color, Color),
     pub(crate) transform: Mat4,
-    gm: Gm<Mesh, PieceMaterial>
+    home: Mat4,
+    model: Mat4,
+    stickers: [Color; 6],
 }
 
 impl Piece {
-    pub fn new(position: (i32, i32, i32), color: (Color, Color, Color), mesh: Mesh) -> Piece {
+    pub fn new(position: (i32, i32, i32), color: (Color, Color, Color)) -> Piece {
+        let mut stickers = [Color::None; 6];
+        for (i, sticker) in stickers.iter_mut().enumerate() {
+            let face_color = match i {
+                0 => if position.0 == -1 { color.0 } else { Color::None },
+                1 => if position.1 ==  1 { color.1 } else { Color::None },
+                2 => if position.2 ==  1 { color.2 } else { Color::None },
+                3 => if position.1 == -1 { color.1 } else { Color::None },
+                4 => if position.0 ==  1 { color.0 } else { Color::None },
+                5 => if position.2 == -1 { color.2 } else { Color::None },
+                _ => Color::None,
+            };
+            *sticker = face_color;
+        }
         Piece {
             position,
             color,
             transform: Mat4::identity(),
-            gm: Gm::new(mesh, PieceMaterial::default())
+            home: Mat4::from_translation(Vec3::new(position.0 as f32, position.1 as f32, position.2 as f32)),
+            model: Mat4::identity(),
+            stickers,
         }
     }
 
@@ -28,8 +46,7 @@ impl Piece {
     }
 
     pub fn cubelet(&self) -> usize {
-        let out = ((self.position.0 + 1) * 9 + (1 - self.position.1) * 3 + (self.position.2 + 1)).try_into().unwrap();
-        out
+        ((self.position.0 + 1) * 9 + (1 - self.position.1) * 3 + (self.position.2 + 1)).try_into().unwrap()
     }
 
     pub fn rotate(&mut self, mat: Mat3) -> Result<(Vec3, Vec3), String> {
@@ -46,7 +63,7 @@ impl Piece {
         if (0..3).filter(|&i| rot[i] == 0.0).count() != 1 {
             return Err(format!("before: {:?}, after: {:?}, rot:{:?}", prev, new, rot));
         }
-        let diff = (0..3).into_iter().filter(|&i| rot[i] != 0.0).collect::<Vec<_>>();
+        let diff = (0..3).filter(|&i| rot[i] != 0.0).collect::<Vec<_>>();
         let mut color_arr = [self.color.0, self.color.1, self.color.2];
         color_arr.swap(diff[0], diff[1]);
         self.color = (color_arr[0], color_arr[1], color_arr[2]);
@@ -55,68 +72,19 @@ impl Piece {
     }
 
     pub fn transform(&mut self, mat: Mat4) {
-        self.gm.set_transformation(mat * self.transform);
-    }
-}
-
-impl Geometry for Piece {
-    fn aabb(&self) -> three_d::prelude::AxisAlignedBoundingBox {
-        self.gm.aabb()
-    }
-
-    fn animate(&mut self, _time: f32) {
-        self.gm.animate(_time);
-    }
-
-    fn draw(
-            &self,
-            camera: &three_d::Camera,
-            program: &three_d::Program,
-            render_states: three_d::RenderStates,
-            attributes: three_d::FragmentAttributes,
-        ) {
-        self.gm.draw(camera, program, render_states, attributes);
-    }
-
-    fn id(&self, required_attributes: three_d::FragmentAttributes) -> u16 {
-        self.gm.id(required_attributes)
+        self.model = mat * self.transform;
     }
 
-    fn render_with_effect(
-            &self,
-            material: &dyn three_d::Effect,
-            camera: &three_d::Camera,
-            lights: &[&dyn three_d::Light],
-            color_texture: Option<three_d::ColorTexture>,
-            depth_texture: Option<three_d::DepthTexture>,
-        ) {
-        self.gm.render_with_effect(material, camera, lights, color_texture, depth_texture)
+    /// The transformations of this piece's six sticker instances, in the
+    /// same order as [`Piece::stickers`].
+    pub fn sticker_transforms(&self) -> impl Iterator<Item = Mat4> + '_ {
+        face_transforms().into_iter().map(move |face| self.model * self.home * face)
     }
 
-    fn render_with_material(&self, material: &dyn three_d::Material, camera: &three_d::Camera, lights: &[&dyn three_d::Light]) {
-        self.gm.render_with_material(material, camera, lights);
-    }
-
-    fn vertex_shader_source(&self, required_attributes: three_d::FragmentAttributes) -> String {
-        self.gm.vertex_shader_source(required_attributes)
-    }
-}
-
-impl Object for Piece {
-    fn material_type(&self) -> three_d::MaterialType {
-        self.gm.material_type()
-    }
-
-    fn render(&self, camera: &three_d::Camera, lights: &[&dyn three_d::Light]) {
-        self.gm.render(camera, lights);
-    }
-}
-
-impl<'a> IntoIterator for &'a Piece {
-    type Item = &'a dyn Object;
-    type IntoIter = std::vec::IntoIter<&'a dyn Object>;
-
-    fn into_iter(self) -> Self::IntoIter {
-        vec![self as &dyn Object].into_iter()
+    /// The colors of this piece's six faces as they were when the piece was
+    /// created. Faces that aren't on the outside of the cube get the color
+    /// of the cube's body.
+    pub fn stickers(&self, theme: &Theme) -> [Srgba; 6] {
+        self.stickers.map(|color| theme.color(color))
     }
 }
@@ -0,0 +1,58 @@
+use std::collections::VecDeque;
+
+use super::Move;
+
+/// The timing of queued moves, apart from drawing them: which move is
+/// turning at a given time and how far. Each move starts the moment the one
+/// before it ends, so queued moves play without pauses.
+#[derive(Clone, Debug)]
+pub struct MoveQueue {
+    /// The move turning and when it started.
+    current: Option<(Move, f32)>,
+    queue: VecDeque<Move>,
+    move_time: f32,
+}
+
+impl MoveQueue {
+    /// An empty queue playing each move in `move_time` milliseconds.
+    pub fn new(move_time: f32) -> MoveQueue {
+        MoveQueue { current: None, queue: VecDeque::new(), move_time }
+    }
+
+    pub fn move_time(&self) -> f32 {
+        self.move_time
+    }
+
+    pub fn extend(&mut self, moves: impl IntoIterator<Item = Move>) {
+        self.queue.extend(moves);
+    }
+
+    /// Whether no move is turning or waiting.
+    pub fn is_idle(&self) -> bool {
+        self.current.is_none() && self.queue.is_empty()
+    }
+
+    /// The move turning, then the moves waiting.
+    pub fn moves(&self) -> impl Iterator<Item = Move> + '_ {
+        self.current.map(|(mv, _)| mv).into_iter().chain(self.queue.iter().copied())
+    }
+
+    /// Move on to a time, never earlier than the last one: the moves that
+    /// ended since, in order, and the move turning now with the fraction of
+    /// it done, from 0 to 1. When idle, the next queued move starts now.
+    pub fn advance(&mut self, time: f32) -> (Vec<Move>, Option<(Move, f32)>) {
+        let mut finished = vec![];
+        if self.current.is_none() {
+            self.current = self.queue.pop_front().map(|mv| (mv, time));
+        }
+        while let Some((mv, start)) = self.current {
+            let elapsed = time - start;
+            if elapsed <= self.move_time {
+                return (finished, Some((mv, elapsed / self.move_time)));
+            }
+            finished.push(mv);
+            self.current = self.queue.pop_front().map(|next| (next, start + self.move_time));
+        }
+        (finished, None)
+    }
+}
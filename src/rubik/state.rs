@@ -0,0 +1,195 @@
+use std::sync::OnceLock;
+
+use three_d::Vec4;
+
+use super::{Color, Move, FACELETS};
+
+/// Outward normal of each face, in facelet string order (L, U, F, D, R, B).
+pub(crate) const FACE_NORMALS: [(i32, i32, i32); 6] = [
+    (-1, 0, 0),
+    (0, 1, 0),
+    (0, 0, 1),
+    (0, -1, 0),
+    (1, 0, 0),
+    (0, 0, -1),
+];
+
+/// Position of a cubelet, with each coordinate in `-1..=1`.
+pub(crate) fn cubelet_position(cubelet: usize) -> (i32, i32, i32) {
+    let i = cubelet as i32;
+    (i / 9 - 1, 1 - (i / 3) % 3, i % 3 - 1)
+}
+
+/// Position of the cubelet a facelet belongs to, and the normal of its face.
+pub(crate) fn facelet_geometry(facelet: usize) -> ((i32, i32, i32), (i32, i32, i32)) {
+    (cubelet_position(FACELETS[facelet]), FACE_NORMALS[facelet / 9])
+}
+
+/// The facelet with the given position and normal.
+pub(crate) fn facelet_at(position: (i32, i32, i32), normal: (i32, i32, i32)) -> Option<usize> {
+    (0..54).find(|&f| facelet_geometry(f) == (position, normal))
+}
+
+fn rotate(mv: Move, v: (i32, i32, i32)) -> (i32, i32, i32) {
+    let r = mv.transform(1.0) * Vec4::new(v.0 as f32, v.1 as f32, v.2 as f32, 0.0);
+    (r.x.round() as i32, r.y.round() as i32, r.z.round() as i32)
+}
+
+/// For each move, the facelet every facelet is sent to.
+fn permutations() -> &'static [[usize; 54]] {
+    static PERMUTATIONS: OnceLock<Vec<[usize; 54]>> = OnceLock::new();
+    PERMUTATIONS.get_or_init(|| {
+        Move::ALL.iter().map(|&mv| {
+            let mut perm = [0; 54];
+            for (f, target) in perm.iter_mut().enumerate() {
+                let (p, n) = facelet_geometry(f);
+                *target = if mv.affects(p) {
+                    facelet_at(rotate(mv, p), rotate(mv, n)).unwrap()
+                } else {
+                    f
+                };
+            }
+            perm
+        }).collect()
+    })
+}
+
+/// The colors of all 54 stickers of a cube, independent of any rendering context.
+///
+/// Facelets are ordered face by face (L, U, F, D, R, B), and each face is read
+/// row by row as seen from outside the cube, the same as the facelet strings
+/// taken by [`CubeState::from_facelet_str`].
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+pub struct CubeState {
+    facelets: [Color; 54],
+}
+
+/// Index of the U face in a facelet string.
+pub(crate) const U: usize = 1;
+
+/// Names of the faces, in facelet string order.
+const FACE_NAMES: [char; 6] = ['L', 'U', 'F', 'D', 'R', 'B'];
+
+/// The faces in the order of Kociemba's facelet strings: U, R, F, D, L, B.
+const KOCIEMBA_FACES: [usize; 6] = [1, 4, 2, 3, 0, 5];
+
+impl CubeState {
+    pub fn solved() -> CubeState {
+        let mut facelets = [Color::None; 54];
+        for (i, facelet) in facelets.iter_mut().enumerate() {
+            *facelet = Color::FACES[i / 9];
+        }
+        CubeState { facelets }
+    }
+
+    /// Parse a string of 54 color letters (`B`, `Y`, `R`, `W`, `G`, `O`).
+    pub fn from_facelet_str(fstr: &str) -> Result<CubeState, String> {
+        let mut facelets = [Color::None; 54];
+        let mut chars = fstr.chars();
+        for facelet in facelets.iter_mut() {
+            match chars.next() {
+                Some(c) => *facelet = Color::from_char(c)?,
+                None => return Err("Invalid string length".to_string()),
+            }
+        }
+        if chars.next().is_some() {
+            return Err("Invalid string length".to_string());
+        }
+        Ok(CubeState { facelets })
+    }
+
+    pub fn from_facelets(facelets: [Color; 54]) -> CubeState {
+        CubeState { facelets }
+    }
+
+    /// Parse a facelet string in the format used by Kociemba's solver: faces
+    /// in U, R, F, D, L, B order, each sticker named by the face whose center
+    /// has its color. Faces get the standard color scheme.
+    pub fn from_kociemba_str(fstr: &str) -> Result<CubeState, String> {
+        let chars: Vec<char> = fstr.chars().collect();
+        if chars.len() != 54 {
+            return Err("Invalid string length".to_string());
+        }
+        let mut facelets = [Color::None; 54];
+        for (i, face) in KOCIEMBA_FACES.iter().enumerate() {
+            for j in 0..9 {
+                let c = chars[i * 9 + j];
+                let named = FACE_NAMES.iter().position(|&n| n == c)
+                    .ok_or(format!("Invalid face name: {}", c))?;
+                facelets[face * 9 + j] = Color::FACES[named];
+            }
+        }
+        Ok(CubeState { facelets })
+    }
+
+    /// The facelet string in the format used by Kociemba's solver; see
+    /// [`CubeState::from_kociemba_str`].
+    pub fn to_kociemba_string(&self) -> Result<String, String> {
+        let centers: Vec<Color> = (0..6).map(|face| self.facelets[face * 9 + 4]).collect();
+        let mut out = String::with_capacity(54);
+        for face in KOCIEMBA_FACES {
+            for &color in &self.facelets[face * 9..face * 9 + 9] {
+                let named = centers.iter().position(|&c| c == color)
+                    .ok_or(format!("No center with color {:?}", color))?;
+                out.push(FACE_NAMES[named]);
+            }
+        }
+        Ok(out)
+    }
+
+    pub fn to_facelet_string(&self) -> String {
+        self.facelets.iter().map(|c| c.to_char()).collect()
+    }
+
+    pub fn facelets(&self) -> &[Color; 54] {
+        &self.facelets
+    }
+
+    /// The color of a single sticker, by facelet index.
+    pub fn facelet(&self, index: usize) -> Color {
+        self.facelets[index]
+    }
+
+    /// The nine stickers of a face (in L, U, F, D, R, B order).
+    pub fn face(&self, face: usize) -> [Color; 9] {
+        self.facelets[face * 9..face * 9 + 9].try_into().unwrap()
+    }
+
+    pub fn apply(&mut self, mv: Move) {
+        let perm = &permutations()[mv as usize];
+        let mut facelets = [Color::None; 54];
+        for (f, &target) in perm.iter().enumerate() {
+            facelets[target] = self.facelets[f];
+        }
+        self.facelets = facelets;
+    }
+
+    pub fn apply_all(&mut self, moves: impl IntoIterator<Item = Move>) {
+        for mv in moves {
+            self.apply(mv);
+        }
+    }
+
+    /// The facelet each facelet ends up at after a sequence of moves.
+    pub fn facelet_permutation(moves: impl IntoIterator<Item = Move>) -> [usize; 54] {
+        let mut perm: [usize; 54] = std::array::from_fn(|f| f);
+        for mv in moves {
+            let mv_perm = &permutations()[mv as usize];
+            for target in perm.iter_mut() {
+                *target = mv_perm[*target];
+            }
+        }
+        perm
+    }
+
+    /// Whether every face shows a single color.
+    pub fn is_solved(&self) -> bool {
+        self.facelets.chunks(9).all(|face| face.iter().all(|&c| c == face[4]))
+    }
+}
+
+impl Default for CubeState {
+    fn default() -> Self {
+        CubeState::solved()
+    }
+}
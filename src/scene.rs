@@ -0,0 +1,195 @@
+use three_d::{Camera, ClearState, Context, DirectionalLight, RenderTarget, ScissorBox, Srgba, Vec3, Viewport, Zero};
+
+use crate::clock::Clock;
+use crate::rotate_camera_around_target;
+use crate::rubik::Cube;
+
+/// A rectangle on the canvas in CSS pixels, measured from its top-left corner.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Region {
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+}
+
+impl Region {
+    pub fn new(x: u32, y: u32, width: u32, height: u32) -> Region {
+        Region { x, y, width, height }
+    }
+
+    /// Convert to a viewport on a canvas with the given height in physical
+    /// pixels and number of physical pixels per CSS pixel.
+    /// Viewports are measured from the bottom-left corner.
+    pub fn viewport(&self, canvas_height: u32, scale: f32) -> Viewport {
+        let px = |v: u32| (v as f32 * scale).round() as i32;
+        Viewport {
+            x: px(self.x),
+            y: canvas_height as i32 - px(self.y + self.height),
+            width: px(self.width).max(1) as u32,
+            height: px(self.height).max(1) as u32,
+        }
+    }
+}
+
+/// The camera every view starts with, looking at the cube from the front-right.
+pub fn default_camera(viewport: Viewport) -> Camera {
+    Camera::new_perspective(
+        viewport,
+        Vec3::new(5.0, 0.0, 5.0),
+        Vec3::new(0.0, 0.0, 0.0),
+        Vec3::new(0.0, 1.0, 0.0),
+        three_d::degrees(45.0),
+        0.1,
+        50.0,
+    )
+}
+
+/// A cube drawn into its own region of a shared canvas, with its own camera.
+pub struct CubeView {
+    pub cube: Cube,
+    pub camera: Camera,
+    /// Slowly orbit the camera around the cube.
+    pub auto_rotate: bool,
+    /// Where the view is drawn, or `None` to cover the whole canvas.
+    region: Option<Region>,
+}
+
+impl CubeView {
+    pub fn new(cube: Cube, region: Region) -> CubeView {
+        CubeView {
+            cube,
+            camera: default_camera(Viewport::new_at_origo(region.width, region.height)),
+            auto_rotate: false,
+            region: Some(region),
+        }
+    }
+
+    /// A view covering the whole canvas, whatever its size.
+    pub fn full(cube: Cube) -> CubeView {
+        CubeView {
+            cube,
+            camera: default_camera(Viewport::new_at_origo(1, 1)),
+            auto_rotate: false,
+            region: None,
+        }
+    }
+
+    pub fn region(&self) -> Option<Region> {
+        self.region
+    }
+
+    pub fn set_region(&mut self, region: Option<Region>) {
+        self.region = region;
+    }
+
+    fn viewport(&self, width: u32, height: u32, scale: f32) -> Viewport {
+        match self.region {
+            Some(region) => region.viewport(height, scale),
+            None => Viewport::new_at_origo(width, height),
+        }
+    }
+
+    fn update(&mut self, time: f32, frame_time: f32) {
+        self.cube.animate(time);
+        if self.auto_rotate {
+            let theta_speed = (time/10000.0).sin()/5000.0;
+            let phi_speed = (time/10000.0).cos()/8000.0;
+            rotate_camera_around_target(&mut self.camera, Vec3::zero(), frame_time*theta_speed, frame_time*phi_speed);
+        }
+    }
+}
+
+/// Any number of cube views sharing one rendering context.
+/// Each view keeps its own camera and animation queue.
+pub struct Scene {
+    views: Vec<Option<CubeView>>,
+    light: DirectionalLight,
+    pub clear_color: Srgba,
+    /// Time of the last [`Scene::tick`].
+    last_time: Option<f64>,
+}
+
+impl Scene {
+    pub fn new(context: &Context) -> Scene {
+        Scene {
+            views: vec![],
+            light: DirectionalLight::new(context, 100.0, Srgba::WHITE, &Vec3::new(1.0, 3.0, 2.5)),
+            clear_color: Srgba::new(0, 0, 0, 0),
+            last_time: None,
+        }
+    }
+
+    /// Add a view to the scene, returning its id.
+    /// Ids stay valid until the view is removed.
+    pub fn add(&mut self, view: CubeView) -> usize {
+        if let Some(id) = self.views.iter().position(|v| v.is_none()) {
+            self.views[id] = Some(view);
+            id
+        } else {
+            self.views.push(Some(view));
+            self.views.len() - 1
+        }
+    }
+
+    pub fn remove(&mut self, id: usize) -> Option<CubeView> {
+        self.views.get_mut(id).and_then(|v| v.take())
+    }
+
+    pub fn view(&self, id: usize) -> Option<&CubeView> {
+        self.views.get(id).and_then(|v| v.as_ref())
+    }
+
+    pub fn view_mut(&mut self, id: usize) -> Option<&mut CubeView> {
+        self.views.get_mut(id).and_then(|v| v.as_mut())
+    }
+
+    pub fn views(&self) -> impl Iterator<Item = &CubeView> {
+        self.views.iter().flatten()
+    }
+
+    /// Recreate the GPU resources of every view with a new context, after
+    /// the previous one was lost. Cameras, regions and move queues are kept.
+    pub fn recreate(&mut self, context: &Context) {
+        self.light = DirectionalLight::new(context, 100.0, Srgba::WHITE, &Vec3::new(1.0, 3.0, 2.5));
+        for view in self.views.iter_mut().flatten() {
+            view.cube = view.cube.recreate(context);
+        }
+    }
+
+    /// Advance the animations of every view. Times are in milliseconds.
+    pub fn update(&mut self, time: f64, frame_time: f64) {
+        for view in self.views.iter_mut().flatten() {
+            view.update(time as f32, frame_time as f32);
+        }
+    }
+
+    /// Advance the animations of every view to the next time of a clock,
+    /// which is returned.
+    pub fn tick(&mut self, clock: &mut impl Clock) -> f64 {
+        let time = clock.tick();
+        let frame_time = self.last_time.map_or(0.0, |last| time - last);
+        self.last_time = Some(time);
+        self.update(time, frame_time);
+        time
+    }
+
+    /// Draw every view into its region of a target with the given size in
+    /// physical pixels. `scale` is the number of physical pixels per CSS pixel.
+    /// Cameras are fitted to their viewports, so views keep their aspect
+    /// ratio when the target is resized.
+    pub fn render(&mut self, target: &RenderTarget, width: u32, height: u32, scale: f32) {
+        let [r, g, b, a]: [f32; 4] = self.clear_color.into();
+        target.clear_partially(
+            ScissorBox::new_at_origo(width, height),
+            ClearState::color_and_depth(r, g, b, a, 1.0),
+        );
+        for view in self.views.iter_mut().flatten() {
+            let viewport = view.viewport(width, height, scale);
+            view.camera.set_viewport(viewport);
+            target
+                .clear_partially(viewport.into(), ClearState::depth(1.0))
+                .render_partially(viewport.into(), &view.camera, &view.cube, &[&self.light]);
+        }
+    }
+}
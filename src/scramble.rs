@@ -0,0 +1,79 @@
+//! Random-state scrambles, as used in competitions: pick a uniformly random
+//! solvable state and turn the inverse of a solution to it into a scramble.
+
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::rubik::{permutation_parity, CubieCube, Move};
+use crate::solver::{two_phase, MAX_LENGTH};
+
+/// A small xorshift64* generator, so scrambles can be reproduced from a seed.
+#[derive(Clone, Debug)]
+pub struct Rng {
+    state: u64,
+}
+
+impl Rng {
+    pub fn new(seed: u64) -> Rng {
+        // The state must never be zero.
+        Rng { state: seed ^ 0x9E37_79B9_7F4A_7C15 | 1 }
+    }
+
+    /// A generator seeded from the system clock.
+    pub fn from_time() -> Rng {
+        let nanos = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_nanos()).unwrap_or(0);
+        Rng::new(nanos as u64)
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state ^= self.state >> 12;
+        self.state ^= self.state << 25;
+        self.state ^= self.state >> 27;
+        self.state.wrapping_mul(0x2545_F491_4F6C_DD1D)
+    }
+
+    /// A number in `0..n`.
+    pub fn below(&mut self, n: usize) -> usize {
+        (self.next_u64() % n as u64) as usize
+    }
+
+    /// Shuffle a slice in place.
+    pub fn shuffle<T>(&mut self, items: &mut [T]) {
+        for i in (1..items.len()).rev() {
+            items.swap(i, self.below(i + 1));
+        }
+    }
+}
+
+/// A uniformly random solvable cube.
+pub fn random_cube(rng: &mut Rng) -> CubieCube {
+    let mut cube = CubieCube::SOLVED;
+    rng.shuffle(&mut cube.cp);
+    rng.shuffle(&mut cube.ep);
+    // Swapping two edges fixes mismatched permutation parity.
+    if permutation_parity(&cube.cp) != permutation_parity(&cube.ep) {
+        cube.ep.swap(0, 1);
+    }
+    for o in cube.co[..7].iter_mut() {
+        *o = rng.below(3) as u8;
+    }
+    cube.co[7] = (3 - cube.co[..7].iter().sum::<u8>() % 3) % 3;
+    for o in cube.eo[..11].iter_mut() {
+        *o = rng.below(2) as u8;
+    }
+    cube.eo[11] = cube.eo[..11].iter().sum::<u8>() % 2;
+    cube
+}
+
+/// A scramble leading to a uniformly random state.
+pub fn random_state_scramble(rng: &mut Rng) -> Vec<Move> {
+    loop {
+        let cube = random_cube(rng);
+        // A scramble that does nothing isn't much use.
+        if cube.is_solved() {
+            continue;
+        }
+        if let Some(solution) = two_phase::solve(&cube, MAX_LENGTH) {
+            return Move::invert_sequence(&solution);
+        }
+    }
+}
@@ -0,0 +1,234 @@
+//! Serde support, behind the `serde` feature.
+//!
+//! The JSON representation is meant to be stored and replayed, so it is kept
+//! stable and compact:
+//!
+//! - A [`Color`] is its facelet letter: `"B"`, `"Y"`, `"R"`, `"W"`, `"G"`,
+//!   `"O"`, or `"-"` for [`Color::None`].
+//! - A [`Move`] is its notation: `"R"`, `"R'"`, `"R2"`, `"Rw"`, `"M'"`, `"y2"`.
+//!   Lowercase wide moves such as `"r"` are accepted too.
+//!   A sequence is an array of moves.
+//! - A [`CubeState`] is its 54 letter facelet string, as taken by
+//!   [`CubeState::from_facelet_str`].
+//! - Colors of options (`background`) are `"#rrggbb"` or `"#rrggbbaa"` strings.
+//! - Options are objects with camelCase field names. Missing fields take
+//!   their default value.
+//!
+//! [`JSON_SCHEMA`] describes all of these.
+
+use std::fmt;
+
+use serde::de::{self, Deserializer, Visitor};
+use serde::{Deserialize, Serialize, Serializer};
+
+use crate::rubik::{Color, CubeState, Move};
+
+/// A JSON Schema (draft 2020-12) of the representations above, with one
+/// definition per type.
+pub const JSON_SCHEMA: &str = r##"{
+  "$schema": "https://json-schema.org/draft/2020-12/schema",
+  "$id": "rustbik",
+  "$defs": {
+    "color": { "type": "string", "enum": ["B", "Y", "R", "W", "G", "O", "-"] },
+    "move": { "type": "string", "pattern": "^([LRUDFBMESxyz]|[LRUDFB]w|[lrudfb])(2'?|')?$" },
+    "moves": { "type": "array", "items": { "$ref": "#/$defs/move" } },
+    "state": { "type": "string", "pattern": "^[BYRWGO]{54}$" },
+    "rgba": { "type": "string", "pattern": "^#([0-9a-fA-F]{6}|[0-9a-fA-F]{8})$" },
+    "animationOptions": {
+      "type": "object",
+      "properties": {
+        "moveTime": { "type": "number", "exclusiveMinimum": 0, "default": 1200 },
+        "moveSmoothing": { "type": "number", "minimum": 0, "default": 2 }
+      },
+      "additionalProperties": false
+    },
+    "diagramOptions": {
+      "type": "object",
+      "properties": {
+        "stickerSize": { "type": "number", "exclusiveMinimum": 0, "default": 20 },
+        "gap": { "type": "number", "minimum": 0, "default": 2 },
+        "background": { "$ref": "#/$defs/rgba", "default": "#000000ff" },
+        "highlight": { "oneOf": [{ "$ref": "#/$defs/color" }, { "type": "null" }], "default": null }
+      },
+      "additionalProperties": false
+    },
+    "exportOptions": {
+      "type": "object",
+      "properties": {
+        "width": { "type": "integer", "minimum": 1, "default": 256 },
+        "height": { "type": "integer", "minimum": 1, "default": 256 },
+        "frameRate": { "type": "number", "exclusiveMinimum": 0, "default": 30 },
+        "background": { "$ref": "#/$defs/rgba", "default": "#ffffffff" }
+      },
+      "additionalProperties": false
+    }
+  }
+}"##;
+
+/// Serialize anything with a string form, and deserialize it with a parser.
+struct StrVisitor<T>(&'static str, fn(&str) -> Result<T, String>);
+
+impl<T> Visitor<'_> for StrVisitor<T> {
+    type Value = T;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.0)
+    }
+
+    fn visit_str<E: de::Error>(self, v: &str) -> Result<T, E> {
+        (self.1)(v).map_err(E::custom)
+    }
+}
+
+impl Serialize for Color {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.to_char().encode_utf8(&mut [0; 4]))
+    }
+}
+
+impl<'de> Deserialize<'de> for Color {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Color, D::Error> {
+        deserializer.deserialize_str(StrVisitor("a color letter", |s| {
+            let mut chars = s.chars();
+            match (chars.next(), chars.next()) {
+                (Some('-'), None) => Ok(Color::None),
+                (Some(c), None) => Color::from_char(c),
+                _ => Err(format!("Invalid color {}", s)),
+            }
+        }))
+    }
+}
+
+impl Serialize for Move {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for Move {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Move, D::Error> {
+        deserializer.deserialize_str(StrVisitor("a move", |s| s.parse()))
+    }
+}
+
+impl Serialize for CubeState {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.to_facelet_string())
+    }
+}
+
+impl<'de> Deserialize<'de> for CubeState {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<CubeState, D::Error> {
+        deserializer.deserialize_str(StrVisitor("a facelet string", CubeState::from_facelet_str))
+    }
+}
+
+/// `#[serde(with = "...")]` helpers for [`three_d::Srgba`], written as
+/// `"#rrggbbaa"`.
+pub(crate) mod rgba {
+    use super::*;
+    use three_d::Srgba;
+
+    pub fn serialize<S: Serializer>(color: &Srgba, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&format!("#{:02x}{:02x}{:02x}{:02x}", color.r, color.g, color.b, color.a))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Srgba, D::Error> {
+        deserializer.deserialize_str(StrVisitor("a #rrggbb or #rrggbbaa color", crate::parse_rgba))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::{json, Value};
+
+    use super::*;
+    use crate::diagram::DiagramOptions;
+    use crate::rubik::CubeAnimationOptions;
+
+    fn round_trip<T: Serialize + for<'de> Deserialize<'de>>(value: &T, expected: Value) {
+        assert_eq!(serde_json::to_value(value).unwrap(), expected);
+        let back: T = serde_json::from_value(expected.clone()).unwrap();
+        assert_eq!(serde_json::to_value(&back).unwrap(), expected);
+    }
+
+    #[test]
+    fn colors() {
+        assert_eq!(serde_json::to_string(&Color::Yellow).unwrap(), r#""Y""#);
+        round_trip(&[Color::Blue, Color::White, Color::None], json!(["B", "W", "-"]));
+        assert!(serde_json::from_str::<Color>(r#""YY""#).is_err());
+        assert!(serde_json::from_str::<Color>(r#""X""#).is_err());
+        assert!(serde_json::from_str::<Color>("1").is_err());
+    }
+
+    #[test]
+    fn moves() {
+        let moves: Vec<Move> = Move::from_sequence("R U2 Rw M' y2").unwrap().collect();
+        round_trip(&moves, json!(["R", "U2", "Rw", "M'", "y2"]));
+        assert_eq!(serde_json::from_str::<Move>(r#""r""#).unwrap(), Move::Rw);
+        assert!(serde_json::from_str::<Move>(r#""Q""#).is_err());
+    }
+
+    #[test]
+    fn states() {
+        let mut state = CubeState::solved();
+        state.apply_all(Move::from_sequence("R U").unwrap());
+        round_trip(&state, json!("RRWBBBBBBYYYYYYRRRGGGRRWRRWWWOWWOWWOYOOGGGGGGBBBYOOYOO"));
+        assert!(serde_json::from_str::<CubeState>(r#""BYR""#).is_err());
+    }
+
+    #[test]
+    fn options() {
+        round_trip(&CubeAnimationOptions::default(), json!({ "moveTime": 1200.0, "moveSmoothing": 2.0 }));
+        round_trip(&DiagramOptions::default(), json!({
+            "stickerSize": 20.0,
+            "gap": 2.0,
+            "background": "#000000ff",
+            "highlight": null,
+        }));
+        let options: DiagramOptions = serde_json::from_str(r##"{ "background": "#102030", "highlight": "R" }"##).unwrap();
+        assert_eq!(serde_json::to_value(&options).unwrap(), json!({
+            "stickerSize": 20.0,
+            "gap": 2.0,
+            "background": "#102030ff",
+            "highlight": "R",
+        }));
+        assert!(serde_json::from_str::<DiagramOptions>(r#"{ "size": 1 }"#).is_err());
+        assert!(serde_json::from_str::<DiagramOptions>(r##"{ "background": "#1020" }"##).is_err());
+    }
+
+    #[cfg(feature = "export")]
+    #[test]
+    fn export_options() {
+        round_trip(&crate::export::ExportOptions::default(), json!({
+            "width": 256,
+            "height": 256,
+            "frameRate": 30.0,
+            "background": "#ffffffff",
+        }));
+    }
+
+    #[test]
+    fn schema() {
+        let schema: Value = serde_json::from_str(JSON_SCHEMA).unwrap();
+        let defs = &schema["$defs"];
+        let colors: Vec<Value> = [Color::Blue, Color::Yellow, Color::Red, Color::White, Color::Green, Color::Orange, Color::None].iter().map(|c| serde_json::to_value(c).unwrap()).collect();
+        assert_eq!(defs["color"]["enum"].as_array().unwrap(), &colors);
+        // The defaults the schema gives are those of the types.
+        let defaults = [
+            ("animationOptions", serde_json::to_value(CubeAnimationOptions::default()).unwrap()),
+            ("diagramOptions", serde_json::to_value(DiagramOptions::default()).unwrap()),
+            #[cfg(feature = "export")]
+            ("exportOptions", serde_json::to_value(crate::export::ExportOptions::default()).unwrap()),
+        ];
+        for (name, default) in defaults {
+            let properties = defs[name]["properties"].as_object().unwrap();
+            let expected = default.as_object().unwrap();
+            assert_eq!(properties.len(), expected.len(), "{}", name);
+            for (field, value) in expected {
+                assert_eq!(properties[field]["default"].as_f64(), value.as_f64(), "{}.{}", name, field);
+                assert_eq!(properties[field]["default"].as_str(), value.as_str(), "{}.{}", name, field);
+            }
+        }
+    }
+}
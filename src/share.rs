@@ -0,0 +1,220 @@
+//! Compact, URL-safe text for states and move sequences, for links such as
+//! `?setup=BMDGEHN&alg=BMGDHEN`.
+//!
+//! Both encodings are base64url digits (`A`–`Z`, `a`–`z`, `0`–`9`, `-`,
+//! `_`), the first of which is the version of the encoding. A move sequence
+//! takes one more digit per move. A state takes 12 more: the permutation and
+//! orientation of the pieces, and which way the cube is rotated.
+
+use crate::rubik::{Color, CubeState, CubieCube, Move};
+use crate::solver::coord::*;
+
+/// The version digit of the encodings made by this crate.
+pub const VERSION: u8 = 1;
+
+const DIGITS: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789-_";
+
+/// Digits of an encoded state, after the version.
+const STATE_DIGITS: usize = 12;
+const N_EDGE_PERM: u128 = 479_001_600;
+
+fn digit(value: u8) -> char {
+    DIGITS[value as usize] as char
+}
+
+fn digit_value(c: char) -> Result<u8, String> {
+    DIGITS.iter().position(|&d| d as char == c).map(|v| v as u8).ok_or(format!("Invalid character {}", c))
+}
+
+/// Split off and check the version digit.
+fn strip_version(text: &str) -> Result<&str, String> {
+    let first = text.chars().next().ok_or("Empty encoding")?;
+    match digit_value(first)? {
+        VERSION => Ok(&text[1..]),
+        version => Err(format!("Unsupported encoding version {}", version)),
+    }
+}
+
+fn has_standard_centers(state: &CubeState) -> bool {
+    (0..6).all(|face| state.facelet(face * 9 + 4) == Color::FACES[face])
+}
+
+/// Encode a state reachable from solved by moves, in the standard color
+/// scheme, held any way.
+pub fn encode_state(state: &CubeState) -> Result<String, String> {
+    let (orientation, upright) = Move::orientations().iter().enumerate()
+        .find_map(|(i, rotation)| {
+            let mut upright = state.clone();
+            upright.apply_all(rotation.iter().copied());
+            has_standard_centers(&upright).then_some((i, upright))
+        })
+        .ok_or("The centers don't have the standard color scheme")?;
+    let cube = CubieCube::from_state(&upright)?;
+    let mut value = corner_perm(&cube) as u128;
+    value = value * N_TWIST as u128 + twist(&cube) as u128;
+    value = value * N_EDGE_PERM + perm_rank(&cube.ep) as u128;
+    value = value * N_FLIP as u128 + flip(&cube) as u128;
+    value = value * 24 + orientation as u128;
+    let mut digits = vec![0; STATE_DIGITS];
+    for d in digits.iter_mut().rev() {
+        *d = (value % 64) as u8;
+        value /= 64;
+    }
+    Ok(std::iter::once(VERSION).chain(digits).map(digit).collect())
+}
+
+/// Decode a state encoded with [`encode_state`].
+pub fn decode_state(text: &str) -> Result<CubeState, String> {
+    let text = strip_version(text)?;
+    if text.len() != STATE_DIGITS {
+        return Err(format!("An encoded state has {} characters", STATE_DIGITS + 1));
+    }
+    let mut value: u128 = 0;
+    for c in text.chars() {
+        value = value * 64 + digit_value(c)? as u128;
+    }
+    let mut take = |n: u128| {
+        let part = (value % n) as usize;
+        value /= n;
+        part
+    };
+    let orientation = take(24);
+    let mut cube = CubieCube::SOLVED;
+    set_flip(&mut cube, take(N_FLIP as u128));
+    set_perm_rank(&mut cube.ep, take(N_EDGE_PERM));
+    set_twist(&mut cube, take(N_TWIST as u128));
+    let corners = take(N_PERM_8 as u128);
+    if value != 0 || corners >= N_PERM_8 {
+        return Err("Invalid state encoding".to_string());
+    }
+    set_corner_perm(&mut cube, corners);
+    cube.verify()?;
+    let mut state = cube.to_state();
+    state.apply_all(Move::invert_sequence(&Move::orientations()[orientation]));
+    Ok(state)
+}
+
+/// Encode a move sequence. Each move is its index in [`Move::ALL`].
+pub fn encode_moves(moves: &[Move]) -> String {
+    std::iter::once(VERSION).chain(moves.iter().map(|&mv| mv as u8)).map(digit).collect()
+}
+
+/// Decode a move sequence encoded with [`encode_moves`].
+pub fn decode_moves(text: &str) -> Result<Vec<Move>, String> {
+    strip_version(text)?.chars()
+        .map(|c| Move::ALL.get(digit_value(c)? as usize).copied().ok_or(format!("Invalid move {}", c)))
+        .collect()
+}
+
+/// What a share link shows: a starting state, setup moves taking it to the
+/// position shown, and an algorithm to play from there.
+#[derive(Clone, PartialEq, Debug, Default)]
+pub struct Shared {
+    /// The state before the setup; solved if `None`.
+    pub state: Option<CubeState>,
+    pub setup: Vec<Move>,
+    pub alg: Vec<Move>,
+}
+
+impl Shared {
+    /// Read the `state`, `setup` and `alg` parameters of a query string
+    /// such as `?setup=BMDGEHN&alg=BMGDHEN`, or of a whole URL. Other
+    /// parameters are ignored.
+    pub fn from_query(query: &str) -> Result<Shared, String> {
+        let query = query.split_once('?').map_or(query, |(_, query)| query);
+        let query = query.split_once('#').map_or(query, |(query, _)| query);
+        let mut shared = Shared::default();
+        for pair in query.split('&') {
+            let (key, value) = pair.split_once('=').unwrap_or((pair, ""));
+            match key {
+                "state" => shared.state = Some(decode_state(value)?),
+                "setup" => shared.setup = decode_moves(value)?,
+                "alg" => shared.alg = decode_moves(value)?,
+                _ => {}
+            }
+        }
+        Ok(shared)
+    }
+
+    /// The query string of a link to this, without the `?`.
+    pub fn to_query(&self) -> Result<String, String> {
+        let mut params = vec![];
+        if let Some(state) = &self.state {
+            params.push(format!("state={}", encode_state(state)?));
+        }
+        if !self.setup.is_empty() {
+            params.push(format!("setup={}", encode_moves(&self.setup)));
+        }
+        if !self.alg.is_empty() {
+            params.push(format!("alg={}", encode_moves(&self.alg)));
+        }
+        Ok(params.join("&"))
+    }
+
+    /// The position shown: the state after the setup.
+    pub fn position(&self) -> CubeState {
+        let mut state = self.state.clone().unwrap_or_default();
+        state.apply_all(self.setup.iter().copied());
+        state
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scramble::{random_cube, Rng};
+
+    #[test]
+    fn states_round_trip() {
+        let mut rng = Rng::new(8);
+        let extra: Vec<Move> = Move::ALL.iter().copied().filter(|mv| !mv.is_face_turn()).collect();
+        for i in 0..200 {
+            let mut state = random_cube(&mut rng).to_state();
+            // Rotations, slice and wide turns move the centers too.
+            state.apply(extra[i % extra.len()]);
+            let text = encode_state(&state).unwrap();
+            assert_eq!(text.len(), STATE_DIGITS + 1);
+            assert_eq!(decode_state(&text), Ok(state));
+        }
+    }
+
+    #[test]
+    fn moves_round_trip() {
+        let moves = Move::ALL.to_vec();
+        let text = encode_moves(&moves);
+        assert_eq!(text.len(), moves.len() + 1);
+        assert_eq!(decode_moves(&text), Ok(moves));
+        assert_eq!(decode_moves(&encode_moves(&[])), Ok(vec![]));
+    }
+
+    /// Links already shared must keep opening the same cube.
+    #[test]
+    fn fixed_encodings() {
+        let moves: Vec<Move> = Move::from_sequence("F R U R' U' F'").unwrap().collect();
+        assert_eq!(encode_moves(&moves), "BMDGEHN");
+        assert_eq!(decode_moves("BMDGEHN"), Ok(moves));
+        let mut state = CubeState::solved();
+        state.apply_all(Move::from_sequence("R U").unwrap());
+        assert_eq!(encode_state(&state).unwrap(), "BBhbixXFussAA");
+        assert_eq!(decode_state("BBhbixXFussAA"), Ok(state));
+        assert_eq!(encode_state(&CubeState::solved()).unwrap(), "BAAAAAAAAAAAA");
+    }
+
+    #[test]
+    fn invalid_encodings() {
+        let state = encode_state(&CubeState::solved()).unwrap();
+        assert!(state.starts_with('B'));
+        assert!(decode_state("").is_err());
+        assert!(decode_moves("").is_err());
+        assert!(decode_state(&state[..STATE_DIGITS]).is_err());
+        assert!(decode_state(&format!("{}A", state)).is_err());
+        // Other version digits.
+        assert!(decode_state(&format!("C{}", &state[1..])).is_err());
+        assert!(decode_moves("A").is_err());
+        assert!(decode_moves("CA").is_err());
+        assert!(decode_state(&format!("B{}", "_".repeat(STATE_DIGITS))).is_err());
+        assert!(decode_state(&format!("B{}*", &state[2..])).is_err());
+        assert!(decode_moves("B_").is_err());
+        assert!(decode_moves("BA!").is_err());
+    }
+}
@@ -0,0 +1,20 @@
+//! Solvers finding move sequences that bring a cube back to solved.
+
+pub mod coord;
+pub mod generate;
+pub mod method;
+pub mod optimal;
+pub mod reduction;
+pub mod two_phase;
+
+use crate::rubik::{CubeState, CubieCube, Move};
+
+/// Longest solution [`solve`] looks for. Almost every state has a two-phase
+/// solution this short that is found quickly.
+pub const MAX_LENGTH: usize = 22;
+
+/// Solve a sticker state with the two-phase algorithm.
+pub fn solve(state: &CubeState) -> Result<Vec<Move>, String> {
+    let cube = CubieCube::from_state(state)?;
+    two_phase::solve(&cube, MAX_LENGTH).ok_or(format!("No solution within {} moves", MAX_LENGTH))
+}
@@ -0,0 +1,190 @@
+//! Coordinates: small integers numbering some aspect of a [`CubieCube`], so
+//! that move tables and pruning tables can be indexed by them.
+
+use crate::rubik::CubieCube;
+
+pub const N_TWIST: usize = 2187;
+pub const N_FLIP: usize = 2048;
+pub const N_SLICE: usize = 495;
+pub const N_PERM_8: usize = 40320;
+pub const N_PERM_4: usize = 24;
+
+/// Binomial coefficient.
+pub fn choose(n: usize, k: usize) -> usize {
+    if k > n {
+        return 0;
+    }
+    let mut out = 1;
+    for i in 0..k {
+        out = out * (n - i) / (i + 1);
+    }
+    out
+}
+
+/// Twist of the first seven corners; the eighth follows from them.
+pub fn twist(cube: &CubieCube) -> usize {
+    cube.co[..7].iter().fold(0, |t, &o| 3 * t + o as usize)
+}
+
+pub fn set_twist(cube: &mut CubieCube, mut twist: usize) {
+    let mut sum = 0;
+    for i in (0..7).rev() {
+        cube.co[i] = (twist % 3) as u8;
+        sum += cube.co[i];
+        twist /= 3;
+    }
+    cube.co[7] = (3 - sum % 3) % 3;
+}
+
+/// Flip of the first eleven edges; the twelfth follows from them.
+pub fn flip(cube: &CubieCube) -> usize {
+    cube.eo[..11].iter().fold(0, |f, &o| 2 * f + o as usize)
+}
+
+pub fn set_flip(cube: &mut CubieCube, mut flip: usize) {
+    let mut sum = 0;
+    for i in (0..11).rev() {
+        cube.eo[i] = (flip % 2) as u8;
+        sum += cube.eo[i];
+        flip /= 2;
+    }
+    cube.eo[11] = sum % 2;
+}
+
+/// Which four slots hold the E slice edges (FR, FL, BL, BR), ignoring their
+/// order. Zero when they are all in the slice.
+pub fn slice(cube: &CubieCube) -> usize {
+    let mut out = 0;
+    let mut seen = 0;
+    for j in (0..12).rev() {
+        if cube.ep[j] >= 8 {
+            out += choose(11 - j, seen + 1);
+            seen += 1;
+        }
+    }
+    out
+}
+
+/// Place the E slice edges in the slots given by a slice coordinate, and
+/// the other edges in order around them.
+pub fn set_slice(cube: &mut CubieCube, mut slice: usize) {
+    let mut ep = [u8::MAX; 12];
+    let mut left = 4;
+    for (j, e) in ep.iter_mut().enumerate() {
+        if left > 0 && slice >= choose(11 - j, left) {
+            *e = 12 - left as u8;
+            slice -= choose(11 - j, left);
+            left -= 1;
+        }
+    }
+    for (other, e) in ep.iter_mut().filter(|e| **e == u8::MAX).enumerate() {
+        *e = other as u8;
+    }
+    cube.ep = ep;
+}
+
+/// Lexicographic rank of a permutation of `0..n` (or of any distinct values).
+pub fn perm_rank(perm: &[u8]) -> usize {
+    let n = perm.len();
+    let mut rank = 0;
+    for i in 0..n {
+        let smaller = perm[i + 1..].iter().filter(|&&x| x < perm[i]).count();
+        rank = rank * (n - i) + smaller;
+    }
+    rank
+}
+
+/// The permutation of `0..perm.len()` with the given rank.
+pub fn set_perm_rank(perm: &mut [u8], mut rank: usize) {
+    let n = perm.len();
+    let mut digits = vec![0; n];
+    for i in (0..n).rev() {
+        digits[i] = rank % (n - i);
+        rank /= n - i;
+    }
+    let mut left: Vec<u8> = (0..n as u8).collect();
+    for i in 0..n {
+        perm[i] = left.remove(digits[i]);
+    }
+}
+
+/// Permutation of the corners.
+pub fn corner_perm(cube: &CubieCube) -> usize {
+    perm_rank(&cube.cp)
+}
+
+pub fn set_corner_perm(cube: &mut CubieCube, rank: usize) {
+    set_perm_rank(&mut cube.cp, rank);
+}
+
+/// Permutation of the U and D layer edges, when they are all in those layers.
+pub fn ud_edge_perm(cube: &CubieCube) -> usize {
+    perm_rank(&cube.ep[..8])
+}
+
+pub fn set_ud_edge_perm(cube: &mut CubieCube, rank: usize) {
+    set_perm_rank(&mut cube.ep[..8], rank);
+}
+
+/// Permutation of the E slice edges, when they are all in the slice.
+pub fn slice_perm(cube: &CubieCube) -> usize {
+    perm_rank(&cube.ep[8..])
+}
+
+pub fn set_slice_perm(cube: &mut CubieCube, rank: usize) {
+    set_perm_rank(&mut cube.ep[8..], rank);
+    for e in cube.ep[8..].iter_mut() {
+        *e += 8;
+    }
+}
+
+/// For every value of a coordinate and every move, the value after the move.
+pub fn move_table(
+    size: usize,
+    moves: &[CubieCube],
+    set: impl Fn(&mut CubieCube, usize),
+    get: impl Fn(&CubieCube) -> usize,
+) -> Vec<Vec<u16>> {
+    (0..size).map(|i| {
+        let mut cube = CubieCube::SOLVED;
+        set(&mut cube, i);
+        moves.iter().map(|mv| get(&cube.multiply(mv)) as u16).collect()
+    }).collect()
+}
+
+/// Breadth-first distances from zero in the product of two coordinates,
+/// indexed by `a * b_size + b`.
+pub fn pruning_table(a_moves: &[Vec<u16>], b_moves: &[Vec<u16>]) -> Vec<u8> {
+    pruning_table_to(a_moves, b_moves, [0])
+}
+
+/// Breadth-first distances from the nearest of some goals in the product of
+/// two coordinates, indexed like [`pruning_table`].
+pub fn pruning_table_to(a_moves: &[Vec<u16>], b_moves: &[Vec<u16>], goals: impl IntoIterator<Item = usize>) -> Vec<u8> {
+    let b_size = b_moves.len();
+    let mut table = vec![u8::MAX; a_moves.len() * b_size];
+    let mut frontier = vec![];
+    for goal in goals {
+        if table[goal] != 0 {
+            table[goal] = 0;
+            frontier.push(goal);
+        }
+    }
+    let mut depth = 0;
+    while !frontier.is_empty() {
+        depth += 1;
+        let mut next = vec![];
+        for index in frontier {
+            let (a, b) = (index / b_size, index % b_size);
+            for m in 0..a_moves[a].len() {
+                let target = a_moves[a][m] as usize * b_size + b_moves[b][m] as usize;
+                if table[target] == u8::MAX {
+                    table[target] = depth;
+                    next.push(target);
+                }
+            }
+        }
+        frontier = next;
+    }
+    table
+}
@@ -0,0 +1,375 @@
+//! Finding many algorithms for a case, as Cube Explorer does: every move
+//! sequence up to some length that reaches a goal using only the moves of a
+//! generator set such as `<R, U>`.
+//!
+//! The search works on stickers, so generators can include wide turns,
+//! slice turns and rotations. A table of positions near the goal, built
+//! backwards, prunes the last moves of the search; the first moves are
+//! enumerated exhaustively, so long searches with many generators are slow.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::str::FromStr;
+
+use crate::library::CaseSet;
+use crate::rubik::{corner_facelets, edge_facelets, Color, CubeState, Move, U};
+
+use super::two_phase::allowed_after;
+
+/// Largest number of positions kept in the table of positions near the goal.
+const MAX_TABLE_SIZE: usize = 2_000_000;
+
+/// The moves a search may use: every amount of a set of move families.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Generators {
+    moves: Vec<Move>,
+}
+
+impl Generators {
+    /// Every amount of the families of the given moves.
+    pub fn new(families: &[Move]) -> Generators {
+        let mut moves = vec![];
+        for family in families {
+            for amount in 1..4 {
+                let mv = Move::from_base(family.base(), amount).unwrap();
+                if !moves.contains(&mv) {
+                    moves.push(mv);
+                }
+            }
+        }
+        Generators { moves }
+    }
+
+    pub fn moves(&self) -> &[Move] {
+        &self.moves
+    }
+}
+
+impl FromStr for Generators {
+    type Err = String;
+
+    /// Parse a list of moves such as `<R, U, F>`, `R,U,F`, `R U F` or `RUF`.
+    fn from_str(input: &str) -> Result<Generators, String> {
+        let inner = input.trim().trim_start_matches('<').trim_end_matches('>');
+        let mut names = vec![];
+        for c in inner.chars() {
+            if c == ',' || c.is_whitespace() {
+                names.push(String::new());
+            } else if c.is_ascii_alphabetic() && c != 'w' || names.is_empty() {
+                // Every letter but the w of wide turns starts a move.
+                names.push(c.to_string());
+            } else {
+                names.last_mut().unwrap().push(c);
+            }
+        }
+        let families = names.iter()
+            .filter(|name| !name.is_empty())
+            .map(|name| name.parse::<Move>())
+            .collect::<Result<Vec<_>, _>>()?;
+        if families.is_empty() {
+            return Err("No generators".to_string());
+        }
+        Ok(Generators::new(&families))
+    }
+}
+
+impl fmt::Display for Generators {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let families: Vec<String> = self.moves.iter().step_by(3).map(|mv| mv.to_string()).collect();
+        write!(f, "<{}>", families.join(", "))
+    }
+}
+
+/// What a search has to reach.
+#[derive(Clone, Debug)]
+pub enum Goal {
+    /// Exactly this state.
+    State(CubeState),
+    /// The step solving a case set done (see
+    /// [`is_step_solved`](crate::library::is_step_solved)), up to a final U
+    /// turn. Stickers that don't matter for the step are ignored.
+    Step(CaseSet),
+}
+
+impl Goal {
+    /// A state with the stickers that don't matter replaced by
+    /// [`Color::None`].
+    fn mask(&self, state: &CubeState) -> CubeState {
+        let set = match self {
+            Goal::State(_) => return state.clone(),
+            Goal::Step(set) => *set,
+        };
+        let up = state.facelet(U * 9 + 4);
+        let mut facelets = *state.facelets();
+        let pieces = corner_facelets().iter().map(|c| &c[..]).chain(edge_facelets().iter().map(|e| &e[..]));
+        for piece in pieces {
+            if !piece.iter().any(|&f| state.facelet(f) == up) {
+                continue;
+            }
+            // A last layer piece: F2L ignores it, OLL only cares where its U
+            // sticker faces.
+            for &f in piece {
+                match set {
+                    CaseSet::F2l => facelets[f] = Color::None,
+                    CaseSet::Oll if state.facelet(f) != up => facelets[f] = Color::None,
+                    _ => {}
+                }
+            }
+        }
+        CubeState::from_facelets(facelets)
+    }
+
+    /// The masked states that count as reached, with the U turn that
+    /// finishes each.
+    fn states(&self) -> Vec<(CubeState, Option<Move>)> {
+        match self {
+            Goal::State(state) => vec![(state.clone(), None)],
+            Goal::Step(_) => {
+                let mut out: Vec<(CubeState, Option<Move>)> = vec![];
+                for quarters in 0..4 {
+                    let mut state = CubeState::solved();
+                    state.apply_all(Move::from_face(U, quarters));
+                    let state = self.mask(&state);
+                    if !out.iter().any(|(s, _)| *s == state) {
+                        out.push((state, Move::from_face(U, 4 - quarters)));
+                    }
+                }
+                out
+            }
+        }
+    }
+}
+
+/// How a search runs.
+#[derive(Clone, Debug)]
+pub struct GenerateOptions {
+    pub generators: Generators,
+    /// Longest sequence to look for.
+    pub max_length: usize,
+    /// Also try the sequences after each U turn, for cases that are only
+    /// defined up to the AUF before the algorithm.
+    pub pre_auf: bool,
+    /// How many algorithms to return, at most.
+    pub max_results: usize,
+}
+
+impl Default for GenerateOptions {
+    fn default() -> Self {
+        GenerateOptions {
+            generators: Generators::new(&[Move::R, Move::U]),
+            max_length: 12,
+            pre_auf: false,
+            max_results: 100,
+        }
+    }
+}
+
+/// An algorithm found by [`generate`].
+#[derive(Clone, Debug)]
+pub struct Generated {
+    pub moves: Vec<Move>,
+    /// The U turn to do first, with [`GenerateOptions::pre_auf`].
+    pub pre_auf: Option<Move>,
+    /// The U turn that finishes a [`Goal::Step`].
+    pub post_auf: Option<Move>,
+    /// The ergonomics score: lower is easier to turn.
+    pub score: f64,
+}
+
+/// A rough measure of how hard a sequence is to turn quickly, as used by
+/// default to rank algorithms of the same length: R and U turns are easiest,
+/// then L, F, D, slices and wide turns, with B and rotations hardest. Half
+/// turns and changes between the hands cost a little more.
+pub fn ergonomics(moves: &[Move]) -> f64 {
+    let mut score = 0.0;
+    for (i, mv) in moves.iter().enumerate() {
+        let family = Move::from_base(mv.base(), 1).unwrap();
+        score += match family {
+            Move::R | Move::U => 1.0,
+            Move::Rw | Move::L => 1.2,
+            Move::F | Move::D | Move::M | Move::Lw => 1.4,
+            Move::Uw | Move::Fw | Move::E | Move::S => 1.8,
+            Move::B => 2.2,
+            _ => 2.5,
+        };
+        if mv.amount() == 2 {
+            score += 0.3;
+        }
+        // Alternating between left and right hand moves slows down.
+        if i > 0 && mv.axis() == 0 && moves[i - 1].axis() == 0 && mv.base() != moves[i - 1].base() {
+            score += 0.5;
+        }
+    }
+    score
+}
+
+struct Search<'a> {
+    moves: &'a [Move],
+    goals: HashMap<CubeState, Option<Move>>,
+    /// Distance to the nearest goal of positions near the goals.
+    near: HashMap<CubeState, usize>,
+    near_depth: usize,
+    max_length: usize,
+    /// Don't start with a U turn, when the pre-AUF is tried separately.
+    skip_first_u: bool,
+    path: Vec<Move>,
+    found: Vec<(Vec<Move>, Option<Move>)>,
+}
+
+impl Search<'_> {
+    fn search(&mut self, state: &CubeState) {
+        if let Some(&post_auf) = self.goals.get(state) {
+            self.found.push((self.path.clone(), post_auf));
+            // Longer sequences through the goal aren't interesting.
+            return;
+        }
+        let left = self.max_length - self.path.len();
+        if left == 0 {
+            return;
+        }
+        if left <= self.near_depth {
+            match self.near.get(state) {
+                Some(&distance) if distance <= left => {}
+                _ => return,
+            }
+        }
+        for &mv in self.moves {
+            if !allowed_after(self.path.last().copied(), mv) {
+                continue;
+            }
+            if self.skip_first_u && self.path.is_empty() && mv.face() == Some(U) {
+                continue;
+            }
+            let mut next = state.clone();
+            next.apply(mv);
+            self.path.push(mv);
+            self.search(&next);
+            self.path.pop();
+        }
+    }
+}
+
+/// Every sequence of generator moves up to the maximum length that takes
+/// `start` to the goal, never passing through it, without consecutive turns
+/// of the same layers and with turns around the same axis in one order.
+/// Results are sorted by length, then by `score` (lower first).
+pub fn generate(
+    start: &CubeState,
+    goal: &Goal,
+    options: &GenerateOptions,
+    score: impl Fn(&[Move]) -> f64,
+) -> Vec<Generated> {
+    let moves = options.generators.moves();
+    let goals: HashMap<CubeState, Option<Move>> = goal.states().into_iter().collect();
+
+    // Positions near the goals, found backwards. Generators contain every
+    // amount of their moves, so the inverse of a generator is one too.
+    let mut near: HashMap<CubeState, usize> = goals.keys().map(|s| (s.clone(), 0)).collect();
+    let mut frontier: Vec<CubeState> = goals.keys().cloned().collect();
+    let mut near_depth = 0;
+    while near_depth < options.max_length && !frontier.is_empty() && near.len() * moves.len() < MAX_TABLE_SIZE {
+        near_depth += 1;
+        let mut next = vec![];
+        for state in &frontier {
+            for &mv in moves {
+                let mut s = state.clone();
+                s.apply(mv);
+                near.entry(s.clone()).or_insert_with(|| {
+                    next.push(s);
+                    near_depth
+                });
+            }
+        }
+        frontier = next;
+    }
+
+    let mut search = Search {
+        moves,
+        goals,
+        near,
+        near_depth,
+        max_length: options.max_length,
+        skip_first_u: options.pre_auf,
+        path: vec![],
+        found: vec![],
+    };
+    let mut results = vec![];
+    let pre_aufs = if options.pre_auf { 4 } else { 1 };
+    for quarters in 0..pre_aufs {
+        let pre_auf = Move::from_face(U, quarters);
+        let mut state = start.clone();
+        state.apply_all(pre_auf);
+        search.search(&goal.mask(&state));
+        for (moves, post_auf) in search.found.drain(..) {
+            let score = score(&moves);
+            results.push(Generated { moves, pre_auf, post_auf, score });
+        }
+    }
+    results.sort_by(|a, b| a.moves.len().cmp(&b.moves.len()).then(a.score.total_cmp(&b.score)));
+    results.truncate(options.max_results);
+    results
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::library;
+
+    #[test]
+    fn algorithms_reach_the_goal() {
+        let mut start = CubeState::solved();
+        start.apply_all(Move::from_sequence("R U R' U R U2 R'").unwrap());
+        let options = GenerateOptions { max_length: 8, max_results: 10, ..GenerateOptions::default() };
+        let found = generate(&start, &Goal::State(CubeState::solved()), &options, ergonomics);
+        assert!(!found.is_empty());
+        assert_eq!(found[0].moves.len(), 7);
+        for generated in &found {
+            let mut state = start.clone();
+            state.apply_all(generated.moves.iter().copied());
+            assert!(state.is_solved());
+            assert!(generated.moves.iter().all(|mv| [Move::R.base(), Move::U.base()].contains(&mv.base())));
+        }
+    }
+
+    #[test]
+    fn steps_with_aufs() {
+        let sune = CaseSet::Oll.case("OLL 27").unwrap();
+        let mut start = sune.setup_state();
+        start.apply(Move::U);
+        let options = GenerateOptions { max_length: 7, pre_auf: true, ..GenerateOptions::default() };
+        let found = generate(&start, &Goal::Step(CaseSet::Oll), &options, ergonomics);
+        assert!(found.iter().any(|generated| generated.moves == sune.algorithm() && generated.pre_auf == Some(Move::UP)));
+        for generated in &found {
+            assert_eq!(generated.moves.len(), 7);
+            // The pre-AUF isn't repeated as a first move.
+            assert_ne!(generated.moves.first().map(|mv| mv.base()), Some(Move::U.base()));
+            let mut state = start.clone();
+            state.apply_all(generated.pre_auf.into_iter().chain(generated.moves.iter().copied()));
+            assert!(library::is_step_solved(CaseSet::Oll, &state));
+        }
+
+        // A solved last layer turned by U only needs the final U turn.
+        let mut start = CubeState::solved();
+        start.apply(Move::U);
+        let found = generate(&start, &Goal::Step(CaseSet::Pll), &GenerateOptions::default(), ergonomics);
+        assert_eq!(found[0].moves, []);
+        assert_eq!(found[0].post_auf, Some(Move::UP));
+    }
+
+    #[test]
+    fn generator_notation() {
+        let ruf = Generators::new(&[Move::R, Move::U, Move::F]);
+        for input in ["<R,U,F>", "<R, U, F>", "RUF", "R U F", " R,U,F "] {
+            assert_eq!(input.parse(), Ok(ruf.clone()), "{}", input);
+        }
+        assert_eq!(ruf.to_string(), "<R, U, F>");
+        assert_eq!(ruf.moves().len(), 9);
+        let wide = Generators::new(&[Move::Rw, Move::U]);
+        assert_eq!("Rw U".parse(), Ok(wide.clone()));
+        assert_eq!("<RwU>".parse(), Ok(wide.clone()));
+        assert_eq!("r U".parse(), Ok(wide));
+        for invalid in ["", "<>", "R Q", "R, 2"] {
+            assert!(invalid.parse::<Generators>().is_err(), "{}", invalid);
+        }
+    }
+}
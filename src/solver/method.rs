@@ -0,0 +1,527 @@
+//! Step solvers for Roux, ZZ and Petrus: the shortest ways to do a step of
+//! a method, such as building a block, from the moves done so far.
+//!
+//! Steps are searched with the moves a solver would use for them, so the
+//! Roux second block and last six edges use `M` and `r`. The first step of a
+//! method can be searched in several ways of holding the cube, such as all
+//! of [`Move::orientations`] for a color neutral solver; later steps keep
+//! the way the moves so far leave the cube held.
+
+use std::fmt;
+use std::str::FromStr;
+use std::sync::OnceLock;
+
+use crate::rubik::{edge_facelets, Color, CubieCube, Move};
+
+use super::coord::{flip, N_FLIP};
+use super::optimal::{corner_tables, CornerTables};
+use super::two_phase::allowed_after;
+
+/// A step of a method.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum Step {
+    /// Roux: a 1x2x3 block on the left.
+    FirstBlock,
+    /// Roux: the 1x2x3 block on the right, with `<U, R, r, M>`.
+    SecondBlock,
+    /// Roux: the corners of the last layer, keeping both blocks.
+    Cmll,
+    /// Roux: the last six edges and the centers, with `<M, U>`.
+    Lse,
+    /// ZZ: orient every edge and solve DF and DB.
+    EoLine,
+    /// ZZ: orient every edge and solve the cross.
+    EoCross,
+    /// Petrus: a 2x2x2 block at DBL.
+    Block222,
+    /// Petrus: the 2x2x2 block extended to a 2x2x3 on the left.
+    Block223,
+}
+
+impl Step {
+    pub const ALL: [Step; 8] = [
+        Step::FirstBlock, Step::SecondBlock, Step::Cmll, Step::Lse,
+        Step::EoLine, Step::EoCross,
+        Step::Block222, Step::Block223,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Step::FirstBlock => "First block",
+            Step::SecondBlock => "Second block",
+            Step::Cmll => "CMLL",
+            Step::Lse => "LSE",
+            Step::EoLine => "EOLine",
+            Step::EoCross => "EOCross",
+            Step::Block222 => "2x2x2",
+            Step::Block223 => "2x2x3",
+        }
+    }
+
+    pub fn method(&self) -> &'static str {
+        match self {
+            Step::FirstBlock | Step::SecondBlock | Step::Cmll | Step::Lse => "Roux",
+            Step::EoLine | Step::EoCross => "ZZ",
+            Step::Block222 | Step::Block223 => "Petrus",
+        }
+    }
+
+    /// Whether the step starts a solve, so it may be done holding the cube
+    /// any way.
+    pub fn is_first(&self) -> bool {
+        matches!(self, Step::FirstBlock | Step::EoLine | Step::EoCross | Step::Block222)
+    }
+
+    /// A length that almost every case of the step can be done in.
+    pub fn max_length(&self) -> usize {
+        match self {
+            Step::FirstBlock => 10,
+            Step::SecondBlock => 16,
+            Step::Cmll => 14,
+            Step::Lse => 20,
+            Step::EoLine => 9,
+            Step::EoCross => 11,
+            Step::Block222 => 8,
+            Step::Block223 => 10,
+        }
+    }
+
+    fn definition(&self) -> &'static Definition {
+        &DEFINITIONS[*self as usize]
+    }
+}
+
+impl fmt::Display for Step {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for Step {
+    type Err = String;
+
+    /// Parse a step by name, case insensitively: `fb`, `sb`, `cmll`, `lse`,
+    /// `eoline`, `eocross`, `2x2x2` or `2x2x3`.
+    fn from_str(input: &str) -> Result<Step, String> {
+        match input.to_lowercase().as_str() {
+            "fb" | "first block" => Ok(Step::FirstBlock),
+            "sb" | "second block" => Ok(Step::SecondBlock),
+            "cmll" => Ok(Step::Cmll),
+            "lse" => Ok(Step::Lse),
+            "eoline" => Ok(Step::EoLine),
+            "eocross" => Ok(Step::EoCross),
+            "2x2x2" => Ok(Step::Block222),
+            "2x2x3" => Ok(Step::Block223),
+            _ => Err(format!("Unknown step {}", input)),
+        }
+    }
+}
+
+/// A cube seen from a fixed frame, ignoring its centers: which corners and
+/// edges are in which slots, and how far the M slice centers are turned.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+struct Position {
+    cube: CubieCube,
+    /// Quarter turns of the M slice centers, in the direction of `M`.
+    centers: u8,
+}
+
+impl Position {
+    const SOLVED: Position = Position { cube: CubieCube::SOLVED, centers: 0 };
+
+    /// This position, then another.
+    fn then(&self, other: &Position) -> Position {
+        Position { cube: self.cube.multiply(&other.cube), centers: (self.centers + other.centers) % 4 }
+    }
+
+    /// The effect of a face turn, a turn of the M slice, or a wide `r` or `l`.
+    fn from_move(mv: Move) -> Result<Position, String> {
+        if mv.is_face_turn() {
+            return Ok(Position { cube: CubieCube::from_move(mv), centers: 0 });
+        }
+        let amount = mv.amount();
+        let turn = |base: usize, amount: u8| Move::from_base(base, amount).unwrap();
+        if mv.base() == Move::M.base() {
+            Ok(Position { cube: m_slice_cubies(mv), centers: amount })
+        } else if mv.base() == Move::Rw.base() {
+            // r is R with M'.
+            Ok(Position::from_move(turn(Move::R.base(), amount))?.then(&Position::from_move(turn(Move::M.base(), 4 - amount))?))
+        } else if mv.base() == Move::Lw.base() {
+            Ok(Position::from_move(turn(Move::L.base(), amount))?.then(&Position::from_move(turn(Move::M.base(), amount))?))
+        } else {
+            Err(format!("{} can't be used in these steps", mv))
+        }
+    }
+}
+
+/// How an M slice turn moves the edges. The edges change parity without the
+/// corners, so this is a cube only together with its centers.
+fn m_slice_cubies(mv: Move) -> CubieCube {
+    let mut state = CubieCube::SOLVED.to_state();
+    state.apply(mv);
+    let edges = edge_facelets();
+    let mut cube = CubieCube::SOLVED;
+    for (slot, facelets) in edges.iter().enumerate() {
+        let colors = facelets.map(|f| state.facelet(f));
+        let home = |piece: &[usize; 2], i: usize| Color::FACES[piece[i] / 9];
+        let (piece, flip) = edges.iter().enumerate()
+            .find_map(|(piece, fs)| {
+                if colors == [home(fs, 0), home(fs, 1)] {
+                    Some((piece, 0))
+                } else if colors == [home(fs, 1), home(fs, 0)] {
+                    Some((piece, 1))
+                } else {
+                    None
+                }
+            })
+            .expect("An M turn should only move edges");
+        cube.ep[slot] = piece as u8;
+        cube.eo[slot] = flip;
+    }
+    cube
+}
+
+/// Some of what a step solves: corners and edges (by piece, in
+/// [`CubieCube`] order), edge orientation and the M slice centers. The
+/// distances from solved of all its values make a pruning table.
+#[derive(Clone, Copy)]
+struct Pattern {
+    corners: &'static [usize],
+    edges: &'static [usize],
+    eo: bool,
+    centers: bool,
+}
+
+const fn pieces(corners: &'static [usize], edges: &'static [usize]) -> Pattern {
+    Pattern { corners, edges, eo: false, centers: false }
+}
+
+impl Pattern {
+    fn size(&self) -> usize {
+        let pieces = 24usize.pow((self.corners.len() + self.edges.len()) as u32);
+        pieces * if self.eo { N_FLIP } else { 1 } * if self.centers { 4 } else { 1 }
+    }
+
+    fn index(&self, position: &Position) -> usize {
+        let cube = &position.cube;
+        let mut index = 0;
+        for &piece in self.corners {
+            let slot = cube.cp.iter().position(|&p| p as usize == piece).unwrap();
+            index = index * 24 + 3 * slot + cube.co[slot] as usize;
+        }
+        for &piece in self.edges {
+            let slot = cube.ep.iter().position(|&p| p as usize == piece).unwrap();
+            index = index * 24 + 2 * slot + cube.eo[slot] as usize;
+        }
+        if self.eo {
+            index = index * N_FLIP + flip(cube);
+        }
+        if self.centers {
+            index = index * 4 + position.centers as usize;
+        }
+        index
+    }
+
+    /// Distances from solved, in moves of the generators.
+    fn table(&self, generators: &[Position]) -> Vec<u8> {
+        let mut table = vec![u8::MAX; self.size()];
+        table[self.index(&Position::SOLVED)] = 0;
+        let mut frontier = vec![Position::SOLVED];
+        let mut depth = 0;
+        while !frontier.is_empty() {
+            depth += 1;
+            let mut next = vec![];
+            for position in frontier {
+                for generator in generators {
+                    let moved = position.then(generator);
+                    let index = self.index(&moved);
+                    if table[index] == u8::MAX {
+                        table[index] = depth;
+                        next.push(moved);
+                    }
+                }
+            }
+            frontier = next;
+        }
+        table
+    }
+}
+
+/// What a step solves, split into patterns small enough to tabulate, and
+/// the moves it is done with.
+struct Definition {
+    generators: &'static [Move],
+    /// Everything the step solves is in some pattern, or in the corners.
+    patterns: &'static [Pattern],
+    /// Whether the step solves every corner, which the optimal solver's
+    /// corner database gives good estimates for.
+    corners: bool,
+}
+
+const SECOND_BLOCK_MOVES: [Move; 12] = [
+    Move::U, Move::UP, Move::U2,
+    Move::R, Move::RP, Move::R2,
+    Move::Rw, Move::RwP, Move::Rw2,
+    Move::M, Move::MP, Move::M2,
+];
+
+const LSE_MOVES: [Move; 6] = [Move::U, Move::UP, Move::U2, Move::M, Move::MP, Move::M2];
+
+// Corners: URF, UFL, ULB, UBR, DFR, DLF, DBL, DRB.
+// Edges: UR, UF, UL, UB, DR, DF, DL, DB, FR, FL, BL, BR.
+const FIRST_BLOCK: [Pattern; 2] = [pieces(&[5, 6], &[6, 9]), pieces(&[5, 6], &[6, 10])];
+const SECOND_BLOCK: [Pattern; 2] = [pieces(&[4, 7], &[4, 8]), pieces(&[4, 7], &[4, 11])];
+
+/// In [`Step::ALL`] order.
+const DEFINITIONS: [Definition; 8] = [
+    Definition { generators: &Move::FACE_TURNS, patterns: &FIRST_BLOCK, corners: false },
+    Definition { generators: &SECOND_BLOCK_MOVES, patterns: &SECOND_BLOCK, corners: false },
+    Definition {
+        generators: &Move::FACE_TURNS,
+        patterns: &[
+            FIRST_BLOCK[0], FIRST_BLOCK[1],
+            SECOND_BLOCK[0], SECOND_BLOCK[1],
+        ],
+        corners: true,
+    },
+    Definition {
+        generators: &LSE_MOVES,
+        patterns: &[
+            Pattern { corners: &[0], edges: &[0, 2], eo: false, centers: true },
+            Pattern { corners: &[], edges: &[1, 3, 5, 7], eo: false, centers: true },
+        ],
+        corners: false,
+    },
+    Definition {
+        generators: &Move::FACE_TURNS,
+        patterns: &[Pattern { corners: &[], edges: &[5, 7], eo: true, centers: false }],
+        corners: false,
+    },
+    Definition {
+        generators: &Move::FACE_TURNS,
+        patterns: &[
+            Pattern { corners: &[], edges: &[5, 7], eo: true, centers: false },
+            Pattern { corners: &[], edges: &[4, 6], eo: true, centers: false },
+            pieces(&[], &[4, 5, 6, 7]),
+        ],
+        corners: false,
+    },
+    Definition { generators: &Move::FACE_TURNS, patterns: &[pieces(&[6], &[6, 7, 10])], corners: false },
+    Definition {
+        generators: &Move::FACE_TURNS,
+        patterns: &[pieces(&[6], &[6, 7, 10]), pieces(&[5], &[5, 6, 9]), pieces(&[5, 6], &[5, 7])],
+        corners: false,
+    },
+];
+
+/// The pruning tables of a step, one for each pattern.
+fn tables(step: Step) -> &'static [Vec<u8>] {
+    static TABLES: [OnceLock<Vec<Vec<u8>>>; 8] = [const { OnceLock::new() }; 8];
+    TABLES[step as usize].get_or_init(|| {
+        let definition = step.definition();
+        let generators: Vec<Position> = definition.generators.iter()
+            .map(|&mv| Position::from_move(mv).expect("Step generators should be supported moves"))
+            .collect();
+        definition.patterns.iter().map(|pattern| pattern.table(&generators)).collect()
+    })
+}
+
+/// A way to do a step.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct StepSolution {
+    /// How to hold the cube first, as rotations.
+    pub rotation: Vec<Move>,
+    /// The moves of the step, after the rotation.
+    pub moves: Vec<Move>,
+}
+
+/// The moves as seen holding the cube after them and then turning it by
+/// `hold`, without rotations: rotations among the moves turn every move
+/// after them instead.
+fn held_moves(moves: &[Move], hold: &[Move]) -> Vec<Move> {
+    let mut rotations: Vec<Move> = vec![];
+    let mut unrotated = vec![];
+    for &mv in moves {
+        if mv.is_rotation() {
+            rotations.push(mv);
+        } else {
+            unrotated.push(rotations.iter().rev().fold(mv, |mv, &rotation| mv.rotate(rotation)));
+        }
+    }
+    rotations.extend(hold);
+    unrotated.iter().map(|&mv| rotations.iter().fold(mv, |mv, rotation| mv.rotate(rotation.inverse()))).collect()
+}
+
+struct Search<'a> {
+    generators: Vec<(Move, Position)>,
+    patterns: &'a [Pattern],
+    tables: &'a [Vec<u8>],
+    corners: Option<&'a CornerTables>,
+    max_results: usize,
+    path: Vec<Move>,
+    found: Vec<Vec<Move>>,
+}
+
+impl Search<'_> {
+    fn search(&mut self, position: Position, depth: usize) {
+        let distance = self.patterns.iter().zip(self.tables)
+            .map(|(pattern, table)| table[pattern.index(&position)] as usize)
+            .chain(self.corners.map(|corners| corners.distance(CornerTables::index(&position.cube)) as usize))
+            .max()
+            .unwrap_or(0);
+        if distance == 0 {
+            if depth == 0 && self.found.len() < self.max_results {
+                self.found.push(self.path.clone());
+            }
+            // Longer ways through solved aren't interesting.
+            return;
+        }
+        if distance > depth || self.found.len() >= self.max_results {
+            return;
+        }
+        for g in 0..self.generators.len() {
+            let (mv, effect) = self.generators[g];
+            if !allowed_after(self.path.last().copied(), mv) {
+                continue;
+            }
+            self.path.push(mv);
+            self.search(position.then(&effect), depth - 1);
+            self.path.pop();
+        }
+    }
+}
+
+/// The shortest ways, up to `max_length` moves, to do a step after some
+/// moves (a scramble and the steps before), holding the cube in each of
+/// `orientations` (rotations from how the moves leave it held; `&[vec![]]`
+/// to keep it). Shortest first, then in the order of `orientations`.
+///
+/// The moves may include rotations, and any moves that are face turns,
+/// `M` turns or `r` and `l` turns once the cube is held for the step.
+///
+/// Each step builds its tables on first use; CMLL also builds the optimal
+/// solver's corner database, which takes 44 MB and some seconds.
+pub fn solve_step(
+    moves: &[Move],
+    step: Step,
+    orientations: &[Vec<Move>],
+    max_length: usize,
+    max_results: usize,
+) -> Result<Vec<StepSolution>, String> {
+    let definition = step.definition();
+    let tables = tables(step);
+    let mut starts = vec![];
+    for rotation in orientations {
+        let mut position = Position::SOLVED;
+        for mv in held_moves(moves, rotation) {
+            position = position.then(&Position::from_move(mv)?);
+        }
+        starts.push(position);
+    }
+    let generators = definition.generators.iter().map(|&mv| (mv, Position::from_move(mv).unwrap())).collect();
+    let corners = definition.corners.then(|| corner_tables(&mut |_| true).expect("Building is only cancelled by the callback"));
+    let mut search = Search {
+        generators,
+        patterns: definition.patterns,
+        tables,
+        corners,
+        max_results,
+        path: vec![],
+        found: vec![],
+    };
+    let mut solutions = vec![];
+    for depth in 0..=max_length {
+        for (rotation, &start) in orientations.iter().zip(&starts) {
+            search.search(start, depth);
+            solutions.extend(search.found.drain(..).map(|moves| StepSolution { rotation: rotation.clone(), moves }));
+            search.max_results = max_results - solutions.len();
+            if search.max_results == 0 {
+                return Ok(solutions);
+            }
+        }
+    }
+    Ok(solutions)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rubik::CubeState;
+    use crate::scramble::{random_state_scramble, Rng};
+
+    /// The pieces after some moves, as seen holding the cube the way the
+    /// rotations among them leave it, with the M slice turned back.
+    fn held(moves: &[Move]) -> CubieCube {
+        let mut state = CubeState::solved();
+        state.apply_all(moves.iter().copied());
+        let mut held = CubeState::solved();
+        held.apply_all(moves.iter().copied().filter(Move::is_rotation));
+        (0..4)
+            .map(|quarters| {
+                let mut state = state.clone();
+                state.apply_all(Move::from_base(Move::M.base(), quarters));
+                state
+            })
+            .find(|state| state.facelet(4) == held.facelet(4) && state.facelet(22) == held.facelet(22))
+            .map(|state| CubieCube::from_state(&state).unwrap())
+            .unwrap()
+    }
+
+    fn is_solved(cube: &CubieCube, corners: &[usize], edges: &[usize]) -> bool {
+        corners.iter().all(|&slot| cube.is_corner_solved(slot)) && edges.iter().all(|&slot| cube.is_edge_solved(slot))
+    }
+
+    /// Do each step in turn after a scramble, checking every solution found.
+    fn solve_steps(seed: u64, steps: &[Step], check: impl Fn(Step, &CubieCube) -> bool) {
+        let mut moves = random_state_scramble(&mut Rng::new(seed));
+        for &step in steps {
+            let orientations = if step.is_first() { Move::orientations().to_vec() } else { vec![vec![]] };
+            let found = solve_step(&moves, step, &orientations, step.max_length(), 3).unwrap();
+            assert!(!found.is_empty(), "No {} found", step);
+            for solution in &found {
+                let done = [&moves[..], &solution.rotation, &solution.moves].concat();
+                assert!(check(step, &held(&done)), "{} doesn't do {}", Move::to_sequence(&done), step);
+            }
+            moves.extend(&found[0].rotation);
+            moves.extend(&found[0].moves);
+        }
+    }
+
+    #[test]
+    fn roux_blocks() {
+        solve_steps(4, &[Step::FirstBlock, Step::SecondBlock], |step, cube| match step {
+            Step::FirstBlock => is_solved(cube, &[5, 6], &[6, 9, 10]),
+            _ => is_solved(cube, &[4, 5, 6, 7], &[4, 6, 8, 9, 10, 11]),
+        });
+    }
+
+    #[test]
+    #[ignore = "builds the optimal solver's corner database"]
+    fn roux_last_layer() {
+        solve_steps(5, &[Step::FirstBlock, Step::SecondBlock, Step::Cmll, Step::Lse], |step, cube| match step {
+            Step::Cmll => is_solved(cube, &[0, 1, 2, 3, 4, 5, 6, 7], &[4, 6, 8, 9, 10, 11]),
+            Step::Lse => cube.is_solved(),
+            _ => true,
+        });
+    }
+
+    #[test]
+    fn zz_and_petrus() {
+        let oriented = |cube: &CubieCube| cube.eo.iter().all(|&flip| flip == 0);
+        solve_steps(6, &[Step::EoLine, Step::EoCross], |step, cube| match step {
+            Step::EoLine => oriented(cube) && is_solved(cube, &[], &[5, 7]),
+            _ => oriented(cube) && cube.is_cross_solved(),
+        });
+        solve_steps(7, &[Step::Block222, Step::Block223], |step, cube| match step {
+            Step::Block222 => is_solved(cube, &[6], &[6, 7, 10]),
+            _ => is_solved(cube, &[5, 6], &[5, 6, 7, 9, 10]),
+        });
+    }
+
+    #[test]
+    fn unsupported_moves() {
+        assert!(solve_step(&[Move::E], Step::SecondBlock, &[vec![]], 4, 1).is_err());
+        assert_eq!("EOLine".parse(), Ok(Step::EoLine));
+        assert!("zb".parse::<Step>().is_err());
+    }
+}
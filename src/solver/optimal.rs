@@ -0,0 +1,337 @@
+//! An optimal solver: iterative deepening A* in the half-turn metric, with
+//! one pattern database for the corners and two for six edges each.
+//!
+//! The databases take about 130 MB and some seconds to build in a release
+//! build; they are built on the first call. Scrambles of up to 15 moves or
+//! so are solved quickly, while the hardest positions (20 moves) can take
+//! hours. Meant for native use.
+
+use std::sync::OnceLock;
+
+use crate::rubik::{CubieCube, Move};
+
+use super::coord::*;
+use super::two_phase::allowed_after;
+
+/// Arrangements of six edges in twelve slots.
+const N_EDGE_SLOTS: usize = 665_280;
+const N_EDGE_GROUP: usize = N_EDGE_SLOTS * 64;
+const N_CORNERS: usize = N_PERM_8 * N_TWIST;
+/// Marks an unknown distance in a pattern database.
+const UNKNOWN: u8 = 0xF;
+
+/// What the solver is doing, as reported to the progress callback.
+#[derive(Clone, Copy, Debug)]
+pub enum Progress {
+    /// Building a pattern database: all states within `depth` moves of
+    /// solved are known, `filled` out of `total`.
+    Building { table: &'static str, depth: u8, filled: usize, total: usize },
+    /// Looking through all sequences of `depth` moves, after visiting
+    /// `nodes` positions in total.
+    Searching { depth: usize, nodes: u64 },
+}
+
+/// The progress callback asked to stop.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Cancelled;
+
+/// Distances to solved, four bits each.
+struct PatternDatabase {
+    data: Vec<u8>,
+}
+
+impl PatternDatabase {
+    /// Breadth-first search from `start`, for a coordinate with `size`
+    /// values and `apply(index, move)` giving the value after a face turn.
+    /// Stops when `progress` returns false after a level.
+    fn build(
+        table: &'static str,
+        size: usize,
+        start: usize,
+        apply: impl Fn(usize, usize) -> usize,
+        progress: &mut impl FnMut(Progress) -> bool,
+    ) -> Result<PatternDatabase, Cancelled> {
+        let mut db = PatternDatabase { data: vec![0xFF; size.div_ceil(2)] };
+        db.set(start, 0);
+        let mut filled = 1;
+        let mut depth = 0;
+        while filled < size {
+            let before = filled;
+            // Once most states are known it's cheaper to look for unknown
+            // states next to the last level than to expand the last level.
+            let backward = filled > size / 2;
+            for index in 0..size {
+                let value = db.get(index);
+                if backward && value == UNKNOWN {
+                    if (0..18).any(|m| db.get(apply(index, m)) == depth) {
+                        db.set(index, depth + 1);
+                        filled += 1;
+                    }
+                } else if !backward && value == depth {
+                    for m in 0..18 {
+                        let target = apply(index, m);
+                        if db.get(target) == UNKNOWN {
+                            db.set(target, depth + 1);
+                            filled += 1;
+                        }
+                    }
+                }
+            }
+            depth += 1;
+            if !progress(Progress::Building { table, depth, filled, total: size }) {
+                return Err(Cancelled);
+            }
+            if filled == before {
+                break;
+            }
+        }
+        Ok(db)
+    }
+
+    fn get(&self, index: usize) -> u8 {
+        (self.data[index / 2] >> (4 * (index % 2))) & 0xF
+    }
+
+    fn set(&mut self, index: usize, value: u8) {
+        let shift = 4 * (index % 2);
+        let byte = &mut self.data[index / 2];
+        *byte = (*byte & !(0xF << shift)) | (value << shift);
+    }
+}
+
+/// Distances to solved for all states of the corners, also used by the
+/// CMLL step solver.
+pub(super) struct CornerTables {
+    corner_perm: Vec<Vec<u16>>,
+    twist: Vec<Vec<u16>>,
+    database: PatternDatabase,
+}
+
+impl CornerTables {
+    pub(super) fn index(cube: &CubieCube) -> usize {
+        corner_perm(cube) * N_TWIST + twist(cube)
+    }
+
+    /// The corners after a face turn, by index in [`Move::FACE_TURNS`].
+    pub(super) fn apply(&self, index: usize, m: usize) -> usize {
+        let (perm, twist) = (index / N_TWIST, index % N_TWIST);
+        self.corner_perm[perm][m] as usize * N_TWIST + self.twist[twist][m] as usize
+    }
+
+    pub(super) fn distance(&self, index: usize) -> u8 {
+        self.database.get(index)
+    }
+}
+
+pub(super) fn corner_tables(progress: &mut impl FnMut(Progress) -> bool) -> Result<&'static CornerTables, Cancelled> {
+    static TABLES: OnceLock<CornerTables> = OnceLock::new();
+    if let Some(tables) = TABLES.get() {
+        return Ok(tables);
+    }
+    let moves: Vec<CubieCube> = Move::FACE_TURNS.iter().map(|&mv| CubieCube::from_move(mv)).collect();
+    let corner_perm = move_table(N_PERM_8, &moves, set_corner_perm, corner_perm);
+    let twist = move_table(N_TWIST, &moves, set_twist, twist);
+    let database = PatternDatabase::build("corners", N_CORNERS, 0, |index, m| {
+        let (perm, tw) = (index / N_TWIST, index % N_TWIST);
+        corner_perm[perm][m] as usize * N_TWIST + twist[tw][m] as usize
+    }, progress)?;
+    Ok(TABLES.get_or_init(|| CornerTables { corner_perm, twist, database }))
+}
+
+struct Tables {
+    corners: &'static CornerTables,
+    /// For every arrangement of six edges and every move, the arrangement
+    /// after it, with the edges it flips in the bits from 20 up.
+    edge_slots: Vec<[u32; 18]>,
+    /// The edges solved in UR, UF, UL, UB, DR and DF, and in the other slots.
+    edges: [PatternDatabase; 2],
+}
+
+/// The slots of the edges UR to DF (group 0) or DL to BR (group 1), times
+/// 64, plus one bit for the flip of each.
+fn edge_group(cube: &CubieCube, group: usize) -> usize {
+    let mut slots = [0; 6];
+    let mut flips = 0;
+    for (slot, (&piece, &flip)) in cube.ep.iter().zip(&cube.eo).enumerate() {
+        let piece = piece as usize;
+        if piece / 6 == group {
+            slots[piece % 6] = slot;
+            flips |= (flip as usize) << (piece % 6);
+        }
+    }
+    slots_rank(&slots) * 64 + flips
+}
+
+/// Rank of six distinct slots out of twelve, in order.
+fn slots_rank(slots: &[usize; 6]) -> usize {
+    let mut rank = 0;
+    for i in 0..6 {
+        let smaller = (0..12).filter(|&s| s < slots[i] && !slots[..i].contains(&s)).count();
+        rank = rank * (12 - i) + smaller;
+    }
+    rank
+}
+
+fn set_slots_rank(mut rank: usize) -> [usize; 6] {
+    let mut digits = [0; 6];
+    for i in (0..6).rev() {
+        digits[i] = rank % (12 - i);
+        rank /= 12 - i;
+    }
+    let mut left: Vec<usize> = (0..12).collect();
+    digits.map(|d| left.remove(d))
+}
+
+fn edge_slot_table(moves: &[CubieCube]) -> Vec<[u32; 18]> {
+    // Where the piece in each slot goes with each move, and whether it flips.
+    let targets: Vec<[(usize, u8); 12]> = moves.iter().map(|mv| {
+        let mut out = [(0, 0); 12];
+        for (slot, (&from, &flip)) in mv.ep.iter().zip(&mv.eo).enumerate() {
+            out[from as usize] = (slot, flip);
+        }
+        out
+    }).collect();
+    (0..N_EDGE_SLOTS).map(|rank| {
+        let slots = set_slots_rank(rank);
+        std::array::from_fn(|m| {
+            let mut moved = [0; 6];
+            let mut flips = 0;
+            for (i, &slot) in slots.iter().enumerate() {
+                let (target, flip) = targets[m][slot];
+                moved[i] = target;
+                flips |= (flip as u32) << i;
+            }
+            slots_rank(&moved) as u32 | flips << 20
+        })
+    }).collect()
+}
+
+impl Tables {
+    fn edge_move(&self, index: usize, m: usize) -> usize {
+        edge_move(&self.edge_slots, index, m)
+    }
+
+    fn estimate(&self, corners: usize, edges: [usize; 2]) -> u8 {
+        self.corners.distance(corners).max(self.edges[0].get(edges[0])).max(self.edges[1].get(edges[1]))
+    }
+}
+
+fn edge_move(edge_slots: &[[u32; 18]], index: usize, m: usize) -> usize {
+    let entry = edge_slots[index / 64][m] as usize;
+    (entry & 0xF_FFFF) * 64 + ((index % 64) ^ (entry >> 20))
+}
+
+/// The tables, built on the first call that isn't cancelled.
+fn tables(progress: &mut impl FnMut(Progress) -> bool) -> Result<&'static Tables, Cancelled> {
+    static TABLES: OnceLock<Tables> = OnceLock::new();
+    if let Some(tables) = TABLES.get() {
+        return Ok(tables);
+    }
+    let corners = corner_tables(progress)?;
+    let moves: Vec<CubieCube> = Move::FACE_TURNS.iter().map(|&mv| CubieCube::from_move(mv)).collect();
+    let edge_slots = edge_slot_table(&moves);
+    let mut build = |group: usize| {
+        let name = ["edges UR to DF", "edges DL to BR"][group];
+        let start = edge_group(&CubieCube::SOLVED, group);
+        PatternDatabase::build(name, N_EDGE_GROUP, start, |index, m| edge_move(&edge_slots, index, m), progress)
+    };
+    let edges = [build(0)?, build(1)?];
+    Ok(TABLES.get_or_init(|| Tables { corners, edge_slots, edges }))
+}
+
+struct Search<'a, P> {
+    tables: &'a Tables,
+    goal: [usize; 2],
+    path: Vec<Move>,
+    nodes: u64,
+    progress: P,
+}
+
+impl<P: FnMut(Progress) -> bool> Search<'_, P> {
+    fn search(&mut self, corners: usize, edges: [usize; 2], depth: usize) -> Result<bool, Cancelled> {
+        if depth == 0 {
+            return Ok(corners == 0 && edges == self.goal);
+        }
+        self.nodes += 1;
+        if self.nodes.is_multiple_of(1 << 20)
+            && !(self.progress)(Progress::Searching { depth: self.path.len() + depth, nodes: self.nodes })
+        {
+            return Err(Cancelled);
+        }
+        let t = self.tables;
+        for mv in Move::FACE_TURNS {
+            if !allowed_after(self.path.last().copied(), mv) {
+                continue;
+            }
+            let m = mv as usize;
+            let corners = t.corners.apply(corners, m);
+            let edges = edges.map(|e| t.edge_move(e, m));
+            if t.estimate(corners, edges) as usize >= depth {
+                continue;
+            }
+            self.path.push(mv);
+            if self.search(corners, edges, depth - 1)? {
+                return Ok(true);
+            }
+            self.path.pop();
+        }
+        Ok(false)
+    }
+}
+
+/// Find a shortest sequence of face turns solving the cube, if there is one
+/// of at most `max_length` moves.
+///
+/// `progress` is called while the pattern databases are built and now and
+/// then during the search; returning false cancels both.
+pub fn solve(
+    cube: &CubieCube,
+    max_length: usize,
+    mut progress: impl FnMut(Progress) -> bool,
+) -> Result<Option<Vec<Move>>, Cancelled> {
+    let tables = tables(&mut progress)?;
+    let goal = [0, 1].map(|group| edge_group(&CubieCube::SOLVED, group));
+    let corners = CornerTables::index(cube);
+    let edges = [0, 1].map(|group| edge_group(cube, group));
+    let mut search = Search { tables, goal, path: vec![], nodes: 0, progress };
+    for depth in tables.estimate(corners, edges) as usize..=max_length {
+        if !(search.progress)(Progress::Searching { depth, nodes: search.nodes }) {
+            return Err(Cancelled);
+        }
+        if search.search(corners, edges, depth)? {
+            return Ok(Some(search.path));
+        }
+    }
+    Ok(None)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    #[ignore = "builds 130 MB of tables"]
+    fn solutions_are_optimal() {
+        let scramble: Vec<Move> = Move::from_sequence("R U2 F' L D B2 R'").unwrap().collect();
+        let mut cube = CubieCube::SOLVED;
+        cube.apply_all(scramble.iter().copied());
+        let solution = solve(&cube, 20, |_| true).unwrap().unwrap();
+        assert_eq!(solution.len(), scramble.len());
+        cube.apply_all(solution);
+        assert!(cube.is_solved());
+    }
+
+    #[test]
+    fn cancel() {
+        let cube = CubieCube::from_move(Move::R);
+        let mut calls = vec![];
+        let result = solve(&cube, 20, |progress| {
+            calls.push(progress);
+            false
+        });
+        assert_eq!(result, Err(Cancelled));
+        // Whether it stopped building a table or before the search, it
+        // stopped at once.
+        assert_eq!(calls.len(), 1);
+    }
+}
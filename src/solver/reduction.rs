@@ -0,0 +1,352 @@
+//! The reduction steps of modern fewest-moves and ZZ solving: edge
+//! orientation (EO), domino reduction (DR) to `<U, D, R2, L2, F2, B2>` and
+//! half-turn reduction (HTR) to `<U2, D2, R2, L2, F2, B2>`, each on any of the
+//! three axes (numbered as by [`Move::axis`]).
+//!
+//! Edges are oriented on an axis when they can be solved without quarter
+//! turns of the two faces on that axis, so EO on F/B is the orientation of
+//! [`CubieCube::eo`]. DR on an axis orients corners and edges for the faces on
+//! that axis and puts the slice between them back together; HTR leaves a
+//! cube that half turns solve.
+
+use std::fmt;
+use std::sync::OnceLock;
+
+use crate::rubik::{CubieCube, Move};
+
+use super::coord::*;
+use super::two_phase::{self, allowed_after, PHASE2_MOVES};
+
+/// Names of the axes, in [`Move::axis`] order.
+pub const AXIS_NAMES: [&str; 3] = ["R/L", "U/D", "F/B"];
+
+/// Default longest sequences to look for, for EO, DR and HTR: searches
+/// beyond these get slow.
+pub const STEP_LENGTHS: [usize; 3] = [7, 12, 14];
+
+/// A rotation bringing each axis to F/B, where [`CubieCube::eo`] orients edges.
+const EO_ROTATIONS: [Option<Move>; 3] = [Some(Move::Y), Some(Move::X), None];
+
+/// A rotation bringing each axis to U/D, the domino axis of the two-phase
+/// tables.
+const DR_ROTATIONS: [Option<Move>; 3] = [Some(Move::Z), None, Some(Move::X)];
+
+/// Edges in the M slice (UF, UB, DF, DB); the S slice edges are the other
+/// U and D layer edges.
+const M_EDGES: [u8; 4] = [1, 3, 5, 7];
+
+/// Ways to choose which four of the eight U and D layer edge slots hold the
+/// M slice edges.
+const N_SPLIT: usize = 70;
+
+/// The cube as seen after a rotation: its faces are named by where their
+/// centers are then.
+fn view(cube: &CubieCube, rotation: Option<Move>) -> CubieCube {
+    match rotation {
+        None => *cube,
+        Some(rotation) => {
+            let mut state = cube.to_state();
+            state.apply(rotation);
+            CubieCube::from_state(&state).expect("A rotated cube should still be a cube")
+        }
+    }
+}
+
+/// Moves found on a view of the cube, as moves of the cube itself.
+fn unview(moves: &[Move], rotation: Option<Move>) -> Vec<Move> {
+    match rotation {
+        None => moves.to_vec(),
+        Some(rotation) => moves.iter().map(|mv| mv.rotate(rotation)).collect(),
+    }
+}
+
+/// How many edges are misoriented for an axis.
+pub fn bad_edges(cube: &CubieCube, axis: usize) -> usize {
+    view(cube, EO_ROTATIONS[axis]).eo.iter().filter(|&&o| o != 0).count()
+}
+
+/// How many corners are twisted away from the faces of an axis.
+pub fn bad_corners(cube: &CubieCube, axis: usize) -> usize {
+    view(cube, DR_ROTATIONS[axis]).co.iter().filter(|&&o| o != 0).count()
+}
+
+pub fn is_eo(cube: &CubieCube, axis: usize) -> bool {
+    bad_edges(cube, axis) == 0
+}
+
+/// Whether the cube is in domino reduction on an axis: solvable with half
+/// turns of the four faces around the axis and any turns of the other two.
+pub fn is_dr(cube: &CubieCube, axis: usize) -> bool {
+    let cube = view(cube, DR_ROTATIONS[axis]);
+    twist(&cube) == 0 && flip(&cube) == 0 && slice(&cube) == 0
+}
+
+/// Whether the cube can be solved with half turns only.
+pub fn is_htr(cube: &CubieCube) -> bool {
+    is_dr(cube, 1) && ms_split(cube) == ms_split(&CubieCube::SOLVED) && htr_tables().corners[corner_perm(cube)]
+}
+
+/// Which of the U and D layer edge slots hold M slice edges.
+fn ms_split(cube: &CubieCube) -> usize {
+    let mut out = 0;
+    let mut seen = 0;
+    for (j, e) in cube.ep[..8].iter().enumerate() {
+        if M_EDGES.contains(e) {
+            seen += 1;
+            out += choose(j, seen);
+        }
+    }
+    out
+}
+
+/// Put the M slice edges in the slots of a split and the S slice edges in
+/// the others, with the E slice edges in their slice.
+fn set_ms_split(cube: &mut CubieCube, mut split: usize) {
+    let (mut m, mut s) = (0, 0);
+    for j in (0..8).rev() {
+        let left = 4 - m;
+        if left > 0 && split >= choose(j, left) {
+            split -= choose(j, left);
+            cube.ep[j] = M_EDGES[3 - m];
+            m += 1;
+        } else {
+            cube.ep[j] = [0, 2, 4, 6][s];
+            s += 1;
+        }
+    }
+    for j in 8..12 {
+        cube.ep[j] = j as u8;
+    }
+}
+
+struct HtrTables {
+    split: Vec<Vec<u16>>,
+    /// Which corner permutations half turns reach.
+    corners: Vec<bool>,
+    /// Distance to HTR by corner permutation and split, in phase 2 moves.
+    distance: Vec<u8>,
+}
+
+fn htr_tables() -> &'static HtrTables {
+    static TABLES: OnceLock<HtrTables> = OnceLock::new();
+    TABLES.get_or_init(|| {
+        let phase2: Vec<CubieCube> = PHASE2_MOVES.iter().map(|&mv| CubieCube::from_move(mv)).collect();
+        let corner_perm = &two_phase::tables().corner_perm;
+        let split = move_table(N_SPLIT, &phase2, set_ms_split, ms_split);
+        let mut corners = vec![false; N_PERM_8];
+        corners[0] = true;
+        let mut frontier = vec![0];
+        while let Some(cp) = frontier.pop() {
+            for (m, mv) in PHASE2_MOVES.iter().enumerate() {
+                let next = corner_perm[cp][m] as usize;
+                if mv.amount() == 2 && !corners[next] {
+                    corners[next] = true;
+                    frontier.push(next);
+                }
+            }
+        }
+        let home = ms_split(&CubieCube::SOLVED);
+        let goals = (0..N_PERM_8).filter(|&cp| corners[cp]).map(|cp| cp * N_SPLIT + home);
+        let distance = pruning_table_to(corner_perm, &split, goals);
+        HtrTables { split, corners, distance }
+    })
+}
+
+/// Every sequence up to `max_length` of `moves` that takes coordinates
+/// `start` to a goal, shortest first, without passing through a goal. A
+/// distance of zero means a goal; other distances must not overestimate.
+fn solve_step<C: Copy>(
+    start: C,
+    moves: &[Move],
+    next: &dyn Fn(C, usize) -> C,
+    distance: &dyn Fn(C) -> usize,
+    max_length: usize,
+    max_results: usize,
+) -> Vec<Vec<Move>> {
+    let mut search = StepSearch { moves, next, distance, max_results, path: vec![], found: vec![] };
+    for depth in 0..=max_length {
+        search.search(start, depth);
+        if search.found.len() >= max_results {
+            break;
+        }
+    }
+    search.found
+}
+
+struct StepSearch<'a, C> {
+    moves: &'a [Move],
+    next: &'a dyn Fn(C, usize) -> C,
+    distance: &'a dyn Fn(C) -> usize,
+    max_results: usize,
+    path: Vec<Move>,
+    found: Vec<Vec<Move>>,
+}
+
+impl<C: Copy> StepSearch<'_, C> {
+    fn search(&mut self, coord: C, depth: usize) {
+        let distance = (self.distance)(coord);
+        if distance == 0 {
+            if depth == 0 && self.found.len() < self.max_results {
+                self.found.push(self.path.clone());
+            }
+            return;
+        }
+        if distance > depth || self.found.len() >= self.max_results {
+            return;
+        }
+        for (m, &mv) in self.moves.iter().enumerate() {
+            if !allowed_after(self.path.last().copied(), mv) {
+                continue;
+            }
+            self.path.push(mv);
+            self.search((self.next)(coord, m), depth - 1);
+            self.path.pop();
+        }
+    }
+}
+
+/// Sequences of up to `max_length` moves orienting the edges on an axis,
+/// shortest first.
+pub fn solve_eo(cube: &CubieCube, axis: usize, max_length: usize, max_results: usize) -> Vec<Vec<Move>> {
+    static DISTANCE: OnceLock<Vec<u8>> = OnceLock::new();
+    let flips = &two_phase::tables().flip;
+    let distance = DISTANCE.get_or_init(|| pruning_table(flips, &[vec![0; Move::FACE_TURNS.len()]]));
+    let rotation = EO_ROTATIONS[axis];
+    let found = solve_step(
+        flip(&view(cube, rotation)),
+        &Move::FACE_TURNS,
+        &|flip, m| flips[flip][m] as usize,
+        &|flip| distance[flip] as usize,
+        max_length,
+        max_results,
+    );
+    found.iter().map(|moves| unview(moves, rotation)).collect()
+}
+
+/// Sequences of up to `max_length` moves reaching domino reduction on an
+/// axis, shortest first. Edges already oriented on another axis stay
+/// oriented, as when going from EO to DR.
+pub fn solve_dr(cube: &CubieCube, axis: usize, max_length: usize, max_results: usize) -> Vec<Vec<Move>> {
+    let t = two_phase::tables();
+    let rotation = DR_ROTATIONS[axis];
+    let kept_eo = (0..3).find(|&a| a != axis && is_eo(cube, a));
+    // Quarter turns of the faces on the axis of the EO would break it.
+    let moves: Vec<Move> = Move::FACE_TURNS.into_iter()
+        .filter(|mv| {
+            let mv = rotation.map_or(*mv, |rotation| mv.rotate(rotation));
+            kept_eo.is_none_or(|a| mv.axis() != a || mv.amount() == 2)
+        })
+        .collect();
+    let view = view(cube, rotation);
+    let found = solve_step(
+        (twist(&view), flip(&view), slice(&view)),
+        &moves,
+        &|(twist, flip, slice), m| {
+            let m = moves[m] as usize;
+            (t.twist[twist][m] as usize, t.flip[flip][m] as usize, t.slice[slice][m] as usize)
+        },
+        &|(twist, flip, slice)| {
+            t.twist_slice[twist * N_SLICE + slice].max(t.flip_slice[flip * N_SLICE + slice]) as usize
+        },
+        max_length,
+        max_results,
+    );
+    found.iter().map(|moves| unview(moves, rotation)).collect()
+}
+
+/// Sequences of up to `max_length` moves, keeping the domino reduction the
+/// cube is in, that reach half-turn reduction, shortest first.
+pub fn solve_htr(cube: &CubieCube, max_length: usize, max_results: usize) -> Result<Vec<Vec<Move>>, String> {
+    let axis = (0..3).find(|&axis| is_dr(cube, axis)).ok_or("The cube isn't in domino reduction")?;
+    let rotation = DR_ROTATIONS[axis];
+    let view = view(cube, rotation);
+    let (corner_perms, t) = (&two_phase::tables().corner_perm, htr_tables());
+    let found = solve_step(
+        (corner_perm(&view), ms_split(&view)),
+        &PHASE2_MOVES,
+        &|(cp, split), m| (corner_perms[cp][m] as usize, t.split[split][m] as usize),
+        &|(cp, split)| t.distance[cp * N_SPLIT + split] as usize,
+        max_length,
+        max_results,
+    );
+    Ok(found.iter().map(|moves| unview(moves, rotation)).collect())
+}
+
+/// How far a cube is from each reduction.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Analysis {
+    /// Misoriented edges on each axis.
+    pub bad_edges: [usize; 3],
+    /// Twisted corners on each axis.
+    pub bad_corners: [usize; 3],
+    /// Domino reduction on each axis.
+    pub dr: [bool; 3],
+    pub htr: bool,
+}
+
+impl Analysis {
+    pub fn new(cube: &CubieCube) -> Analysis {
+        Analysis {
+            bad_edges: std::array::from_fn(|axis| bad_edges(cube, axis)),
+            bad_corners: std::array::from_fn(|axis| bad_corners(cube, axis)),
+            dr: std::array::from_fn(|axis| is_dr(cube, axis)),
+            htr: is_htr(cube),
+        }
+    }
+}
+
+impl fmt::Display for Analysis {
+    /// One line for each of bad edges, bad corners, DR and HTR, such as
+    /// `Bad edges: R/L 6, U/D 4, F/B 0`.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let counts = |counts: &[usize; 3]| {
+            (0..3).map(|axis| format!("{} {}", AXIS_NAMES[axis], counts[axis])).collect::<Vec<_>>().join(", ")
+        };
+        writeln!(f, "Bad edges: {}", counts(&self.bad_edges))?;
+        writeln!(f, "Bad corners: {}", counts(&self.bad_corners))?;
+        let dr: Vec<&str> = (0..3).filter(|&axis| self.dr[axis]).map(|axis| AXIS_NAMES[axis]).collect();
+        writeln!(f, "DR: {}", if dr.is_empty() { "none".to_string() } else { dr.join(", ") })?;
+        writeln!(f, "HTR: {}", if self.htr { "yes" } else { "no" })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scramble::{random_cube, Rng};
+
+    #[test]
+    fn steps_reach_their_goals() {
+        let mut rng = Rng::new(3);
+        for _ in 0..5 {
+            let mut cube = random_cube(&mut rng);
+            for axis in 0..3 {
+                for moves in solve_eo(&cube, axis, STEP_LENGTHS[0], 3) {
+                    let mut done = cube;
+                    done.apply_all(moves);
+                    assert!(is_eo(&done, axis));
+                }
+            }
+            let eo = solve_eo(&cube, 2, STEP_LENGTHS[0], 1).remove(0);
+            cube.apply_all(eo);
+            let dr = solve_dr(&cube, 1, STEP_LENGTHS[1], 1).remove(0);
+            cube.apply_all(dr);
+            assert!(is_dr(&cube, 1));
+            let htr = solve_htr(&cube, STEP_LENGTHS[2], 1).unwrap().remove(0);
+            cube.apply_all(htr);
+            assert!(is_htr(&cube));
+        }
+    }
+
+    #[test]
+    fn analysis_of_solved() {
+        let analysis = Analysis::new(&CubieCube::SOLVED);
+        assert_eq!(analysis.bad_edges, [0; 3]);
+        assert_eq!(analysis.bad_corners, [0; 3]);
+        assert_eq!(analysis.dr, [true; 3]);
+        assert!(analysis.htr);
+        let mut cube = CubieCube::SOLVED;
+        cube.apply_all([Move::R, Move::U]);
+        assert!(solve_htr(&cube, 10, 1).is_err());
+    }
+}
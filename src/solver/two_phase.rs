@@ -0,0 +1,183 @@
+//! Kociemba's two-phase algorithm: first reach the subgroup
+//! `<U, D, R2, L2, F2, B2>`, where all corners and edges are oriented and the
+//! E slice edges are in the E slice, then solve within it.
+//!
+//! Solutions are short (usually around 20 moves) but not necessarily optimal.
+
+use std::sync::OnceLock;
+
+use crate::rubik::{CubieCube, Move};
+
+use super::coord::*;
+
+/// The moves that stay within the phase 2 subgroup.
+pub const PHASE2_MOVES: [Move; 10] = [
+    Move::U, Move::UP, Move::U2,
+    Move::D, Move::DP, Move::D2,
+    Move::R2, Move::L2, Move::F2, Move::B2,
+];
+
+pub(super) struct Tables {
+    pub(super) twist: Vec<Vec<u16>>,
+    pub(super) flip: Vec<Vec<u16>>,
+    pub(super) slice: Vec<Vec<u16>>,
+    pub(super) twist_slice: Vec<u8>,
+    pub(super) flip_slice: Vec<u8>,
+    /// Indexed by moves of [`PHASE2_MOVES`], as are the other phase 2 tables.
+    pub(super) corner_perm: Vec<Vec<u16>>,
+    ud_edge_perm: Vec<Vec<u16>>,
+    slice_perm: Vec<Vec<u16>>,
+    corner_slice: Vec<u8>,
+    edge_slice: Vec<u8>,
+}
+
+pub(super) fn tables() -> &'static Tables {
+    static TABLES: OnceLock<Tables> = OnceLock::new();
+    TABLES.get_or_init(|| {
+        let moves: Vec<CubieCube> = Move::FACE_TURNS.iter().map(|&mv| CubieCube::from_move(mv)).collect();
+        let phase2: Vec<CubieCube> = PHASE2_MOVES.iter().map(|&mv| CubieCube::from_move(mv)).collect();
+        let twist = move_table(N_TWIST, &moves, set_twist, twist);
+        let flip = move_table(N_FLIP, &moves, set_flip, flip);
+        let slice = move_table(N_SLICE, &moves, set_slice, slice);
+        let corner_perm = move_table(N_PERM_8, &phase2, set_corner_perm, corner_perm);
+        let ud_edge_perm = move_table(N_PERM_8, &phase2, set_ud_edge_perm, ud_edge_perm);
+        let slice_perm = move_table(N_PERM_4, &phase2, set_slice_perm, slice_perm);
+        Tables {
+            twist_slice: pruning_table(&twist, &slice),
+            flip_slice: pruning_table(&flip, &slice),
+            corner_slice: pruning_table(&corner_perm, &slice_perm),
+            edge_slice: pruning_table(&ud_edge_perm, &slice_perm),
+            twist,
+            flip,
+            slice,
+            corner_perm,
+            ud_edge_perm,
+            slice_perm,
+        }
+    })
+}
+
+/// Whether `next` may follow `last` in a search: never turn the same face
+/// twice in a row, and turn opposite faces in a fixed order.
+pub(crate) fn allowed_after(last: Option<Move>, next: Move) -> bool {
+    match last {
+        None => true,
+        Some(last) => {
+            next.base() != last.base() && !(next.axis() == last.axis() && next.base() < last.base())
+        }
+    }
+}
+
+struct Search<'a> {
+    cube: CubieCube,
+    max_length: usize,
+    path: Vec<Move>,
+    tables: &'a Tables,
+}
+
+impl Search<'_> {
+    fn phase1(&mut self, twist: usize, flip: usize, slice: usize, depth: usize) -> bool {
+        if depth == 0 {
+            if twist != 0 || flip != 0 || slice != 0 {
+                return false;
+            }
+            // A phase 1 solution ending in a phase 2 move would have been
+            // found at a shorter depth.
+            if let Some(&last) = self.path.last() {
+                if PHASE2_MOVES.contains(&last) {
+                    return false;
+                }
+            }
+            return self.start_phase2();
+        }
+        let t = self.tables;
+        for mv in Move::FACE_TURNS {
+            if !allowed_after(self.path.last().copied(), mv) {
+                continue;
+            }
+            let m = mv as usize;
+            let (twist, flip, slice) = (t.twist[twist][m] as usize, t.flip[flip][m] as usize, t.slice[slice][m] as usize);
+            let estimate = t.twist_slice[twist * N_SLICE + slice].max(t.flip_slice[flip * N_SLICE + slice]);
+            if estimate as usize >= depth {
+                continue;
+            }
+            self.path.push(mv);
+            if self.phase1(twist, flip, slice, depth - 1) {
+                return true;
+            }
+            self.path.pop();
+        }
+        false
+    }
+
+    fn start_phase2(&mut self) -> bool {
+        let mut cube = self.cube;
+        cube.apply_all(self.path.iter().copied());
+        let (cp, ep, sp) = (corner_perm(&cube), ud_edge_perm(&cube), slice_perm(&cube));
+        let phase1_length = self.path.len();
+        for depth in 0..=self.max_length - phase1_length {
+            if self.phase2(cp, ep, sp, depth) {
+                return true;
+            }
+        }
+        false
+    }
+
+    fn phase2(&mut self, cp: usize, ep: usize, sp: usize, depth: usize) -> bool {
+        if depth == 0 {
+            return cp == 0 && ep == 0 && sp == 0;
+        }
+        let t = self.tables;
+        for (m, &mv) in PHASE2_MOVES.iter().enumerate() {
+            if !allowed_after(self.path.last().copied(), mv) {
+                continue;
+            }
+            let (cp, ep, sp) = (t.corner_perm[cp][m] as usize, t.ud_edge_perm[ep][m] as usize, t.slice_perm[sp][m] as usize);
+            let estimate = t.corner_slice[cp * N_PERM_4 + sp].max(t.edge_slice[ep * N_PERM_4 + sp]);
+            if estimate as usize >= depth {
+                continue;
+            }
+            self.path.push(mv);
+            if self.phase2(cp, ep, sp, depth - 1) {
+                return true;
+            }
+            self.path.pop();
+        }
+        false
+    }
+}
+
+/// Find a sequence of at most `max_length` moves solving the cube, or
+/// `None` if there isn't one that the two-phase search can find.
+///
+/// The first call builds the move and pruning tables, which takes a moment.
+pub fn solve(cube: &CubieCube, max_length: usize) -> Option<Vec<Move>> {
+    let tables = tables();
+    let mut search = Search { cube: *cube, max_length, path: vec![], tables };
+    let (twist, flip, slice) = (twist(cube), flip(cube), slice(cube));
+    for depth in 0..=max_length {
+        if search.phase1(twist, flip, slice, depth) {
+            return Some(search.path);
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scramble::{random_cube, Rng};
+
+    #[test]
+    fn solutions_solve() {
+        let mut rng = Rng::new(2);
+        for _ in 0..20 {
+            let cube = random_cube(&mut rng);
+            let solution = solve(&cube, 22).expect("Random states should have a 22 move solution");
+            let mut solved = cube;
+            solved.apply_all(solution.iter().copied());
+            assert!(solved.is_solved());
+        }
+        assert_eq!(solve(&CubieCube::SOLVED, 22), Some(vec![]));
+    }
+}
@@ -0,0 +1,227 @@
+//! Drilling cases of the algorithm library: the trainer sets up a random
+//! case from a chosen subset, checks whether the moves made solve it, and
+//! keeps times and recognition errors for every case.
+
+use std::collections::HashMap;
+
+use crate::library::{self, Case, CaseSet};
+use crate::rubik::{CubeState, Move};
+use crate::scramble::Rng;
+
+/// A case set up for solving.
+#[derive(Clone, Debug)]
+pub struct Drill {
+    pub case: &'static Case,
+    /// Moves from a solved cube to the case: the inverse of its algorithm,
+    /// then a random AUF and a random `y` rotation the solver has to undo.
+    pub setup: Vec<Move>,
+    pub state: CubeState,
+    /// When the case was set up, in milliseconds.
+    pub start: f64,
+    /// Whether a wrong case was guessed.
+    misrecognized: bool,
+}
+
+/// What the trainer remembers about a case.
+#[derive(Clone, Default, Debug)]
+pub struct CaseStats {
+    /// How many times the case was set up.
+    pub attempts: u32,
+    /// Solve times, in milliseconds.
+    pub times: Vec<f64>,
+    /// How many times the case was taken for another one, by a wrong guess
+    /// or by moves that left the cube in another case of the set.
+    pub recognition_errors: u32,
+}
+
+impl CaseStats {
+    pub fn best(&self) -> Option<f64> {
+        self.times.iter().copied().reduce(f64::min)
+    }
+
+    pub fn mean(&self) -> Option<f64> {
+        if self.times.is_empty() {
+            None
+        } else {
+            Some(self.times.iter().sum::<f64>() / self.times.len() as f64)
+        }
+    }
+}
+
+/// The result of checking the cube after the solver's moves.
+#[derive(Clone, Copy, Debug)]
+pub enum Outcome {
+    /// The case was solved in this many milliseconds.
+    Solved(f64),
+    /// The case is not solved yet. If the cube is in another case of the
+    /// set, the solver most likely recognized the case wrong.
+    Unsolved(Option<&'static Case>),
+}
+
+pub struct Trainer {
+    set: CaseSet,
+    cases: Vec<&'static Case>,
+    rng: Rng,
+    drill: Option<Drill>,
+    stats: HashMap<&'static str, CaseStats>,
+}
+
+impl Trainer {
+    /// A trainer for some cases of a set, by name or nickname, or for all of
+    /// them if no names are given.
+    pub fn new(set: CaseSet, names: &[&str], rng: Rng) -> Result<Trainer, String> {
+        let cases = if names.is_empty() {
+            set.cases().iter().collect()
+        } else {
+            names.iter()
+                .map(|&name| set.case(name).ok_or(format!("Unknown {} case: {}", set.name(), name)))
+                .collect::<Result<_, _>>()?
+        };
+        Ok(Trainer { set, cases, rng, drill: None, stats: HashMap::new() })
+    }
+
+    pub fn set(&self) -> CaseSet {
+        self.set
+    }
+
+    pub fn cases(&self) -> &[&'static Case] {
+        &self.cases
+    }
+
+    /// The case being drilled, if any.
+    pub fn drill(&self) -> Option<&Drill> {
+        self.drill.as_ref()
+    }
+
+    /// Set up a random case, replacing the current one. `time` is the
+    /// current time in milliseconds.
+    pub fn next(&mut self, time: f64) -> &Drill {
+        let case = self.cases[self.rng.below(self.cases.len())];
+        let mut setup = case.setup();
+        setup.extend(Move::from_face(1, self.rng.below(4) as u8));
+        setup.extend(Move::from_base(Move::Y.base(), self.rng.below(4) as u8));
+        let mut state = CubeState::solved();
+        state.apply_all(setup.iter().copied());
+        self.stats.entry(case.name()).or_default().attempts += 1;
+        self.drill.insert(Drill { case, setup, state, start: time, misrecognized: false })
+    }
+
+    /// Name the case being drilled. Returns whether the guess was right; a
+    /// wrong guess counts as a recognition error, once per drill.
+    pub fn guess(&mut self, name: &str) -> Result<bool, String> {
+        let drill = self.drill.as_mut().ok_or("No case is set up")?;
+        let guessed = self.set.case(name).ok_or(format!("Unknown {} case: {}", self.set.name(), name))?;
+        let right = std::ptr::eq(guessed, drill.case);
+        if !right && !drill.misrecognized {
+            drill.misrecognized = true;
+            self.stats.entry(drill.case.name()).or_default().recognition_errors += 1;
+        }
+        Ok(right)
+    }
+
+    /// Check the cube after the solver's moves, given the state it is in now
+    /// and the current time in milliseconds. A solved case records its time
+    /// and ends the drill.
+    pub fn check(&mut self, state: &CubeState, time: f64) -> Result<Outcome, String> {
+        let drill = self.drill.as_mut().ok_or("No case is set up")?;
+        let stats = self.stats.entry(drill.case.name()).or_default();
+        if library::is_step_solved(self.set, state) {
+            let elapsed = time - drill.start;
+            stats.times.push(elapsed);
+            self.drill = None;
+            return Ok(Outcome::Solved(elapsed));
+        }
+        let other = library::identify(self.set, state)
+            .map(|recognized| recognized.case)
+            .filter(|&case| !std::ptr::eq(case, drill.case));
+        if other.is_some() && !drill.misrecognized {
+            drill.misrecognized = true;
+            stats.recognition_errors += 1;
+        }
+        Ok(Outcome::Unsolved(other))
+    }
+
+    /// What the trainer remembers about a case, by name.
+    pub fn stats(&self, name: &str) -> Option<&CaseStats> {
+        self.stats.get(name)
+    }
+
+    /// Every case drilled so far, with its statistics, in library order.
+    pub fn all_stats(&self) -> Vec<(&'static Case, &CaseStats)> {
+        self.set.cases().iter()
+            .filter_map(|case| self.stats.get(case.name()).map(|stats| (case, stats)))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The moves solving a drill: undoing the `y` and the AUF, then the
+    /// case's algorithm.
+    fn solution(drill: &Drill) -> Vec<Move> {
+        let tail = &drill.setup[drill.case.setup().len()..];
+        [Move::invert_sequence(tail), drill.case.algorithm()].concat()
+    }
+
+    #[test]
+    fn setups_reach_the_case() {
+        for set in CaseSet::ALL {
+            let mut trainer = Trainer::new(set, &[], Rng::new(7)).unwrap();
+            for _ in 0..40 {
+                let drill = trainer.next(0.0).clone();
+                assert!(drill.setup.starts_with(&drill.case.setup()));
+                let tail = &drill.setup[drill.case.setup().len()..];
+                assert!(tail.len() <= 2, "{:?}", tail);
+                assert!(tail.iter().all(|&mv| mv.base() == Move::U.base() || mv.base() == Move::Y.base()));
+                let recognized = library::identify(set, &drill.state).unwrap();
+                assert_eq!(recognized.case.name(), drill.case.name());
+                let mut state = drill.state.clone();
+                state.apply_all(solution(&drill));
+                assert!(library::is_step_solved(set, &state), "{}", drill.case.name());
+            }
+        }
+    }
+
+    #[test]
+    fn guesses() {
+        let mut trainer = Trainer::new(CaseSet::Pll, &["T"], Rng::new(1)).unwrap();
+        assert!(trainer.guess("T").is_err());
+        trainer.next(0.0);
+        assert_eq!(trainer.guess("Y"), Ok(false));
+        assert_eq!(trainer.guess("Z"), Ok(false));
+        assert_eq!(trainer.guess("T"), Ok(true));
+        assert!(trainer.guess("Q").is_err());
+        assert_eq!(trainer.stats("T").unwrap().recognition_errors, 1);
+        trainer.next(0.0);
+        assert_eq!(trainer.guess("Y"), Ok(false));
+        let stats = trainer.stats("T").unwrap();
+        assert_eq!((stats.attempts, stats.recognition_errors), (2, 2));
+    }
+
+    #[test]
+    fn checks() {
+        let mut trainer = Trainer::new(CaseSet::Pll, &["T"], Rng::new(2)).unwrap();
+        assert!(trainer.check(&CubeState::solved(), 0.0).is_err());
+        let drill = trainer.next(1000.0).clone();
+        assert!(matches!(trainer.check(&drill.state, 1500.0), Ok(Outcome::Unsolved(None))));
+        // The Y perm's algorithm instead of the T perm's leaves another case.
+        let mut state = drill.state.clone();
+        let tail = &drill.setup[drill.case.setup().len()..];
+        state.apply_all(Move::invert_sequence(tail));
+        state.apply_all(CaseSet::Pll.case("Y").unwrap().algorithm());
+        for _ in 0..2 {
+            assert!(matches!(trainer.check(&state, 2000.0), Ok(Outcome::Unsolved(Some(_)))));
+        }
+        assert_eq!(trainer.stats("T").unwrap().recognition_errors, 1);
+        let mut state = drill.state.clone();
+        state.apply_all(solution(&drill));
+        assert!(matches!(trainer.check(&state, 4250.0), Ok(Outcome::Solved(time)) if time == 3250.0));
+        assert!(trainer.drill().is_none());
+        let stats = trainer.stats("T").unwrap();
+        assert_eq!(stats.times, [3250.0]);
+        assert_eq!((stats.best(), stats.mean()), (Some(3250.0), Some(3250.0)));
+        assert_eq!(trainer.all_stats().len(), 1);
+    }
+}
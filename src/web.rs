@@ -0,0 +1,218 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use three_d::{Context, RenderTarget};
+use wasm_bindgen::prelude::*;
+use web_sys::HtmlCanvasElement;
+
+use crate::canvas::{self, CanvasError, ContextWatch};
+use crate::clock::{Clock, FixedStep, ManualClock, RealTime};
+use crate::rubik::{self, CubeState, Move};
+use crate::scene::{CubeView, Region, Scene};
+use crate::share::Shared;
+
+mod bld;
+mod cfop;
+mod fmc;
+mod method;
+mod metrics;
+mod options;
+mod share;
+mod trainer;
+
+pub use bld::{blind_solution, JsBlindSolution};
+pub use cfop::{cfop_splits, JsCfopSolve};
+pub use fmc::{
+    find_insertions, fmc_leftover, reduction_analysis, reduction_steps, JsInsertions, JsReductionAnalysis, JsSequences,
+};
+pub use method::{method_step, JsStepSolutions};
+pub use metrics::{move_count, move_counts, JsMetric, JsMoveCounts};
+pub use options::{CanvasOptions, ClockOption, JsCanvasOptions};
+pub use share::{
+    alg_cubing_link, decode_moves, decode_state, encode_moves, encode_state, parse_alg_link, twizzle_link, JsAlgLink,
+};
+pub use trainer::{CaseTrainer, JsCaseStats, JsTrainerCheck};
+
+/// A plain object with the given properties, leaving out undefined ones.
+fn object(entries: &[(&str, JsValue)]) -> js_sys::Object {
+    let object = js_sys::Object::new();
+    for (key, value) in entries {
+        if !value.is_undefined() {
+            js_sys::Reflect::set(&object, &(*key).into(), value).expect("Setting a property of a plain object should work");
+        }
+    }
+    object
+}
+
+/// A canvas showing any number of cubes, each in its own region and with
+/// its own camera and move queue, all sharing one WebGL2 context.
+#[wasm_bindgen]
+pub struct CubeCanvas {
+    /// Replaced when a lost context is restored.
+    context: Rc<RefCell<Context>>,
+    context_watch: ContextWatch,
+    scene: Rc<RefCell<Scene>>,
+    options: CanvasOptions,
+    /// The clock, when it is advanced by hand.
+    manual_clock: Option<ManualClock>,
+}
+
+#[wasm_bindgen]
+impl CubeCanvas {
+    #[wasm_bindgen(constructor)]
+    pub fn new(canvas_element: HtmlCanvasElement, opts: Option<JsCanvasOptions>) -> Result<CubeCanvas, JsValue> {
+        #[cfg(feature = "debug")]
+        console_error_panic_hook::set_once();
+
+        let options = match opts {
+            Some(opts) => CanvasOptions::from_js(&opts).map_err(CanvasError::InvalidOptions)?,
+            None => CanvasOptions::default(),
+        };
+        let window = canvas::Canvas::new(canvas_element, &options.context)?;
+        let context = Rc::new(RefCell::new(window.gl()));
+        let mut scene = Scene::new(&context.borrow());
+        scene.clear_color = options.background;
+        let scene = Rc::new(RefCell::new(scene));
+        let context_watch = window.context_watch();
+        let manual_clock = (options.clock == ClockOption::Manual).then(ManualClock::new);
+        let clock: Box<dyn Clock> = match options.clock {
+            ClockOption::RealTime => Box::new(RealTime::new()),
+            ClockOption::FixedStep(step) => Box::new(FixedStep::new(step)),
+            ClockOption::Manual => Box::new(manual_clock.clone().unwrap()),
+        };
+
+        let render_context = context.clone();
+        let render_scene = scene.clone();
+        window.run(clock, move |input| {
+            let mut scene = render_scene.borrow_mut();
+            if input.context_restored {
+                scene.recreate(&input.context);
+                *render_context.borrow_mut() = input.context.clone();
+            }
+            scene.update(input.time, input.frame_time);
+            let target = RenderTarget::screen(&input.context, input.width, input.height);
+            scene.render(&target, input.width, input.height, input.device_pixel_ratio as f32);
+        });
+
+        Ok(CubeCanvas { context, context_watch, scene, options, manual_clock })
+    }
+
+    /// Add a cube drawn into the given region of the canvas, in CSS pixels
+    /// from its top-left corner. The cube starts in the `state` option, or
+    /// solved, unless a facelet string is given. Returns the id of the new cube.
+    #[wasm_bindgen(js_name = addCube)]
+    pub fn add_cube(&self, x: u32, y: u32, width: u32, height: u32, facelets: Option<String>) -> Result<usize, JsValue> {
+        let cube = self.cube(facelets)?;
+        Ok(self.add_view(CubeView::new(cube, Region::new(x, y, width, height))))
+    }
+
+    /// Add a cube covering the whole canvas, following its size.
+    /// Returns the id of the new cube.
+    #[wasm_bindgen(js_name = addFullCube)]
+    pub fn add_full_cube(&self, facelets: Option<String>) -> Result<usize, JsValue> {
+        let cube = self.cube(facelets)?;
+        Ok(self.add_view(CubeView::full(cube)))
+    }
+
+    /// Whether the browser took the WebGL context away. Drawing pauses until
+    /// it is restored; cubes, cameras and move queues are kept.
+    #[wasm_bindgen(js_name = isContextLost)]
+    pub fn is_context_lost(&self) -> bool {
+        self.context_watch.is_lost()
+    }
+
+    /// Move the time of a canvas made with the `"manual"` clock option
+    /// forward by some milliseconds. The next frame shows the cubes then.
+    #[wasm_bindgen(js_name = advanceClock)]
+    pub fn advance_clock(&self, milliseconds: f64) -> Result<(), JsValue> {
+        let clock = self.manual_clock.as_ref().ok_or("The canvas clock isn't manual")?;
+        clock.advance(milliseconds);
+        Ok(())
+    }
+
+    /// Remove a cube from the canvas. Returns false if there was no cube with that id.
+    #[wasm_bindgen(js_name = removeCube)]
+    pub fn remove_cube(&self, id: usize) -> bool {
+        self.scene.borrow_mut().remove(id).is_some()
+    }
+
+    /// Move a cube to another region of the canvas.
+    #[wasm_bindgen(js_name = setRegion)]
+    pub fn set_region(&self, id: usize, x: u32, y: u32, width: u32, height: u32) -> Result<(), JsValue> {
+        self.with_view(id, |view| view.set_region(Some(Region::new(x, y, width, height))))
+    }
+
+    /// Slowly orbit the camera of a cube.
+    #[wasm_bindgen(js_name = setAutoRotate)]
+    pub fn set_auto_rotate(&self, id: usize, auto_rotate: bool) -> Result<(), JsValue> {
+        self.with_view(id, |view| view.auto_rotate = auto_rotate)
+    }
+
+    /// Replace a cube with one in the given state, dropping its queued moves.
+    #[wasm_bindgen(js_name = setState)]
+    pub fn set_state(&self, id: usize, facelets: String) -> Result<(), JsValue> {
+        let cube = self.cube(Some(facelets))?;
+        self.with_view(id, |view| view.cube = cube)
+    }
+
+    /// The facelet string of a cube, after the moves that finished animating.
+    pub fn state(&self, id: usize) -> Result<String, JsValue> {
+        let scene = self.scene.borrow();
+        let view = scene.view(id).ok_or(format!("No cube with id {}", id))?;
+        Ok(view.cube.state().to_facelet_string())
+    }
+
+    /// Show the position of a share link on a cube and queue its algorithm.
+    /// Reads the `state`, `setup` and `alg` parameters of a query string or
+    /// URL, by default those of the page.
+    #[wasm_bindgen(js_name = loadQuery)]
+    pub fn load_query(&self, id: usize, query: Option<String>) -> Result<(), JsValue> {
+        let query = match query {
+            Some(query) => query,
+            None => web_sys::window().ok_or("No window")?.location().search()?,
+        };
+        let shared = Shared::from_query(&query)?;
+        let mut cube = self.cube(Some(shared.position().to_facelet_string()))?;
+        cube.queue(shared.alg.into_iter());
+        self.with_view(id, |view| view.cube = cube)
+    }
+
+    /// Queue a sequence of moves, such as `"R U R' U'"`, on a cube.
+    pub fn queue(&self, id: usize, moves: &str) -> Result<(), JsValue> {
+        let moves = Move::from_sequence(moves)?;
+        self.with_view(id, |view| view.cube.queue(moves))
+    }
+}
+
+impl CubeCanvas {
+    fn cube(&self, facelets: Option<String>) -> Result<rubik::Cube, JsValue> {
+        let state = match facelets {
+            Some(fstr) => CubeState::from_facelet_str(&fstr)?,
+            None => self.options.state.clone().unwrap_or_default(),
+        };
+        let mut cube = rubik::Cube::from_state(&self.context.borrow(), state, self.options.animation);
+        cube.set_theme(self.options.theme);
+        Ok(cube)
+    }
+
+    fn add_view(&self, mut view: CubeView) -> usize {
+        view.auto_rotate = self.options.auto_rotate.unwrap_or(false);
+        self.scene.borrow_mut().add(view)
+    }
+
+    fn with_view(&self, id: usize, f: impl FnOnce(&mut CubeView)) -> Result<(), JsValue> {
+        let mut scene = self.scene.borrow_mut();
+        let view = scene.view_mut(id).ok_or(format!("No cube with id {}", id))?;
+        f(view);
+        Ok(())
+    }
+}
+
+/// Show a single, slowly rotating cube covering the whole canvas.
+#[wasm_bindgen]
+pub fn bind(canvas_element: HtmlCanvasElement, opts: Option<JsCanvasOptions>) -> Result<CubeCanvas, JsValue> {
+    let canvas = CubeCanvas::new(canvas_element, opts)?;
+    let id = canvas.add_full_cube(None)?;
+    canvas.set_auto_rotate(id, canvas.options.auto_rotate.unwrap_or(true))?;
+    Ok(canvas)
+}
@@ -0,0 +1,58 @@
+use wasm_bindgen::prelude::*;
+
+use super::object;
+use crate::bld::{EdgeMethod, Execution, LetterScheme, SPEFFZ};
+use crate::reconstruction::Reconstruction;
+use crate::rubik::{CubeState, Move};
+
+#[wasm_bindgen(typescript_custom_section)]
+const BLIND_TYPES: &'static str = r#"
+/** A blindfolded solution of a state. */
+interface BlindSolution {
+    /** The memo: lines of letter pairs, cycle breaks in parentheses. */
+    memo: string,
+    parity: boolean,
+    /** One line per target, commented with its letter. */
+    steps: string,
+    /** Every move, to queue on a cube. */
+    moves: string,
+}
+"#;
+
+#[wasm_bindgen]
+extern "C" {
+    #[wasm_bindgen(typescript_type = "BlindSolution")]
+    pub type JsBlindSolution;
+}
+
+/// Memo and execution of a state with Old Pochmann corners and `"op"`
+/// (default) or `"m2"` edges. The letters default to Speffz; custom ones are
+/// 24 letters each, in Speffz order.
+#[wasm_bindgen(js_name = blindSolution)]
+pub fn blind_solution(
+    facelets: &str,
+    edge_method: Option<String>,
+    corner_letters: Option<String>,
+    edge_letters: Option<String>,
+) -> Result<JsBlindSolution, JsValue> {
+    let method = match edge_method {
+        Some(name) => name.parse()?,
+        None => EdgeMethod::OldPochmann,
+    };
+    let scheme = match (corner_letters, edge_letters) {
+        (None, None) => LetterScheme::speffz(),
+        (corners, edges) => LetterScheme::new(
+            corners.as_deref().unwrap_or(SPEFFZ),
+            edges.as_deref().unwrap_or(SPEFFZ),
+        )?,
+    };
+    let execution = Execution::new(&CubeState::from_facelet_str(facelets)?, &scheme, method)?;
+    let memo = execution.memo.to_string();
+    let steps = Reconstruction { steps: execution.steps.clone(), ..Default::default() }.steps_text();
+    Ok(object(&[
+        ("memo", memo.into()),
+        ("parity", execution.memo.parity().into()),
+        ("steps", steps.into()),
+        ("moves", Move::to_sequence(&execution.moves()).into()),
+    ]).unchecked_into())
+}
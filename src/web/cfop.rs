@@ -0,0 +1,73 @@
+use js_sys::Array;
+use wasm_bindgen::prelude::*;
+
+use super::object;
+use crate::cfop::{Cfop, TimedMove};
+use crate::rubik::Move;
+
+#[wasm_bindgen(typescript_custom_section)]
+const CFOP_TYPES: &'static str = r#"
+/** A finished CFOP step. Times are in milliseconds. */
+interface CfopSplit {
+    /** `"Cross"`, `"F2L 1"`, `"F2L 2+3"`, `"OLL"` or `"PLL"`. */
+    step: string,
+    /** Empty for a skipped step. */
+    moves: string,
+    /** Moves in STM. */
+    length: number,
+    time: number,
+    /** The pause before the first move of the step. */
+    recognition: number,
+    tps: number,
+}
+
+/** A solve split into CFOP steps. */
+interface CfopSolve {
+    /** The color letter of the cross, if one was solved. */
+    cross?: string,
+    splits: CfopSplit[],
+    /** Moves after the last finished step. */
+    unfinished: string,
+    finished: boolean,
+    length: number,
+    time: number,
+    tps: number,
+    /** Algorithm text with a commented line for each step. */
+    reconstruction: string,
+}
+"#;
+
+#[wasm_bindgen]
+extern "C" {
+    #[wasm_bindgen(typescript_type = "CfopSolve")]
+    pub type JsCfopSolve;
+}
+
+/// Split the moves of a solve after a scramble into CFOP steps. Moves are
+/// timed in milliseconds from the start of the solve, as in `"R@0 U'@180"`.
+#[wasm_bindgen(js_name = cfopSplits)]
+pub fn cfop_splits(scramble: &str, moves: &str) -> Result<JsCfopSolve, JsValue> {
+    let scramble: Vec<Move> = Move::from_sequence(scramble)?.collect();
+    let cfop = Cfop::new(&scramble, &TimedMove::parse_sequence(moves)?)?;
+    let splits: Array = cfop.splits.iter().map(|split| {
+        object(&[
+            ("step", split.stage.to_string().into()),
+            ("moves", Move::to_sequence(&split.moves()).into()),
+            ("length", split.length().into()),
+            ("time", split.time().into()),
+            ("recognition", split.recognition().into()),
+            ("tps", split.tps().into()),
+        ])
+    }).collect();
+    let unfinished: Vec<Move> = cfop.unfinished.iter().map(|mv| mv.mv).collect();
+    Ok(object(&[
+        ("cross", cfop.cross.map_or(JsValue::UNDEFINED, |color| color.to_char().to_string().into())),
+        ("splits", splits.into()),
+        ("unfinished", Move::to_sequence(&unfinished).into()),
+        ("finished", cfop.is_finished().into()),
+        ("length", cfop.length().into()),
+        ("time", cfop.time().into()),
+        ("tps", cfop.tps().into()),
+        ("reconstruction", cfop.to_reconstruction(&scramble).steps_text().into()),
+    ]).unchecked_into())
+}
@@ -0,0 +1,123 @@
+use js_sys::Array;
+use wasm_bindgen::prelude::*;
+
+use super::object;
+use crate::fmc::{self, Leftover, Niss};
+use crate::rubik::{CubeState, CubieCube, Move};
+use crate::solver::reduction::{self, Analysis, AXIS_NAMES, STEP_LENGTHS};
+
+#[wasm_bindgen(typescript_custom_section)]
+const FMC_TYPES: &'static str = r#"
+/** A solution found by inserting commutators into a skeleton. */
+interface Insertion {
+    /** The whole solution, with cancelling moves merged. */
+    solution: string,
+    length: number,
+    /** How many moves of the skeleton (with the earlier insertions made)
+     * each commutator comes after. */
+    positions: number[],
+    commutators: string[],
+    cancelled: number,
+}
+
+/** How far a state is from EO, DR and HTR. */
+interface ReductionAnalysis {
+    /** Misoriented edges on the R/L, U/D and F/B axes. */
+    badEdges: number[],
+    /** Twisted corners on the R/L, U/D and F/B axes. */
+    badCorners: number[],
+    /** The axes with domino reduction, such as `"U/D"`. */
+    dr: string[],
+    htr: boolean,
+}
+"#;
+
+#[wasm_bindgen]
+extern "C" {
+    #[wasm_bindgen(typescript_type = "Insertion[]")]
+    pub type JsInsertions;
+    #[wasm_bindgen(typescript_type = "ReductionAnalysis")]
+    pub type JsReductionAnalysis;
+    #[wasm_bindgen(typescript_type = "string[]")]
+    pub type JsSequences;
+}
+
+fn parse_moves(moves: &str) -> Result<Vec<Move>, String> {
+    Ok(Move::from_sequence(moves)?.collect())
+}
+
+/// What a partial solution leaves unsolved after a scramble, one line for
+/// corners and one for edges. Moves found on the inverse scramble go in
+/// parentheses, as in `"R U (F' D)"`.
+#[wasm_bindgen(js_name = fmcLeftover)]
+pub fn fmc_leftover(scramble: &str, moves: &str) -> Result<String, JsValue> {
+    let niss: Niss = moves.parse()?;
+    Ok(Leftover::new(&parse_moves(scramble)?, &niss.solution())?.to_string())
+}
+
+/// Ways to finish a skeleton by inserting up to `maxInsertions` (default
+/// 2) commutators, shortest first.
+#[wasm_bindgen(js_name = findInsertions)]
+pub fn find_insertions(
+    scramble: &str,
+    skeleton: &str,
+    max_insertions: Option<usize>,
+    max_results: Option<usize>,
+) -> Result<JsInsertions, JsValue> {
+    let skeleton = skeleton.parse::<Niss>()?.solution();
+    let found = fmc::find_insertions(&parse_moves(scramble)?, &skeleton, max_insertions.unwrap_or(2), max_results.unwrap_or(10))?;
+    let insertions: Array = found.iter().map(|insertion| {
+        object(&[
+            ("solution", Move::to_sequence(&insertion.moves).into()),
+            ("length", insertion.moves.len().into()),
+            ("positions", insertion.positions.iter().map(|&p| JsValue::from(p)).collect::<Array>().into()),
+            ("commutators", insertion.commutators.iter().map(|c| JsValue::from(Move::to_sequence(c))).collect::<Array>().into()),
+            ("cancelled", insertion.cancelled.into()),
+        ])
+    }).collect();
+    Ok(insertions.unchecked_into())
+}
+
+fn cubies(facelets: &str) -> Result<CubieCube, String> {
+    CubieCube::from_state(&CubeState::from_facelet_str(facelets)?)
+}
+
+/// Bad edges and corners on each axis of a facelet string, and whether it
+/// is in domino or half-turn reduction.
+#[wasm_bindgen(js_name = reductionAnalysis)]
+pub fn reduction_analysis(facelets: &str) -> Result<JsReductionAnalysis, JsValue> {
+    let analysis = Analysis::new(&cubies(facelets)?);
+    let numbers = |counts: [usize; 3]| counts.iter().map(|&n| JsValue::from(n)).collect::<Array>();
+    let dr: Array = (0..3).filter(|&axis| analysis.dr[axis]).map(|axis| JsValue::from(AXIS_NAMES[axis])).collect();
+    Ok(object(&[
+        ("badEdges", numbers(analysis.bad_edges).into()),
+        ("badCorners", numbers(analysis.bad_corners).into()),
+        ("dr", dr.into()),
+        ("htr", analysis.htr.into()),
+    ]).unchecked_into())
+}
+
+/// The shortest sequences for a step, `"eo"` or `"dr"` on an axis (`"R/L"`,
+/// `"U/D"` or `"F/B"`), or `"htr"` from domino reduction.
+#[wasm_bindgen(js_name = reductionSteps)]
+pub fn reduction_steps(
+    facelets: &str,
+    step: &str,
+    axis: Option<String>,
+    max_results: Option<usize>,
+) -> Result<JsSequences, JsValue> {
+    let cube = cubies(facelets)?;
+    let max_results = max_results.unwrap_or(10);
+    let axis = || {
+        let axis = axis.as_deref().ok_or(format!("The {} step needs an axis", step))?;
+        AXIS_NAMES.iter().position(|&name| name == axis).ok_or(format!("Unknown axis {}", axis))
+    };
+    let found = match step {
+        "eo" => reduction::solve_eo(&cube, axis()?, STEP_LENGTHS[0], max_results),
+        "dr" => reduction::solve_dr(&cube, axis()?, STEP_LENGTHS[1], max_results),
+        "htr" => reduction::solve_htr(&cube, STEP_LENGTHS[2], max_results)?,
+        _ => return Err(format!("Unknown step {}", step).into()),
+    };
+    let sequences: Array = found.iter().map(|moves| JsValue::from(Move::to_sequence(moves))).collect();
+    Ok(sequences.unchecked_into())
+}
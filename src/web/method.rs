@@ -0,0 +1,54 @@
+use js_sys::Array;
+use wasm_bindgen::prelude::*;
+
+use super::object;
+use crate::rubik::Move;
+use crate::solver::method::{self, Step};
+
+#[wasm_bindgen(typescript_custom_section)]
+const METHOD_TYPES: &'static str = r#"
+/** A way to do a step of Roux, ZZ or Petrus. */
+interface StepSolution {
+    /** How to hold the cube first, such as `"z y"`, or `""`. */
+    rotation: string,
+    moves: string,
+    length: number,
+}
+"#;
+
+#[wasm_bindgen]
+extern "C" {
+    #[wasm_bindgen(typescript_type = "StepSolution[]")]
+    pub type JsStepSolutions;
+}
+
+/// The shortest ways to do a step (`"fb"`, `"sb"`, `"cmll"`, `"lse"`,
+/// `"eoline"`, `"eocross"`, `"2x2x2"` or `"2x2x3"`) after some moves, such
+/// as a scramble and the steps before. The first step of a method is tried
+/// holding the cube every way unless `anyOrientation` is false.
+///
+/// CMLL builds a 44 MB table the first time, which takes some seconds.
+#[wasm_bindgen(js_name = methodStep)]
+pub fn method_step(
+    moves: &str,
+    step: &str,
+    any_orientation: Option<bool>,
+    max_results: Option<usize>,
+) -> Result<JsStepSolutions, JsValue> {
+    let step: Step = step.parse()?;
+    let moves: Vec<Move> = Move::from_sequence(moves)?.collect();
+    let orientations = if step.is_first() && any_orientation.unwrap_or(true) {
+        Move::orientations().to_vec()
+    } else {
+        vec![vec![]]
+    };
+    let found = method::solve_step(&moves, step, &orientations, step.max_length(), max_results.unwrap_or(10))?;
+    let solutions: Array = found.iter().map(|solution| {
+        object(&[
+            ("rotation", Move::to_sequence(&solution.rotation).into()),
+            ("moves", Move::to_sequence(&solution.moves).into()),
+            ("length", solution.moves.len().into()),
+        ])
+    }).collect();
+    Ok(solutions.unchecked_into())
+}
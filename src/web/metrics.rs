@@ -0,0 +1,44 @@
+use wasm_bindgen::prelude::*;
+
+use super::object;
+use crate::algorithm::{Algorithm, Metric};
+
+#[wasm_bindgen(typescript_custom_section)]
+const METRIC_TYPES: &'static str = r#"
+/** Move metrics: half-turn, quarter-turn, slice-turn and execution-turn. */
+type Metric = "htm" | "qtm" | "stm" | "etm";
+
+/** The length of a sequence in every metric. */
+interface MoveCounts {
+    htm: number,
+    qtm: number,
+    stm: number,
+    etm: number,
+}
+"#;
+
+#[wasm_bindgen]
+extern "C" {
+    #[wasm_bindgen(typescript_type = "Metric")]
+    pub type JsMetric;
+    #[wasm_bindgen(typescript_type = "MoveCounts")]
+    pub type JsMoveCounts;
+}
+
+/// The length of a sequence such as `"R U R' U'"` or `"[R, U]"` in a metric.
+#[wasm_bindgen(js_name = moveCount)]
+pub fn move_count(moves: &str, metric: JsMetric) -> Result<usize, JsValue> {
+    let metric: Metric = metric.as_string().ok_or("The metric must be a string")?.parse()?;
+    Ok(moves.parse::<Algorithm>()?.count(metric))
+}
+
+/// The length of a sequence in every metric.
+#[wasm_bindgen(js_name = moveCounts)]
+pub fn move_counts(moves: &str) -> Result<JsMoveCounts, JsValue> {
+    let algorithm: Algorithm = moves.parse()?;
+    let keys = Metric::ALL.map(|metric| metric.name().to_ascii_lowercase());
+    let counts: Vec<(&str, JsValue)> = Metric::ALL.iter().zip(&keys)
+        .map(|(&metric, key)| (key.as_str(), algorithm.count(metric).into()))
+        .collect();
+    Ok(object(&counts).unchecked_into())
+}
@@ -0,0 +1,202 @@
+use js_sys::{Object, Reflect};
+use three_d::Srgba;
+use wasm_bindgen::prelude::*;
+
+use crate::canvas::{ContextOptions, PowerPreference};
+use crate::rubik::{CubeAnimationOptions, CubeState, Theme};
+
+#[wasm_bindgen(typescript_custom_section)]
+const CANVAS_OPTS: &'static str = r#"
+interface CanvasOptions {
+    /** WebGL context attributes, see `HTMLCanvasElement.getContext`. */
+    alpha?: boolean,
+    antialias?: boolean,
+    depth?: boolean,
+    stencil?: boolean,
+    premultipliedAlpha?: boolean,
+    failIfMajorPerformanceCaveat?: boolean,
+    desynchronized?: boolean,
+    powerPreference?: "default" | "high-performance" | "low-power",
+    preserveDrawingBuffer?: boolean,
+
+    /** Color behind the cubes, as `#rrggbb` or `#rrggbbaa`. Transparent by default. */
+    background?: string,
+    /** Slowly orbit the camera of new cubes. Defaults to true for `bind`, false otherwise. */
+    autoRotate?: boolean,
+    /** Facelet string of new cubes added without one. Solved by default. */
+    state?: string,
+    /** Duration of one move in milliseconds. Defaults to 1200. */
+    moveTime?: number,
+    /** Colors of the cubes. Defaults to "classic". */
+    theme?: "classic" | "white" | "bright",
+    /**
+     * What times frames: "realtime" (the default) follows the wall clock,
+     * a number advances that many milliseconds every frame, for
+     * frame-exact recordings, and "manual" only advances on
+     * `CubeCanvas.advanceClock`.
+     */
+    clock?: "realtime" | "manual" | number,
+}
+
+/** Thrown when a canvas can't be set up. */
+interface RustbikError extends Error {
+    name: "RustbikError",
+    code: "webgl2-unsupported" | "missing-extension" | "context" | "invalid-options" | "events",
+    /** The missing extension, for "missing-extension" errors. */
+    extension?: string,
+}
+"#;
+
+#[wasm_bindgen]
+extern "C" {
+    #[wasm_bindgen(typescript_type = "CanvasOptions")]
+    pub type JsCanvasOptions;
+}
+
+/// What times the frames of a canvas.
+#[derive(Clone, Copy, PartialEq, Debug, Default)]
+pub enum ClockOption {
+    #[default]
+    RealTime,
+    /// Milliseconds per frame.
+    FixedStep(f64),
+    Manual,
+}
+
+/// Options of a [`CubeCanvas`](super::CubeCanvas), read from a JS object.
+#[derive(Clone, Debug)]
+pub struct CanvasOptions {
+    pub context: ContextOptions,
+    pub background: Srgba,
+    pub auto_rotate: Option<bool>,
+    pub state: Option<CubeState>,
+    pub animation: CubeAnimationOptions,
+    pub theme: Theme,
+    pub clock: ClockOption,
+}
+
+impl Default for CanvasOptions {
+    fn default() -> Self {
+        CanvasOptions {
+            context: ContextOptions::default(),
+            background: Srgba::new(0, 0, 0, 0),
+            auto_rotate: None,
+            state: None,
+            animation: CubeAnimationOptions::default(),
+            theme: Theme::default(),
+            clock: ClockOption::default(),
+        }
+    }
+}
+
+const KEYS: [&str; 15] = [
+    "alpha",
+    "antialias",
+    "depth",
+    "stencil",
+    "premultipliedAlpha",
+    "failIfMajorPerformanceCaveat",
+    "desynchronized",
+    "powerPreference",
+    "preserveDrawingBuffer",
+    "background",
+    "autoRotate",
+    "state",
+    "moveTime",
+    "theme",
+    "clock",
+];
+
+/// Typed access to the properties of an options object. Properties that are
+/// missing, `null` or `undefined` are `None`.
+struct Reader<'a>(&'a Object);
+
+impl Reader<'_> {
+    fn get(&self, key: &str) -> Option<JsValue> {
+        Reflect::get(self.0, &key.into()).ok().filter(|v| !v.is_null() && !v.is_undefined())
+    }
+
+    fn bool(&self, key: &str) -> Result<Option<bool>, String> {
+        self.get(key)
+            .map(|v| v.as_bool().ok_or(format!("CanvasOptions.{} must be a boolean", key)))
+            .transpose()
+    }
+
+    fn number(&self, key: &str) -> Result<Option<f64>, String> {
+        self.get(key)
+            .map(|v| v.as_f64().ok_or(format!("CanvasOptions.{} must be a number", key)))
+            .transpose()
+    }
+
+    fn string(&self, key: &str) -> Result<Option<String>, String> {
+        self.get(key)
+            .map(|v| v.as_string().ok_or(format!("CanvasOptions.{} must be a string", key)))
+            .transpose()
+    }
+}
+
+impl CanvasOptions {
+    /// Read options from a JS object, checking the type of every property
+    /// and rejecting unknown ones. Missing properties take their default.
+    pub fn from_js(value: &JsValue) -> Result<CanvasOptions, String> {
+        let object = value.dyn_ref::<Object>().ok_or("CanvasOptions must be an object")?;
+        for key in Object::keys(object).iter() {
+            let key = key.as_string().unwrap_or_default();
+            if !KEYS.contains(&key.as_str()) {
+                return Err(format!("Unknown option CanvasOptions.{}", key));
+            }
+        }
+
+        let r = Reader(object);
+        let mut opts = CanvasOptions::default();
+        let context = &mut opts.context;
+        let flags = [
+            ("alpha", &mut context.alpha),
+            ("antialias", &mut context.antialias),
+            ("depth", &mut context.depth),
+            ("stencil", &mut context.stencil),
+            ("premultipliedAlpha", &mut context.premultiplied_alpha),
+            ("failIfMajorPerformanceCaveat", &mut context.fail_if_major_performance_caveat),
+            ("desynchronized", &mut context.desynchronized),
+            ("preserveDrawingBuffer", &mut context.preserve_drawing_buffer),
+        ];
+        for (key, flag) in flags {
+            if let Some(value) = r.bool(key)? {
+                *flag = value;
+            }
+        }
+        if let Some(name) = r.string("powerPreference")? {
+            context.power_preference = PowerPreference::from_name(&name)?;
+        }
+
+        if let Some(color) = r.string("background")? {
+            opts.background = crate::parse_rgba(&color)?;
+        }
+        opts.auto_rotate = r.bool("autoRotate")?;
+        if let Some(facelets) = r.string("state")? {
+            opts.state = Some(CubeState::from_facelet_str(&facelets)?);
+        }
+        if let Some(move_time) = r.number("moveTime")? {
+            if !(move_time.is_finite() && move_time > 0.0) {
+                return Err("CanvasOptions.moveTime must be a positive number".to_string());
+            }
+            opts.animation.move_time = move_time as f32;
+        }
+        if let Some(name) = r.string("theme")? {
+            opts.theme = Theme::from_name(&name)?;
+        }
+        if let Some(clock) = r.get("clock") {
+            opts.clock = match (clock.as_string().as_deref(), clock.as_f64()) {
+                (Some("realtime"), _) => ClockOption::RealTime,
+                (Some("manual"), _) => ClockOption::Manual,
+                (_, Some(step)) if step.is_finite() && step > 0.0 => ClockOption::FixedStep(step),
+                _ => {
+                    return Err(
+                        "CanvasOptions.clock must be \"realtime\", \"manual\" or a positive number".to_string(),
+                    )
+                }
+            };
+        }
+        Ok(opts)
+    }
+}
@@ -0,0 +1,81 @@
+use wasm_bindgen::prelude::*;
+
+use super::object;
+use crate::reconstruction::Reconstruction;
+use crate::rubik::{CubeState, Move};
+use crate::share;
+
+#[wasm_bindgen(typescript_custom_section)]
+const ALG_LINK_TYPES: &'static str = r#"
+/** The contents of an alg.cubing.net or Twizzle link. */
+interface AlgLink {
+    title?: string,
+    /** Moves from solved to the start of the algorithm. */
+    setup: string,
+    /** One line per step, with `//` comments. */
+    alg: string,
+}
+"#;
+
+#[wasm_bindgen]
+extern "C" {
+    #[wasm_bindgen(typescript_type = "AlgLink")]
+    pub type JsAlgLink;
+}
+
+/// Compact, URL-safe text for a facelet string.
+#[wasm_bindgen(js_name = encodeState)]
+pub fn encode_state(facelets: &str) -> Result<String, JsValue> {
+    Ok(share::encode_state(&CubeState::from_facelet_str(facelets)?)?)
+}
+
+/// The facelet string of a state encoded with `encodeState`.
+#[wasm_bindgen(js_name = decodeState)]
+pub fn decode_state(text: &str) -> Result<String, JsValue> {
+    Ok(share::decode_state(text)?.to_facelet_string())
+}
+
+/// Compact, URL-safe text for a sequence such as `"R U R' U'"`.
+#[wasm_bindgen(js_name = encodeMoves)]
+pub fn encode_moves(moves: &str) -> Result<String, JsValue> {
+    let moves: Vec<Move> = Move::from_sequence(moves)?.collect();
+    Ok(share::encode_moves(&moves))
+}
+
+/// The sequence encoded with `encodeMoves`, in standard notation.
+#[wasm_bindgen(js_name = decodeMoves)]
+pub fn decode_moves(text: &str) -> Result<String, JsValue> {
+    Ok(Move::to_sequence(&share::decode_moves(text)?))
+}
+
+fn reconstruction(setup: &str, alg: &str, title: Option<String>) -> Result<Reconstruction, String> {
+    Ok(Reconstruction {
+        title,
+        setup: Move::from_sequence(setup)?.collect(),
+        steps: Reconstruction::parse_steps(alg)?,
+    })
+}
+
+/// An alg.cubing.net link showing an algorithm or reconstruction, which can
+/// have several lines and `//` comments.
+#[wasm_bindgen(js_name = algCubingLink)]
+pub fn alg_cubing_link(setup: &str, alg: &str, title: Option<String>) -> Result<String, JsValue> {
+    Ok(reconstruction(setup, alg, title)?.to_alg_cubing_url())
+}
+
+/// A Twizzle editor link showing an algorithm or reconstruction.
+#[wasm_bindgen(js_name = twizzleLink)]
+pub fn twizzle_link(setup: &str, alg: &str) -> Result<String, JsValue> {
+    Ok(reconstruction(setup, alg, None)?.to_twizzle_url())
+}
+
+/// Read an alg.cubing.net or Twizzle link, in standard notation.
+#[wasm_bindgen(js_name = parseAlgLink)]
+pub fn parse_alg_link(url: &str) -> Result<JsAlgLink, JsValue> {
+    let reconstruction = Reconstruction::from_url(url)?;
+    Ok(object(&[
+        ("title", reconstruction.title.clone().into()),
+        ("setup", Move::to_sequence(&reconstruction.setup).into()),
+        ("alg", reconstruction.steps_text().into()),
+    ]).unchecked_into())
+}
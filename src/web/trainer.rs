@@ -0,0 +1,113 @@
+use js_sys::Array;
+use wasm_bindgen::prelude::*;
+
+use super::{object, CubeCanvas};
+use crate::canvas::performance;
+use crate::library::CaseSet;
+use crate::rubik::{CubeState, Move};
+use crate::scramble::Rng;
+use crate::trainer::{Outcome, Trainer};
+
+#[wasm_bindgen(typescript_custom_section)]
+const TRAINER_TYPES: &'static str = r#"
+/** The result of `CaseTrainer.check`. */
+interface TrainerCheck {
+    solved: boolean,
+    /** Solve time in milliseconds, if solved. */
+    time?: number,
+    /** The case the cube is in now, if it is another case of the set. */
+    otherCase?: string,
+}
+
+/** What a `CaseTrainer` remembers about a case. */
+interface CaseStats {
+    name: string,
+    attempts: number,
+    /** Solve times in milliseconds. */
+    times: number[],
+    best?: number,
+    mean?: number,
+    recognitionErrors: number,
+}
+"#;
+
+#[wasm_bindgen]
+extern "C" {
+    #[wasm_bindgen(typescript_type = "TrainerCheck")]
+    pub type JsTrainerCheck;
+    #[wasm_bindgen(typescript_type = "CaseStats[]")]
+    pub type JsCaseStats;
+}
+
+/// Drills random cases of an algorithm set on a cube of a canvas.
+#[wasm_bindgen]
+pub struct CaseTrainer {
+    trainer: Trainer,
+}
+
+#[wasm_bindgen]
+impl CaseTrainer {
+    /// A trainer for `"f2l"`, `"oll"` or `"pll"` cases, limited to some cases
+    /// by name (such as `"OLL 27"`, `"Sune"` or `"T"`) if given.
+    #[wasm_bindgen(constructor)]
+    pub fn new(set: &str, cases: Option<Vec<String>>) -> Result<CaseTrainer, JsValue> {
+        let set = CaseSet::ALL.into_iter()
+            .find(|s| s.name().eq_ignore_ascii_case(set))
+            .ok_or(format!("Unknown case set {}", set))?;
+        let names = cases.unwrap_or_default();
+        let names: Vec<&str> = names.iter().map(String::as_str).collect();
+        Ok(CaseTrainer { trainer: Trainer::new(set, &names, Rng::from_time())? })
+    }
+
+    /// Set up a random case, with a random AUF and `y` rotation, on a cube
+    /// of a canvas. The time starts now.
+    pub fn next(&mut self, canvas: &CubeCanvas, id: usize) -> Result<(), JsValue> {
+        let drill = self.trainer.next(performance().now());
+        canvas.set_state(id, drill.state.to_facelet_string())
+    }
+
+    /// The name of the case set up, to reveal after solving.
+    #[wasm_bindgen(js_name = caseName)]
+    pub fn case_name(&self) -> Option<String> {
+        self.trainer.drill().map(|drill| drill.case.name().to_string())
+    }
+
+    /// The moves setting up the case from a solved cube.
+    pub fn setup(&self) -> Option<String> {
+        self.trainer.drill().map(|drill| Move::to_sequence(&drill.setup))
+    }
+
+    /// Name the case set up. A wrong guess counts as a recognition error.
+    pub fn guess(&mut self, name: &str) -> Result<bool, JsValue> {
+        Ok(self.trainer.guess(name)?)
+    }
+
+    /// Check whether the moves made on a cube solved the case. A solved case
+    /// records its time and has to be followed by `next`.
+    pub fn check(&mut self, canvas: &CubeCanvas, id: usize) -> Result<JsTrainerCheck, JsValue> {
+        let state = CubeState::from_facelet_str(&canvas.state(id)?)?;
+        let result = match self.trainer.check(&state, performance().now())? {
+            Outcome::Solved(time) => object(&[("solved", true.into()), ("time", time.into())]),
+            Outcome::Unsolved(other) => object(&[
+                ("solved", false.into()),
+                ("otherCase", other.map(|case| case.name()).into()),
+            ]),
+        };
+        Ok(result.unchecked_into())
+    }
+
+    /// Statistics of every case drilled so far.
+    pub fn stats(&self) -> JsCaseStats {
+        let stats: Array = self.trainer.all_stats().into_iter().map(|(case, stats)| {
+            object(&[
+                ("name", case.name().into()),
+                ("attempts", stats.attempts.into()),
+                ("times", stats.times.iter().map(|&t| JsValue::from(t)).collect::<Array>().into()),
+                ("best", stats.best().into()),
+                ("mean", stats.mean().into()),
+                ("recognitionErrors", stats.recognition_errors.into()),
+            ])
+        }).collect();
+        stats.unchecked_into()
+    }
+}